@@ -17,7 +17,7 @@ fn main() {
 	// Send F1 key to chrome
 	
 	// focus before send key
-	win.focus(); 
+	win.focus().expect("The window is gone.");
 	// press key
 	win.send_key(KeyType::Press, XK_F1, 0);
 	// release key
@@ -0,0 +1,235 @@
+use std::{
+	os::raw::{c_int, c_uint},
+	time::Duration,
+};
+
+use x11::xlib::{Button1, Button3, XFlush};
+
+use crate::event::btn_event::{ButtonType, ScrollDirection};
+use crate::event::key_event::KeyType;
+use crate::keys::{KeyCombo, UnknownKey};
+use crate::{Session, Window};
+
+/// A single step of [Actions].
+///
+/// Positions are relative to the window the actions run against.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+	/// Waits before the next action.
+	Wait(Duration),
+	/// Moves the pointer, see [Window::send_motion].
+	Move {
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+	},
+	/// Presses or releases a mouse button, see [Window::send_btn].
+	Button {
+		/// Whether the button is pressed or released.
+		press: bool,
+		/// The button, e.g. [x11::xlib::Button1].
+		button: c_uint,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+	},
+	/// Clicks a mouse button `count` times, see [Window::click_button].
+	Click {
+		/// The button, e.g. [x11::xlib::Button1].
+		button: c_uint,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+		/// How many times the button is clicked, 2 for a double click.
+		count: u32,
+	},
+	/// Scrolls the wheel, see [Window::scroll].
+	Scroll {
+		/// Which way to scroll.
+		direction: ScrollDirection,
+		/// How many steps to scroll.
+		clicks: u32,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+	},
+	/// Presses or releases a key, see [Window::send_key].
+	Key {
+		/// Whether the key is pressed or released.
+		press: bool,
+		/// The keysym of the key, e.g. [x11::keysym::XK_F1].
+		keysym: c_uint,
+		/// The state of the event.
+		modifiers: c_uint,
+	},
+	/// Types text, see [Window::type_text].
+	Text(String),
+	/// Presses a shortcut, see [Window::send_combo].
+	Combo(KeyCombo),
+}
+
+impl Action {
+	/// Performs the action on the window.
+	pub fn run(&self, window: &Window) {
+		match self {
+			Action::Wait(duration) => {
+				unsafe { XFlush(window.display.as_raw()) };
+				window.display.cancel_token().sleep(*duration);
+			}
+			Action::Move { x, y } => window.send_motion(*x, *y, 0),
+			Action::Button { press, button, x, y } => {
+				let typ = if *press { ButtonType::Press } else { ButtonType::Release };
+				window.send_btn(typ, *button, *x, *y, 0);
+			}
+			Action::Click { button, x, y, count } => window.click_button(*button, *x, *y, *count, false),
+			Action::Scroll { direction, clicks, x, y } => window.scroll(*direction, *clicks, *x, *y),
+			Action::Key { press, keysym, modifiers } => {
+				let typ = if *press { KeyType::Press } else { KeyType::Release };
+				window.send_key(typ, *keysym, *modifiers);
+			}
+			Action::Text(text) => window.type_text(text, Duration::ZERO),
+			Action::Combo(combo) => window.send_combo(combo),
+		}
+	}
+}
+
+/// A sequence of input actions that run one after the other against a window.
+///
+/// The builder methods queue actions, nothing is sent until [Self::run].
+/// # Example
+/// ```ignore
+/// Actions::new()
+///     .click(10, 10)
+///     .wait(Duration::from_millis(200))
+///     .type_text("hi")
+///     .combo("Ctrl+S")
+///     .run(&window)
+///     .expect("Unknown key in a combo.");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Actions {
+	actions: Vec<Action>,
+	error: Option<UnknownKey>,
+}
+
+impl Actions {
+	/// Creates an empty sequence.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds an action to the end of the sequence.
+	pub fn then(mut self, action: Action) -> Self {
+		self.actions.push(action);
+		self
+	}
+
+	/// Waits before the next action.
+	pub fn wait(self, duration: Duration) -> Self {
+		self.then(Action::Wait(duration))
+	}
+
+	/// Moves the pointer.
+	pub fn move_to(self, x: c_int, y: c_int) -> Self {
+		self.then(Action::Move { x, y })
+	}
+
+	/// Clicks the left button.
+	pub fn click(self, x: c_int, y: c_int) -> Self {
+		self.then(Action::Click { button: Button1, x, y, count: 1 })
+	}
+
+	/// Double clicks the left button.
+	pub fn double_click(self, x: c_int, y: c_int) -> Self {
+		self.then(Action::Click { button: Button1, x, y, count: 2 })
+	}
+
+	/// Clicks the right button.
+	pub fn right_click(self, x: c_int, y: c_int) -> Self {
+		self.then(Action::Click { button: Button3, x, y, count: 1 })
+	}
+
+	/// Scrolls the wheel.
+	pub fn scroll(self, direction: ScrollDirection, clicks: u32, x: c_int, y: c_int) -> Self {
+		self.then(Action::Scroll { direction, clicks, x, y })
+	}
+
+	/// Presses and releases the key.
+	pub fn key(self, keysym: c_uint) -> Self {
+		self.then(Action::Key { press: true, keysym, modifiers: 0 })
+			.then(Action::Key { press: false, keysym, modifiers: 0 })
+	}
+
+	/// Types the text.
+	pub fn type_text(self, text: impl Into<String>) -> Self {
+		self.then(Action::Text(text.into()))
+	}
+
+	/// Presses the shortcut, see [KeyCombo::parse] for the syntax.
+	///
+	/// If the shortcut can't be parsed, [Self::run] fails before sending anything.
+	pub fn combo(mut self, combo: &str) -> Self {
+		match KeyCombo::parse(combo) {
+			Ok(combo) => self.then(Action::Combo(combo)),
+			Err(err) => {
+				self.error.get_or_insert(err);
+				self
+			}
+		}
+	}
+
+	/// The queued actions.
+	pub fn actions(&self) -> &[Action] {
+		&self.actions
+	}
+
+	/// Runs every action in order and flushes the connection once at the end.
+	///
+	/// Returns the first shortcut that could not be parsed, in which case nothing is sent.
+	pub fn run(&self, window: &Window) -> Result<(), UnknownKey> {
+		self.replay_on(window, 1.0)
+	}
+
+	/// Plays the actions back on the whole screen, with positions relative to the root window.
+	///
+	/// This is how a recording of [crate::recorder::InputRecorder] is replayed.
+	/// Events sent to the root window don't reach the windows on it, so switch the
+	/// display to the XTest backend first, see [crate::Display::set_input_backend].
+	/// See [Self::replay_on] for `speed`.
+	pub fn replay(&self, session: &Session, speed: f64) -> Result<(), UnknownKey> {
+		self.replay_on(session.root(), speed)
+	}
+
+	/// Plays the actions back on a window, with every wait divided by `speed`.
+	///
+	/// A `speed` of 2 plays twice as fast, a `speed` that is not positive or infinite skips the waits.
+	/// The replay stops early, without an error, once the [crate::CancelToken] of the display is cancelled.
+	pub fn replay_on(&self, window: &Window, speed: f64) -> Result<(), UnknownKey> {
+		if let Some(err) = &self.error {
+			return Err(err.clone());
+		}
+		let token = window.display.cancel_token();
+		for action in &self.actions {
+			if token.is_cancelled() {
+				break;
+			}
+			match action {
+				Action::Wait(duration) if speed.is_finite() && speed > 0.0 => Action::Wait(duration.div_f64(speed)).run(window),
+				Action::Wait(_) => {}
+				action => action.run(window),
+			}
+		}
+		unsafe { XFlush(window.display.as_raw()) };
+		Ok(())
+	}
+}
+
+impl From<Vec<Action>> for Actions {
+	fn from(actions: Vec<Action>) -> Self {
+		Actions { actions, error: None }
+	}
+}
@@ -0,0 +1,251 @@
+use std::{
+	collections::HashMap,
+	fmt,
+	time::{Duration, Instant},
+};
+
+use x11::xlib::XFlush;
+
+use crate::util::unescape;
+use crate::{Session, Window};
+
+use super::{Action, Actions, ParseActionError, ScriptError};
+
+/// How long [Macro::replay] waits for the window of a step to appear, e.g. one opened by the steps before.
+const WINDOW_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often [Macro::replay] looks for the window of a step while waiting for it.
+const WINDOW_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The window a step of a [Macro] is sent to, found again by its title and class every time the macro is replayed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Target {
+	/// A part of the title of the window.
+	pub title: Option<String>,
+	/// The instance or class name in `WM_CLASS`, ignoring case.
+	pub class: Option<String>,
+}
+
+impl Target {
+	/// The window whose title contains `title`.
+	pub fn title(title: impl Into<String>) -> Self {
+		Target { title: Some(title.into()), class: None }
+	}
+
+	/// The window with the instance or class name `class`, e.g. `firefox`.
+	pub fn class(class: impl Into<String>) -> Self {
+		Target { title: None, class: Some(class.into()) }
+	}
+
+	/// The title and class of the window.
+	pub fn of(window: &Window) -> Self {
+		Target { title: window.title(), class: window.class().ok().flatten().map(|(_, class)| class) }
+	}
+
+	/// Whether the window is the target.
+	pub fn matches(&self, window: &Window) -> bool {
+		let title = self.title.as_ref().is_none_or(|title| window.title().is_some_and(|it| it.contains(title.as_str())));
+		let class = self.class.as_ref().is_none_or(|class| {
+			window.class().ok().flatten().is_some_and(|(name, it)| name.eq_ignore_ascii_case(class) || it.eq_ignore_ascii_case(class))
+		});
+		title && class
+	}
+
+	/// The first window of the session that is the target.
+	pub fn find(&self, session: &Session) -> Option<Window> {
+		session.get_windows().ok()?.inner().into_iter().find(|it| self.matches(it))
+	}
+}
+
+/// A single event of a [Macro].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroStep {
+	/// How long after the step before this one happens.
+	pub delay: Duration,
+	/// The window the action is sent to, [None] for the root window.
+	///
+	/// Positions of the action are relative to the window.
+	pub target: Option<Target>,
+	/// What is sent, [Action::Wait] is not needed here as every step has a delay.
+	pub action: Action,
+}
+
+/// The error returned when a [Macro] can't be replayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MacroError {
+	/// No window matched the target of a step in time.
+	WindowNotFound(Target),
+}
+
+impl fmt::Display for MacroError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MacroError::WindowNotFound(target) => write!(f, "no window matches {:?}", target),
+		}
+	}
+}
+
+impl std::error::Error for MacroError {}
+
+/// A timed sequence of input aimed at windows that are found by title and class, recorded or written by hand.
+///
+/// Unlike [Actions] that run against a single window, every step finds its own window again when replayed,
+/// so a macro keeps working after the windows were closed and opened again.
+/// Record one with [crate::recorder::MacroRecorder], and save it with [Self::to_script].
+///
+/// # Example
+/// ```ignore
+/// let mut steps = Macro::new();
+/// steps.push(Duration::ZERO, Some(Target::class("gedit")), Action::Combo(KeyCombo::parse("Ctrl+S")?));
+/// steps.push(Duration::from_millis(500), Some(Target::title("Save As")), Action::Text("notes.txt\n".into()));
+/// steps.replay(&session, 1.0)?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Macro {
+	steps: Vec<MacroStep>,
+}
+
+impl Macro {
+	/// Creates an empty macro.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Runs actions against one window, every [Action::Wait] becomes the delay of the step after it.
+	pub fn from_actions(actions: &Actions, target: Option<Target>) -> Self {
+		let mut steps = Macro::new();
+		let mut delay = Duration::ZERO;
+		for action in actions.actions() {
+			match action {
+				Action::Wait(duration) => delay += *duration,
+				action => steps.push(std::mem::take(&mut delay), target.clone(), action.clone()),
+			}
+		}
+		steps
+	}
+
+	/// Adds a step to the end of the macro.
+	pub fn push(&mut self, delay: Duration, target: Option<Target>, action: Action) {
+		self.steps.push(MacroStep { delay, target, action });
+	}
+
+	/// The steps of the macro.
+	pub fn steps(&self) -> &[MacroStep] {
+		&self.steps
+	}
+
+	/// How long the macro takes at normal speed.
+	pub fn duration(&self) -> Duration {
+		self.steps.iter().map(|it| it.delay).sum()
+	}
+
+	/// Plays the macro back, with every delay divided by `speed`.
+	///
+	/// A `speed` of 2 plays twice as fast, a `speed` that is not positive or infinite skips the delays.
+	/// The window of every step is looked up again when it is gone, waiting up to 5 seconds for it to appear,
+	/// and is focused before keys are sent to it. Positions of steps without a target are relative
+	/// to the root window, see [Actions::replay] for what that needs.
+	/// The replay stops early, without an error, once the [crate::CancelToken] of the session is cancelled.
+	pub fn replay(&self, session: &Session, speed: f64) -> Result<(), MacroError> {
+		let token = session.cancel_token();
+		let mut windows: HashMap<&Target, Window> = HashMap::new();
+		let mut focused = None;
+		for step in &self.steps {
+			if speed.is_finite() && speed > 0.0 && token.sleep(step.delay.div_f64(speed)) {
+				break;
+			}
+			let window = match &step.target {
+				Some(target) => {
+					let alive = windows.get(target).is_some_and(|it| it.try_get_attr().is_ok());
+					if !alive {
+						match wait_for(session, target) {
+							Some(window) => windows.insert(target, window),
+							None if token.is_cancelled() => break,
+							None => return Err(MacroError::WindowNotFound(target.clone())),
+						};
+					}
+					&windows[target]
+				}
+				None => session.root(),
+			};
+			let types = matches!(step.action, Action::Key { .. } | Action::Text(_) | Action::Combo(_));
+			if types && step.target.is_some() && focused != Some(window.window) {
+				let _ = window.focus();
+				focused = Some(window.window);
+			}
+			step.action.run(window);
+			unsafe { XFlush(session.display.as_raw()) };
+		}
+		Ok(())
+	}
+
+	/// Writes the macro with one step per line: the delay in milliseconds, the class, the title and the action,
+	/// separated by tabs, e.g. to save a recording.
+	///
+	/// The class and title are empty for steps without a target, the action is written like [Actions::to_script].
+	/// With the tabs shown as `<TAB>`:
+	/// ```text
+	/// 0<TAB>gedit<TAB><TAB>combo Ctrl+s
+	/// 500<TAB><TAB>Save As<TAB>text notes.txt\n
+	/// ```
+	pub fn to_script(&self) -> String {
+		self.steps
+			.iter()
+			.map(|step| {
+				let target = step.target.clone().unwrap_or_default();
+				let field = |it: Option<String>| it.map(|it| it.escape_default().to_string()).unwrap_or_default();
+				format!("{}\t{}\t{}\t{}\n", step.delay.as_millis(), field(target.class), field(target.title), step.action)
+			})
+			.collect()
+	}
+
+	/// Reads a script written by [Self::to_script].
+	///
+	/// Empty lines and lines starting with `#` are skipped.
+	pub fn from_script(script: &str) -> Result<Self, ScriptError> {
+		script
+			.lines()
+			.enumerate()
+			.filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+			.map(|(i, line)| parse_step(line).map_err(|source| ScriptError { line: i + 1, text: line.to_string(), source }))
+			.collect::<Result<Vec<_>, _>>()
+			.map(|steps| Macro { steps })
+	}
+}
+
+impl From<Vec<MacroStep>> for Macro {
+	fn from(steps: Vec<MacroStep>) -> Self {
+		Macro { steps }
+	}
+}
+
+/// Reads a line written by [Macro::to_script].
+fn parse_step(line: &str) -> Result<MacroStep, ParseActionError> {
+	let mut fields = line.splitn(4, '\t');
+	// A line without all four fields is read as a single wrong argument.
+	let mut next = || fields.next().ok_or_else(|| ParseActionError::InvalidArgument(line.to_string()));
+	let delay = next()?.trim();
+	let delay = Duration::from_millis(delay.parse().map_err(|_| ParseActionError::InvalidArgument(delay.to_string()))?);
+	let field = |it: &str| match it {
+		"" => Ok(None),
+		_ => unescape(it).map(Some).map_err(|_| ParseActionError::InvalidArgument(it.to_string())),
+	};
+	let class = field(next()?)?;
+	let title = field(next()?)?;
+	let action = next()?.parse()?;
+	let target = if class.is_none() && title.is_none() { None } else { Some(Target { title, class }) };
+	Ok(MacroStep { delay, target, action })
+}
+
+/// Looks for the window of the target until it appears or [WINDOW_TIMEOUT] passes.
+fn wait_for(session: &Session, target: &Target) -> Option<Window> {
+	let deadline = Instant::now() + WINDOW_TIMEOUT;
+	loop {
+		if let Some(window) = target.find(session) {
+			return Some(window);
+		}
+		if Instant::now() >= deadline || session.cancel_token().sleep(WINDOW_POLL_INTERVAL) {
+			return None;
+		}
+	}
+}
@@ -0,0 +1,9 @@
+mod builder;
+mod macros;
+mod script;
+
+pub use self::{
+	builder::*,
+	macros::{Macro, MacroError, MacroStep, Target},
+	script::{ParseActionError, ScriptError},
+};
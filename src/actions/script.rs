@@ -0,0 +1,243 @@
+use std::{
+	fmt,
+	str::FromStr,
+	time::Duration,
+};
+
+use x11::xlib::KeySym;
+
+use crate::event::btn_event::ScrollDirection;
+use crate::keys::{keysym_from_name, name_from_keysym, KeyCombo};
+use crate::util::unescape;
+
+use super::{Action, Actions};
+
+/// The error returned when a script of [Actions] can't be read, with the line that is wrong.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptError {
+	/// The number of the line, starting at 1.
+	pub line: usize,
+	/// The text of the line.
+	pub text: String,
+	/// What is wrong with the line.
+	pub source: ParseActionError,
+}
+
+impl fmt::Display for ScriptError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.source)
+	}
+}
+
+impl std::error::Error for ScriptError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.source)
+	}
+}
+
+/// The error returned when a line is not an [Action], see [Action::from_str].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseActionError {
+	/// The command is not known.
+	UnknownCommand(String),
+	/// The command is known, but takes a different number of arguments.
+	WrongArgumentCount {
+		/// The command of the line.
+		command: String,
+		/// The number of arguments the command takes.
+		expected: usize,
+		/// The number of arguments in the line.
+		found: usize,
+	},
+	/// An argument of the command, as written in the line, isn't valid for it.
+	InvalidArgument(String),
+}
+
+impl fmt::Display for ParseActionError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ParseActionError::UnknownCommand(command) => write!(f, "unknown command {:?}", command),
+			ParseActionError::WrongArgumentCount { command, expected, found } => {
+				write!(f, "{:?} takes {} arguments, not {}", command, expected, found)
+			}
+			ParseActionError::InvalidArgument(arg) => write!(f, "invalid argument {:?}", arg),
+		}
+	}
+}
+
+impl std::error::Error for ParseActionError {}
+
+/// Writes the action as a single line of a script, see [Actions::to_script].
+impl fmt::Display for Action {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Action::Wait(duration) => write!(f, "wait {}", duration.as_millis()),
+			Action::Move { x, y } => write!(f, "move {} {}", x, y),
+			Action::Button { press, button, x, y } => write!(f, "{} {} {} {}", if *press { "press" } else { "release" }, button, x, y),
+			Action::Click { button, x, y, count } => write!(f, "click {} {} {} {}", button, x, y, count),
+			Action::Scroll { direction, clicks, x, y } => {
+				let direction = match direction {
+					ScrollDirection::Up => "up",
+					ScrollDirection::Down => "down",
+					ScrollDirection::Left => "left",
+					ScrollDirection::Right => "right",
+				};
+				write!(f, "scroll {} {} {} {}", direction, clicks, x, y)
+			}
+			Action::Key { press, keysym, modifiers } => {
+				let name = name_from_keysym(*keysym as KeySym).unwrap_or_else(|| format!("{:#x}", keysym));
+				write!(f, "{} {} {}", if *press { "keydown" } else { "keyup" }, name, modifiers)
+			}
+			Action::Text(text) => write!(f, "text {}", text.escape_default()),
+			Action::Combo(combo) => write!(f, "combo {}", combo),
+		}
+	}
+}
+
+/// Reads a line written by the [fmt::Display] of [Action].
+impl FromStr for Action {
+	type Err = ParseActionError;
+
+	fn from_str(line: &str) -> Result<Self, Self::Err> {
+		let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+		if command == "text" {
+			return unescape(rest).map(Action::Text).map_err(|_| ParseActionError::InvalidArgument(rest.to_string()));
+		}
+		let args = rest.split_whitespace().collect::<Vec<_>>();
+		let invalid = |i: usize| ParseActionError::InvalidArgument(args[i].to_string());
+		let expected = match command {
+			"wait" | "combo" => 1,
+			"move" | "keydown" | "keyup" => 2,
+			"press" | "release" => 3,
+			"click" | "scroll" => 4,
+			_ => return Err(ParseActionError::UnknownCommand(command.to_string())),
+		};
+		if args.len() != expected {
+			return Err(ParseActionError::WrongArgumentCount { command: command.to_string(), expected, found: args.len() });
+		}
+		Ok(match command {
+			"wait" => Action::Wait(Duration::from_millis(arg(&args, 0)?)),
+			"move" => Action::Move { x: arg(&args, 0)?, y: arg(&args, 1)? },
+			"press" | "release" => Action::Button { press: command == "press", button: arg(&args, 0)?, x: arg(&args, 1)?, y: arg(&args, 2)? },
+			"click" => Action::Click { button: arg(&args, 0)?, x: arg(&args, 1)?, y: arg(&args, 2)?, count: arg(&args, 3)? },
+			"scroll" => {
+				let direction = match args[0] {
+					"up" => ScrollDirection::Up,
+					"down" => ScrollDirection::Down,
+					"left" => ScrollDirection::Left,
+					"right" => ScrollDirection::Right,
+					_ => return Err(invalid(0)),
+				};
+				Action::Scroll { direction, clicks: arg(&args, 1)?, x: arg(&args, 2)?, y: arg(&args, 3)? }
+			}
+			"keydown" | "keyup" => {
+				let keysym = match args[0].strip_prefix("0x") {
+					Some(hex) => KeySym::from_str_radix(hex, 16).ok(),
+					None => keysym_from_name(args[0]),
+				};
+				Action::Key { press: command == "keydown", keysym: keysym.ok_or_else(|| invalid(0))? as _, modifiers: arg(&args, 1)? }
+			}
+			// Only combo is left, the other commands were rejected above.
+			_ => Action::Combo(KeyCombo::parse(args[0]).map_err(|_| invalid(0))?),
+		})
+	}
+}
+
+impl Actions {
+	/// Writes the actions as a script with one action per line, e.g. to save a recording.
+	///
+	/// ```text
+	/// click 1 10 10 1
+	/// wait 200
+	/// text hi\n
+	/// combo Ctrl+s
+	/// ```
+	pub fn to_script(&self) -> String {
+		self.actions().iter().map(|it| format!("{}\n", it)).collect()
+	}
+
+	/// Reads a script written by [Self::to_script].
+	///
+	/// Empty lines and lines starting with `#` are skipped.
+	pub fn from_script(script: &str) -> Result<Self, ScriptError> {
+		script
+			.lines()
+			.enumerate()
+			.filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+			.map(|(i, line)| line.trim_start().parse().map_err(|source| ScriptError { line: i + 1, text: line.to_string(), source }))
+			.collect::<Result<Vec<_>, _>>()
+			.map(Actions::from)
+	}
+}
+
+/// Parses the argument at `i`.
+fn arg<T: FromStr>(args: &[&str], i: usize) -> Result<T, ParseActionError> {
+	let arg = args.get(i).copied().unwrap_or_default();
+	arg.parse().map_err(|_| ParseActionError::InvalidArgument(arg.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use x11::keysym::{XK_Return, XK_a};
+	use x11::xlib::ShiftMask;
+
+	use super::*;
+
+	fn all_actions() -> Actions {
+		Actions::from(vec![
+			Action::Wait(Duration::from_millis(200)),
+			Action::Move { x: 10, y: -5 },
+			Action::Button { press: true, button: 1, x: 10, y: 20 },
+			Action::Button { press: false, button: 1, x: 12, y: 22 },
+			Action::Click { button: 3, x: 5, y: 6, count: 2 },
+			Action::Scroll { direction: ScrollDirection::Left, clicks: 3, x: 7, y: 8 },
+			Action::Key { press: true, keysym: XK_a, modifiers: ShiftMask },
+			Action::Key { press: false, keysym: 0x1000e9, modifiers: 0 },
+			Action::Text("hi \"there\"\n\tbye".to_owned()),
+			Action::Combo(KeyCombo::parse("Ctrl+s").unwrap()),
+		])
+	}
+
+	#[test]
+	fn script_round_trips() {
+		let actions = all_actions();
+		assert_eq!(Actions::from_script(&actions.to_script()), Ok(actions));
+	}
+
+	#[test]
+	fn actions_are_written_one_per_line() {
+		let actions = Actions::from(vec![
+			Action::Click { button: 1, x: 10, y: 10, count: 1 },
+			Action::Wait(Duration::from_millis(200)),
+			Action::Text("hi\n".to_owned()),
+			Action::Key { press: true, keysym: XK_Return, modifiers: 0 },
+		]);
+		assert_eq!(actions.to_script(), "click 1 10 10 1\nwait 200\ntext hi\\n\nkeydown Return 0\n");
+	}
+
+	#[test]
+	fn from_script_skips_comments_and_reports_the_wrong_line() {
+		let script = "# a recording\n\nmove 1 2\n  wait 10\nwiggle 3\n";
+		let error = Actions::from_script(script).unwrap_err();
+		let source = ParseActionError::UnknownCommand("wiggle".to_owned());
+		assert_eq!(error, ScriptError { line: 5, text: "wiggle 3".to_owned(), source: source.clone() });
+		assert_eq!(error.to_string(), "line 5: unknown command \"wiggle\"");
+		assert_eq!(std::error::Error::source(&error).map(ToString::to_string), Some(source.to_string()));
+		assert_eq!(
+			Actions::from_script("# a recording\n\nmove 1 2\n  wait 10\n"),
+			Ok(Actions::from(vec![Action::Move { x: 1, y: 2 }, Action::Wait(Duration::from_millis(10))])),
+		);
+	}
+
+	#[test]
+	fn from_str_tells_unknown_commands_from_invalid_arguments() {
+		assert_eq!("wiggle 3".parse::<Action>(), Err(ParseActionError::UnknownCommand("wiggle".to_owned())));
+		let wrong_count = |found| Err(ParseActionError::WrongArgumentCount { command: "wait".to_owned(), expected: 1, found });
+		assert_eq!("wait".parse::<Action>(), wrong_count(0));
+		assert_eq!("wait 1 2".parse::<Action>(), wrong_count(2));
+		assert_eq!("wait soon".parse::<Action>(), Err(ParseActionError::InvalidArgument("soon".to_owned())));
+		assert_eq!("scroll sideways 1 0 0".parse::<Action>(), Err(ParseActionError::InvalidArgument("sideways".to_owned())));
+		assert_eq!("keydown NoSuchKey 0".parse::<Action>(), Err(ParseActionError::InvalidArgument("NoSuchKey".to_owned())));
+		assert_eq!("combo Hyper+t".parse::<Action>(), Err(ParseActionError::InvalidArgument("Hyper+t".to_owned())));
+	}
+}
@@ -1,12 +1,16 @@
 use x11::xlib::{
     Atom as XAtom,
     True as XTrue,
+    XFree,
+    XGetAtomName,
     XInternAtom,
 };
 use std::ffi::{
+    CStr,
     CString,
     NulError,
 };
+use std::os::raw::c_void;
 use crate::Display;
 
 /// A wrapper around a [x11::xlib::Atom].
@@ -27,7 +31,20 @@ impl Atom {
     /// ```
     pub fn new<T: Into<Vec<u8>>>(display: &Display, text: T) -> Result<Self, NulError> {
         let text = CString::new(text)?;
-        let atom = unsafe { XInternAtom(display.0, text.as_ptr(), XTrue) };
+        let atom = unsafe { XInternAtom(display.as_raw(), text.as_ptr(), XTrue) };
         Ok(Atom(atom))
     }
+
+    /// The name of the atom, e.g. `WM_NAME`, [None] if the server doesn't know it.
+    pub fn name(&self, display: &Display) -> Option<String> {
+        unsafe {
+            let name = XGetAtomName(display.as_raw(), self.0);
+            if name.is_null() {
+                return None;
+            }
+            let text = CStr::from_ptr(name).to_string_lossy().into_owned();
+            XFree(name as *mut c_void);
+            Some(text)
+        }
+    }
 }
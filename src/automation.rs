@@ -0,0 +1,201 @@
+use std::{
+	fmt,
+	time::{Duration, Instant},
+};
+
+use crate::actions::Target;
+use crate::keys::UnknownKey;
+use crate::{Error, PasteError, Session, Window};
+
+/// How long [Automation] waits for windows and for windows to settle, unless changed with [Automation::set_timeout].
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often [Automation::window] looks for the window while waiting for it.
+const WINDOW_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a window has to stay unchanged for [Automation::wait_stable].
+const QUIET_PERIOD: Duration = Duration::from_millis(300);
+
+/// The delay between the characters typed by [Automation::type_into].
+const TYPING_DELAY: Duration = Duration::from_millis(5);
+
+/// The error returned by the steps of an [Automation].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AutomationError {
+	/// The connection to the X server failed, or a request on it.
+	X11(Error),
+	/// No window with this title or class appeared in time.
+	WindowNotFound(String),
+	/// The shortcut has a part that is not a known modifier or key.
+	UnknownKey(String),
+	/// Pasting the text failed.
+	Paste(PasteError),
+	/// The window didn't stop changing in time.
+	Timeout,
+	/// The [crate::CancelToken] of the session was cancelled.
+	Cancelled,
+}
+
+impl fmt::Display for AutomationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AutomationError::X11(err) => err.fmt(f),
+			AutomationError::WindowNotFound(query) => write!(f, "no window with the title or class {:?}", query),
+			AutomationError::UnknownKey(key) => write!(f, "unknown key {:?}", key),
+			AutomationError::Paste(err) => err.fmt(f),
+			AutomationError::Timeout => write!(f, "the window did not stop changing in time"),
+			AutomationError::Cancelled => write!(f, "the automation was cancelled"),
+		}
+	}
+}
+
+impl std::error::Error for AutomationError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			AutomationError::X11(err) => Some(err),
+			AutomationError::Paste(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<Error> for AutomationError {
+	fn from(err: Error) -> Self {
+		AutomationError::X11(err)
+	}
+}
+
+impl From<PasteError> for AutomationError {
+	fn from(err: PasteError) -> Self {
+		AutomationError::Paste(err)
+	}
+}
+
+impl From<UnknownKey> for AutomationError {
+	fn from(err: UnknownKey) -> Self {
+		AutomationError::UnknownKey(err.0)
+	}
+}
+
+/// The common steps of scripting other applications, behind one entry point.
+///
+/// Windows are found by a part of their title or by their class, waiting for them to appear.
+/// Input is sent to the window after focusing it, and the focus and pointer are given back afterwards,
+/// see [Session::with_focus]. Everything else is on the [Window]s this returns and on [Self::session].
+///
+/// Every wait gives up after the timeout, 5 seconds unless changed, and returns early with
+/// [AutomationError::Cancelled] once the [crate::CancelToken] of the session is cancelled.
+///
+/// # Example
+/// ```ignore
+/// let automation = Automation::open()?;
+/// let firefox = automation.window("Firefox")?;
+/// automation.press(&firefox, "Ctrl+L")?;
+/// automation.type_into(&firefox, "example.org\n")?;
+/// automation.wait_stable(&firefox)?;
+/// firefox.click(200, 300);
+/// ```
+pub struct Automation {
+	session: Session,
+	timeout: Duration,
+}
+
+impl Automation {
+	/// Opens a connection to the X server.
+	pub fn open() -> Result<Self, AutomationError> {
+		Session::open().map(Self::from_session).ok_or(AutomationError::X11(Error::DisplayOpenFailed))
+	}
+
+	/// Automates on an already opened session.
+	pub fn from_session(session: Session) -> Self {
+		Automation { session, timeout: DEFAULT_TIMEOUT }
+	}
+
+	/// The session, for everything that is not part of this facade.
+	pub fn session(&self) -> &Session {
+		&self.session
+	}
+
+	/// How long the waits give up after.
+	pub fn timeout(&self) -> Duration {
+		self.timeout
+	}
+
+	/// Changes how long the waits give up after.
+	pub fn set_timeout(&mut self, timeout: Duration) {
+		self.timeout = timeout;
+	}
+
+	/// Finds the window whose title contains `query`, or whose class is `query` ignoring case,
+	/// waiting for it to appear.
+	pub fn window(&self, query: &str) -> Result<Window, AutomationError> {
+		let deadline = Instant::now() + self.timeout;
+		loop {
+			if let Some(window) = self.find_window(query) {
+				return Ok(window);
+			}
+			if Instant::now() >= deadline {
+				return Err(AutomationError::WindowNotFound(query.to_owned()));
+			}
+			self.sleep(WINDOW_POLL_INTERVAL)?;
+		}
+	}
+
+	/// Finds the window like [Self::window], without waiting.
+	pub fn find_window(&self, query: &str) -> Option<Window> {
+		let (title, class) = (Target::title(query), Target::class(query));
+		self.session.get_windows().ok()?.inner().into_iter().find(|it| title.matches(it) || class.matches(it))
+	}
+
+	/// Waits until the window is closed.
+	pub fn wait_closed(&self, window: &Window) -> Result<(), AutomationError> {
+		let deadline = Instant::now() + self.timeout;
+		while window.try_get_attr().is_ok() {
+			if Instant::now() >= deadline {
+				return Err(AutomationError::Timeout);
+			}
+			self.sleep(WINDOW_POLL_INTERVAL)?;
+		}
+		Ok(())
+	}
+
+	/// Waits until the window stopped changing for a moment, e.g. after a click opened a page or a dialog,
+	/// see [Window::wait_until_stable].
+	pub fn wait_stable(&self, window: &Window) -> Result<(), AutomationError> {
+		if window.wait_until_stable(QUIET_PERIOD, self.timeout) {
+			Ok(())
+		} else {
+			Err(self.cancelled().unwrap_or(AutomationError::Timeout))
+		}
+	}
+
+	/// Runs `steps` with the window focused, then gives the focus and pointer back.
+	pub fn focused<R>(&self, window: &Window, steps: impl FnOnce(&Window) -> R) -> R {
+		self.session.with_focus(window, || steps(window))
+	}
+
+	/// Types the text into the window, see [Window::type_text].
+	pub fn type_into(&self, window: &Window, text: &str) -> Result<(), AutomationError> {
+		self.focused(window, |it| it.type_text(text, TYPING_DELAY));
+		self.cancelled().map_or(Ok(()), Err)
+	}
+
+	/// Presses a shortcut like `Ctrl+Shift+T` in the window, see [Window::send_chord].
+	pub fn press(&self, window: &Window, chord: &str) -> Result<(), AutomationError> {
+		Ok(self.focused(window, |it| it.send_chord(chord))?)
+	}
+
+	/// Pastes the text into the window with Ctrl+V, and puts the clipboard back, see [Window::paste_text].
+	pub fn paste(&self, window: &Window, text: &str) -> Result<(), AutomationError> {
+		Ok(self.focused(window, |it| it.paste_text(text))?)
+	}
+
+	/// Waits for `duration`, returns [AutomationError::Cancelled] if the session was cancelled meanwhile.
+	pub fn sleep(&self, duration: Duration) -> Result<(), AutomationError> {
+		if self.session.cancel_token().sleep(duration) { Err(AutomationError::Cancelled) } else { Ok(()) }
+	}
+
+	fn cancelled(&self) -> Option<AutomationError> {
+		if self.session.cancel_token().is_cancelled() { Some(AutomationError::Cancelled) } else { None }
+	}
+}
@@ -0,0 +1,286 @@
+use std::{
+	collections::HashMap,
+	sync::{Mutex, MutexGuard},
+};
+
+use x11::xlib::{Atom as XAtom, Window as XWindow, XA_STRING, XA_WINDOW};
+
+use crate::{capture::CaptureError, Error, Rect, NET_CLIENT_LIST, NET_WM_NAME};
+
+use super::{Backend, Property, RawImage};
+
+/// The root window of the mock, like a real root window it is not in the client list.
+const ROOT: XWindow = 1;
+
+/// Atoms that are created by the mock start here, above the predefined ones like [XA_WINDOW].
+const FIRST_ATOM: XAtom = 100;
+
+/// A [Backend] that keeps its windows in memory, for testing code without an X server or Xvfb.
+///
+/// Windows, titles, geometry and pixels are scripted with the methods of the mock,
+/// client messages that are sent are recorded and can be read back with [Self::sent_messages].
+/// Like a real server, [Backend::intern_atom] only knows atoms that were created,
+/// here by the setters of the mock. Build a session without an X server on it with [crate::Session::with_backend]
+/// to make the window lists of the session come from the mock, or pass it to code written against [Backend] directly.
+///
+/// # Example
+/// ```ignore
+/// let mock = MockBackend::new();
+/// let editor = mock.add_window("notes.txt - Editor", Rect::new(0, 0, 800, 600));
+/// mock.add_window("Terminal", Rect::new(800, 0, 400, 600));
+/// assert_eq!(find_editor(&mock), Some(editor));
+/// ```
+#[derive(Default)]
+pub struct MockBackend {
+	state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+	atoms: HashMap<String, XAtom>,
+	windows: HashMap<XWindow, MockWindow>,
+	clients: Vec<XWindow>,
+	sent: Vec<SentMessage>,
+}
+
+#[derive(Default)]
+struct MockWindow {
+	geometry: Rect,
+	properties: HashMap<XAtom, Property>,
+	image: Option<RawImage>,
+}
+
+/// A client message that was sent through a [MockBackend].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentMessage {
+	/// The window the message is about.
+	pub window: XWindow,
+	/// The name of the message type, e.g. `_NET_ACTIVE_WINDOW`.
+	pub message_type: String,
+	/// The five values of the message.
+	pub data: [u32; 5],
+}
+
+impl MockBackend {
+	/// A mock with only a root window.
+	pub fn new() -> Self {
+		let mock = MockBackend::default();
+		mock.state().windows.insert(ROOT, MockWindow::default());
+		mock.sync_client_list();
+		mock
+	}
+
+	/// Adds a window to the end of the client list, with `title` in `_NET_WM_NAME` and `WM_NAME`.
+	pub fn add_window(&self, title: &str, geometry: Rect) -> XWindow {
+		let window = {
+			let mut state = self.state();
+			let window = state.windows.keys().max().map_or(ROOT, |it| *it) + 1;
+			state.windows.insert(window, MockWindow { geometry, ..Default::default() });
+			state.clients.push(window);
+			window
+		};
+		self.set_title(window, title);
+		self.sync_client_list();
+		window
+	}
+
+	/// Removes a window, later requests about it fail with [Error::BadWindow] like for a destroyed window.
+	pub fn remove_window(&self, window: XWindow) {
+		{
+			let mut state = self.state();
+			state.windows.remove(&window);
+			state.clients.retain(|it| *it != window);
+		}
+		self.sync_client_list();
+	}
+
+	/// Changes the title of the window.
+	pub fn set_title(&self, window: XWindow, title: &str) {
+		let utf8 = self.atom("UTF8_STRING");
+		self.set_property(window, NET_WM_NAME, Property { typ: utf8, format: 8, data: title.as_bytes().to_vec() });
+		self.set_property(window, "WM_NAME", Property { typ: XA_STRING, format: 8, data: title.as_bytes().to_vec() });
+	}
+
+	/// Moves and resizes the window.
+	pub fn set_geometry(&self, window: XWindow, geometry: Rect) {
+		if let Some(it) = self.state().windows.get_mut(&window) {
+			it.geometry = geometry;
+		}
+	}
+
+	/// Sets any property of the window, the atom called `name` is created if needed.
+	pub fn set_property(&self, window: XWindow, name: &str, property: Property) {
+		let atom = self.atom(name);
+		if let Some(it) = self.state().windows.get_mut(&window) {
+			it.properties.insert(atom, property);
+		}
+	}
+
+	/// The pixels [Backend::get_image] reads from the window, without them it fails like an unmapped window.
+	pub fn set_image(&self, window: XWindow, image: RawImage) {
+		if let Some(it) = self.state().windows.get_mut(&window) {
+			it.image = Some(image);
+		}
+	}
+
+	/// The client messages that were sent, oldest first.
+	pub fn sent_messages(&self) -> Vec<SentMessage> {
+		self.state().sent.clone()
+	}
+
+	/// Forgets the client messages that were sent until now.
+	pub fn clear_sent_messages(&self) {
+		self.state().sent.clear();
+	}
+
+	/// The atom called `name`, created if it doesn't exist yet.
+	pub fn atom(&self, name: &str) -> XAtom {
+		let mut state = self.state();
+		let next = FIRST_ATOM + state.atoms.len() as XAtom;
+		*state.atoms.entry(name.to_owned()).or_insert(next)
+	}
+
+	fn state(&self) -> MutexGuard<'_, State> {
+		self.state.lock().unwrap_or_else(|it| it.into_inner())
+	}
+
+	fn sync_client_list(&self) {
+		let data = self.state().clients.iter().flat_map(|it| (*it as u32).to_ne_bytes()).collect();
+		self.set_property(ROOT, NET_CLIENT_LIST, Property { typ: XA_WINDOW, format: 32, data });
+	}
+}
+
+impl Backend for MockBackend {
+	fn root(&self) -> XWindow {
+		ROOT
+	}
+
+	fn intern_atom(&self, name: &str) -> Result<XAtom, Error> {
+		Ok(self.state().atoms.get(name).copied().unwrap_or(0))
+	}
+
+	fn get_properties(&self, requests: &[(XWindow, XAtom)]) -> Vec<Result<Property, Error>> {
+		let state = self.state();
+		requests
+			.iter()
+			.map(|(window, property)| match state.windows.get(window) {
+				Some(it) => Ok(it.properties.get(property).cloned().unwrap_or_default()),
+				None => Err(Error::BadWindow(*window)),
+			})
+			.collect()
+	}
+
+	fn send_client_message(&self, window: XWindow, message_type: XAtom, data: [u32; 5]) -> Result<(), Error> {
+		let mut state = self.state();
+		let message_type = state
+			.atoms
+			.iter()
+			.find(|(_, atom)| **atom == message_type)
+			.map(|(name, _)| name.clone())
+			.ok_or(Error::RequestFailed(0))?;
+		state.sent.push(SentMessage { window, message_type, data });
+		Ok(())
+	}
+
+	fn geometry(&self, window: XWindow) -> Result<Rect, Error> {
+		self.state().windows.get(&window).map(|it| it.geometry).ok_or(Error::BadWindow(window))
+	}
+
+	fn get_image(&self, window: XWindow, rect: Rect) -> Result<RawImage, Error> {
+		let state = self.state();
+		let image = state
+			.windows
+			.get(&window)
+			.ok_or(Error::BadWindow(window))?
+			.image
+			.as_ref()
+			.ok_or(Error::Capture(CaptureError::Null))?;
+		let inside = Rect::new(0, 0, image.width, image.height)
+			.intersect(&rect)
+			.filter(|it| *it == rect)
+			.ok_or(Error::Capture(CaptureError::OutOfBounds))?;
+		let data = (0..inside.height as usize)
+			.flat_map(|y| {
+				let start = ((inside.y as usize + y) * image.width as usize + inside.x as usize) * 4;
+				image.data[start..start + inside.width as usize * 4].iter().copied()
+			})
+			.collect();
+		Ok(RawImage { width: inside.width, height: inside.height, depth: image.depth, data })
+	}
+
+	fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn client_list_follows_added_and_removed_windows() {
+		let mock = MockBackend::new();
+		let editor = mock.add_window("Editor", Rect::new(0, 0, 800, 600));
+		let terminal = mock.add_window("Terminal", Rect::new(800, 0, 400, 600));
+		assert_eq!(mock.client_list(), Ok(vec![editor, terminal]));
+
+		mock.remove_window(editor);
+		assert_eq!(mock.client_list(), Ok(vec![terminal]));
+		assert_eq!(mock.geometry(editor), Err(Error::BadWindow(editor)));
+	}
+
+	#[test]
+	fn title_prefers_net_wm_name() {
+		let mock = MockBackend::new();
+		let window = mock.add_window("Editor", Rect::default());
+		assert_eq!(mock.title(window), Ok(Some("Editor".to_owned())));
+
+		mock.set_property(window, NET_WM_NAME, Property { typ: mock.atom("UTF8_STRING"), format: 8, data: "Éditeur".into() });
+		assert_eq!(mock.title(window), Ok(Some("Éditeur".to_owned())));
+	}
+
+	#[test]
+	fn find_windows_matches_wm_name_up_to_the_limit() {
+		let mock = MockBackend::new();
+		let first = mock.add_window("notes.txt - Editor", Rect::default());
+		mock.add_window("Terminal", Rect::default());
+		let second = mock.add_window("todo.txt - Editor", Rect::default());
+		let editor = |name: &[u8]| name.ends_with(b"- Editor");
+
+		assert_eq!(mock.find_windows(&editor, 0), Ok(vec![first, second]));
+		assert_eq!(mock.find_windows(&editor, 1), Ok(vec![first]));
+	}
+
+	#[test]
+	fn find_windows_skips_windows_without_a_name() {
+		let mock = MockBackend::new();
+		let unnamed = mock.add_window("", Rect::default());
+		mock.set_property(unnamed, "WM_NAME", Property::default());
+		let named = mock.add_window("Terminal", Rect::default());
+
+		assert_eq!(mock.find_windows(&|_| true, 0), Ok(vec![named]));
+	}
+
+	#[test]
+	fn records_sent_client_messages() {
+		let mock = MockBackend::new();
+		let window = mock.add_window("Editor", Rect::default());
+		let active = mock.atom("_NET_ACTIVE_WINDOW");
+		mock.send_client_message(window, active, [1, 0, 0, 0, 0]).unwrap();
+
+		let sent = SentMessage { window, message_type: "_NET_ACTIVE_WINDOW".to_owned(), data: [1, 0, 0, 0, 0] };
+		assert_eq!(mock.sent_messages(), vec![sent]);
+		mock.clear_sent_messages();
+		assert!(mock.sent_messages().is_empty());
+	}
+
+	#[test]
+	fn get_image_copies_the_region() {
+		let mock = MockBackend::new();
+		let window = mock.add_window("Image", Rect::new(0, 0, 2, 2));
+		let data = (0..16).collect::<Vec<u8>>();
+		mock.set_image(window, RawImage { width: 2, height: 2, depth: 24, data });
+
+		let right = mock.get_image(window, Rect::new(1, 0, 1, 2)).unwrap();
+		assert_eq!(right, RawImage { width: 1, height: 2, depth: 24, data: vec![4, 5, 6, 7, 12, 13, 14, 15] });
+		assert_eq!(mock.get_image(window, Rect::new(1, 1, 2, 2)), Err(Error::Capture(CaptureError::OutOfBounds)));
+	}
+}
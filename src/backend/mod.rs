@@ -0,0 +1,164 @@
+mod xlib;
+#[cfg(any(feature = "backend-xcb", feature = "async"))]
+mod xcb;
+#[cfg(any(test, feature = "testing"))]
+mod mock;
+
+use std::convert::TryInto;
+
+use x11::xlib::{Atom as XAtom, Window as XWindow, XA_WINDOW};
+
+use crate::{Error, Rect, NET_CLIENT_LIST, NET_WM_NAME};
+
+pub use self::xlib::XlibBackend;
+#[cfg(any(feature = "backend-xcb", feature = "async"))]
+pub use self::xcb::XcbBackend;
+#[cfg(feature = "async")]
+pub(crate) use self::xcb::{ImageRequest, SizeRequest};
+#[cfg(any(test, feature = "testing"))]
+pub use self::mock::{MockBackend, SentMessage};
+
+/// The backend picked by the `backend-xcb` feature, [XlibBackend] without it.
+#[cfg(not(feature = "backend-xcb"))]
+pub type DefaultBackend = XlibBackend;
+/// The backend picked by the `backend-xcb` feature, [XcbBackend] with it.
+#[cfg(feature = "backend-xcb")]
+pub type DefaultBackend = XcbBackend;
+
+/// The requests to the X server the crate is built on, so they can go through Xlib or XCB.
+///
+/// Errors are returned from the request that caused them instead of going to a global error handler.
+/// Get the backend of a session with [crate::Session::backend], [crate::Session::root], [crate::Session::get_windows],
+/// [crate::Session::get_window_by_name] and [crate::Session::find_window] make their requests through it.
+/// A backend is shared with the session between threads, so it has to be [Send] and [Sync].
+///
+/// # Scope
+/// Only the window lists of a session go through the backend. The methods of [crate::Window], like titles,
+/// geometry, capture, focus and input, keep making their requests with Xlib on [crate::Display], whatever
+/// backend the session has, and [crate::Window::capture_stream_async] always uses XCB. Read those through
+/// the backend instead by passing the id, e.g. `session.backend().title(window.window)`, which is also
+/// how code tested with a [crate::backend::MockBackend] should do it.
+pub trait Backend: Send + Sync {
+	/// The root window of the default screen.
+	fn root(&self) -> XWindow;
+
+	/// The atom called `name`, 0 if no client created it yet.
+	fn intern_atom(&self, name: &str) -> Result<XAtom, Error>;
+
+	/// Reads `property` of `window`, whatever its type is.
+	///
+	/// A property that isn't set is returned with a type of 0 and no data.
+	fn get_property(&self, window: XWindow, property: XAtom) -> Result<Property, Error> {
+		self.get_properties(&[(window, property)]).pop().unwrap_or(Err(Error::RequestFailed(0)))
+	}
+
+	/// Reads many properties at once, in the same order as `requests`.
+	///
+	/// Xlib waits for every reply before sending the next request, XCB sends all of them first.
+	fn get_properties(&self, requests: &[(XWindow, XAtom)]) -> Vec<Result<Property, Error>>;
+
+	/// Sends a client message about `window` to the root window, the way EWMH requests are sent
+	/// to the window manager.
+	fn send_client_message(&self, window: XWindow, message_type: XAtom, data: [u32; 5]) -> Result<(), Error>;
+
+	/// Where `window` is relative to the root window and its size, without borders or decorations.
+	fn geometry(&self, window: XWindow) -> Result<Rect, Error>;
+
+	/// Copies the pixels in `rect` of `window`, relative to the window.
+	fn get_image(&self, window: XWindow, rect: Rect) -> Result<RawImage, Error>;
+
+	/// Sends the requests that are still buffered to the server.
+	fn flush(&self);
+
+	/// The title of `window` from `_NET_WM_NAME`, or from `WM_NAME` if that isn't set.
+	fn title(&self, window: XWindow) -> Result<Option<String>, Error> {
+		for name in &[NET_WM_NAME, "WM_NAME"] {
+			let atom = self.intern_atom(name)?;
+			if atom == 0 {
+				continue;
+			}
+			if let Some(title) = self.get_property(window, atom)?.text() {
+				return Ok(Some(title));
+			}
+		}
+		Ok(None)
+	}
+
+	/// The windows in `_NET_CLIENT_LIST` of the root window.
+	fn client_list(&self) -> Result<Vec<XWindow>, Error> {
+		let atom = self.intern_atom(NET_CLIENT_LIST)?;
+		if atom == 0 {
+			return Err(Error::AtomMissing(NET_CLIENT_LIST));
+		}
+		let property = self.get_property(self.root(), atom)?;
+		match property.typ {
+			0 => Err(Error::PropertyMissing(NET_CLIENT_LIST)),
+			XA_WINDOW if property.format == 32 => Ok(property.values().into_iter().map(XWindow::from).collect()),
+			_ => Err(Error::PropertyFormatUnexpected(property.format as _)),
+		}
+	}
+
+	/// The windows in the client list whose `WM_NAME` matches `filter`, at most `limit` of them, 0 for all.
+	///
+	/// The names of all the clients are read at once with [Self::get_properties],
+	/// windows without a name or that are gone by then are left out.
+	fn find_windows(&self, filter: &dyn Fn(&[u8]) -> bool, limit: usize) -> Result<Vec<XWindow>, Error> {
+		let clients = self.client_list()?;
+		let wm_name = self.intern_atom("WM_NAME")?;
+		let requests = clients.iter().map(|it| (*it, wm_name)).collect::<Vec<_>>();
+		let limit = if limit == 0 { usize::MAX } else { limit };
+		Ok(clients
+			.into_iter()
+			.zip(self.get_properties(&requests))
+			.filter_map(|(window, name)| {
+				let name = name.ok().filter(|it| it.typ != 0 && it.format == 8)?;
+				let end = name.data.iter().rposition(|&it| it != 0).map_or(0, |it| it + 1);
+				Some(window).filter(|_| filter(&name.data[..end]))
+			})
+			.take(limit)
+			.collect())
+	}
+}
+
+/// The value of a property, as read by [Backend::get_property].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Property {
+	/// The type of the value, 0 if the property isn't set.
+	pub typ: XAtom,
+	/// Whether the value is made of 8, 16 or 32 bit items.
+	pub format: u8,
+	/// The items packed in the byte order of this machine, 4 bytes each when the format is 32.
+	pub data: Vec<u8>,
+}
+
+impl Property {
+	/// The items of a format 32 property, empty for other formats.
+	pub fn values(&self) -> Vec<u32> {
+		if self.format != 32 {
+			return Vec::new();
+		}
+		self.data.chunks_exact(4).map(|it| u32::from_ne_bytes(it.try_into().unwrap())).collect()
+	}
+
+	/// The value of a format 8 property as text, without trailing NUL bytes.
+	pub fn text(&self) -> Option<String> {
+		if self.format != 8 {
+			return None;
+		}
+		let end = self.data.iter().rposition(|&it| it != 0).map_or(0, |it| it + 1);
+		Some(String::from_utf8_lossy(&self.data[..end]).into_owned())
+	}
+}
+
+/// Pixels read by [Backend::get_image], in the Z pixmap format of the server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawImage {
+	/// Width in pixels.
+	pub width: u32,
+	/// Height in pixels.
+	pub height: u32,
+	/// Bits per pixel that hold the color, 24 for most windows and 32 for ARGB ones.
+	pub depth: u8,
+	/// Rows of 4 bytes per pixel for depths 24 and 32, in BGRX order on little endian servers.
+	pub data: Vec<u8>,
+}
@@ -0,0 +1,392 @@
+use std::{
+	os::raw::{c_char, c_int, c_uint, c_void},
+	ptr::null_mut,
+	sync::Arc,
+	slice,
+};
+
+use libc::free;
+use x11::{
+	xlib::{Atom as XAtom, Window as XWindow, XDefaultRootWindow, SubstructureNotifyMask, SubstructureRedirectMask},
+	xlib_xcb::{xcb_connection_t, XGetXCBConnection},
+};
+
+use crate::{capture::CaptureError, Display, Error, Rect, XError};
+
+use super::{Backend, Property, RawImage};
+
+/// The number of 4 byte units of a property that are read, the same limit as the Xlib backend.
+const PROPERTY_LENGTH: u32 = 4096 / 4;
+
+/// Makes the requests with XCB on the connection Xlib already opened for the display.
+///
+/// Requests are sent before any reply is waited for, so [Backend::get_properties] takes one
+/// round trip no matter how many properties are read, and errors come back with the reply
+/// of the request instead of going to the error handler of Xlib.
+pub struct XcbBackend {
+	display: Arc<Display>,
+	connection: *mut xcb_connection_t,
+}
+
+// XCB locks the connection around every request and reply itself.
+unsafe impl Send for XcbBackend {}
+unsafe impl Sync for XcbBackend {}
+
+impl XcbBackend {
+	/// Uses the XCB connection underneath `display`.
+	pub fn new(display: Arc<Display>) -> Self {
+		let connection = unsafe { XGetXCBConnection(display.as_raw()) };
+		XcbBackend { display, connection }
+	}
+}
+
+impl Backend for XcbBackend {
+	fn root(&self) -> XWindow {
+		unsafe { XDefaultRootWindow(self.display.as_raw()) }
+	}
+
+	fn intern_atom(&self, name: &str) -> Result<XAtom, Error> {
+		unsafe {
+			let cookie = xcb_intern_atom(self.connection, 1, name.len() as u16, name.as_ptr() as _);
+			let mut error = null_mut();
+			let reply = xcb_intern_atom_reply(self.connection, cookie, &mut error);
+			let reply = take_reply(reply, error)?;
+			let atom = (*reply).atom as XAtom;
+			free(reply as _);
+			Ok(atom)
+		}
+	}
+
+	fn get_properties(&self, requests: &[(XWindow, XAtom)]) -> Vec<Result<Property, Error>> {
+		let cookies: Vec<_> = requests
+			.iter()
+			.map(|&(window, property)| unsafe {
+				xcb_get_property(self.connection, 0, window as u32, property as u32, 0, 0, PROPERTY_LENGTH)
+			})
+			.collect();
+		cookies.into_iter().map(|cookie| unsafe { self.property_reply(cookie) }).collect()
+	}
+
+	fn send_client_message(&self, window: XWindow, message_type: XAtom, data: [u32; 5]) -> Result<(), Error> {
+		let mut event = ClientMessageEvent {
+			response_type: CLIENT_MESSAGE,
+			format: 32,
+			sequence: 0,
+			window: window as u32,
+			typ: message_type as u32,
+			data,
+		};
+		unsafe {
+			let cookie = xcb_send_event_checked(
+				self.connection, 0, self.root() as u32,
+				(SubstructureRedirectMask | SubstructureNotifyMask) as u32,
+				&mut event as *mut ClientMessageEvent as *const c_char,
+			);
+			let error = xcb_request_check(self.connection, cookie);
+			if error.is_null() { Ok(()) } else { Err(take_error(error)) }
+		}
+	}
+
+	fn geometry(&self, window: XWindow) -> Result<Rect, Error> {
+		unsafe {
+			let geometry = xcb_get_geometry(self.connection, window as u32);
+			let position = xcb_translate_coordinates(self.connection, window as u32, self.root() as u32, 0, 0);
+			let mut error = null_mut();
+			let geometry = take_reply(xcb_get_geometry_reply(self.connection, geometry, &mut error), error);
+			let mut error = null_mut();
+			let position = take_reply(xcb_translate_coordinates_reply(self.connection, position, &mut error), error);
+			let (geometry, position) = match (geometry, position) {
+				(Ok(geometry), Ok(position)) => (geometry, position),
+				(geometry, position) => {
+					// Free whichever reply did arrive before returning the error of the other.
+					let geometry = geometry.map(|it| free(it as _));
+					let position = position.map(|it| free(it as _));
+					return Err(geometry.and(position).unwrap_err());
+				}
+			};
+			let rect = Rect::new(
+				(*position).dst_x as i32, (*position).dst_y as i32,
+				(*geometry).width as u32, (*geometry).height as u32,
+			);
+			free(geometry as _);
+			free(position as _);
+			Ok(rect)
+		}
+	}
+
+	fn get_image(&self, window: XWindow, rect: Rect) -> Result<RawImage, Error> {
+		unsafe {
+			let cookie = send_get_image(self.connection, window, rect);
+			let mut error = null_mut();
+			let reply = xcb_get_image_reply(self.connection, cookie, &mut error);
+			if reply.is_null() && error.is_null() {
+				return Err(Error::Capture(CaptureError::Null));
+			}
+			Ok(take_image(take_reply(reply, error)?, rect))
+		}
+	}
+
+	fn flush(&self) {
+		unsafe { xcb_flush(self.connection) };
+	}
+}
+
+/// A GetImage request that was sent without waiting for its reply, see [XcbBackend::request_image].
+#[cfg(feature = "async")]
+pub(crate) struct ImageRequest {
+	cookie: Cookie,
+	rect: Rect,
+}
+
+/// A GetGeometry request that was sent without waiting for its reply, see [XcbBackend::request_size].
+#[cfg(feature = "async")]
+pub(crate) struct SizeRequest {
+	cookie: Cookie,
+}
+
+#[cfg(feature = "async")]
+impl XcbBackend {
+	/// Sends a GetImage request for `rect` of `window` to the server, without waiting for the reply.
+	pub(crate) fn request_image(&self, window: XWindow, rect: Rect) -> ImageRequest {
+		let cookie = unsafe { send_get_image(self.connection, window, rect) };
+		unsafe { xcb_flush(self.connection) };
+		ImageRequest { cookie, rect }
+	}
+
+	/// The pixels of the request, `None` while the reply didn't arrive.
+	///
+	/// This reads what the connection has without blocking, wait for its descriptor to become readable otherwise.
+	pub(crate) fn poll_image(&self, request: &ImageRequest) -> Option<Result<RawImage, Error>> {
+		unsafe {
+			let reply = self.poll_reply::<GetImageReply>(request.cookie)?;
+			Some(reply.map(|it| take_image(it, request.rect)))
+		}
+	}
+
+	/// Sends a GetGeometry request for the size of `window` to the server, without waiting for the reply.
+	pub(crate) fn request_size(&self, window: XWindow) -> SizeRequest {
+		let cookie = unsafe { xcb_get_geometry(self.connection, window as u32) };
+		unsafe { xcb_flush(self.connection) };
+		SizeRequest { cookie }
+	}
+
+	/// The width and height of the window, `None` while the reply didn't arrive, see [Self::poll_image].
+	pub(crate) fn poll_size(&self, request: &SizeRequest) -> Option<Result<(u32, u32), Error>> {
+		unsafe {
+			let reply = self.poll_reply::<GetGeometryReply>(request.cookie)?;
+			Some(reply.map(|it| {
+				let size = ((*it).width as u32, (*it).height as u32);
+				free(it as _);
+				size
+			}))
+		}
+	}
+
+	/// Gives up on the request, XCB throws its reply away once it arrives.
+	pub(crate) fn discard_image(&self, request: ImageRequest) {
+		unsafe { xcb_discard_reply(self.connection, request.cookie.sequence) };
+	}
+
+	/// Gives up on the request, see [Self::discard_image].
+	pub(crate) fn discard_size(&self, request: SizeRequest) {
+		unsafe { xcb_discard_reply(self.connection, request.cookie.sequence) };
+	}
+
+	/// The reply of the request, `None` if it didn't arrive yet.
+	///
+	/// Every request is only polled until its reply was taken, XCB doesn't know it anymore afterwards.
+	unsafe fn poll_reply<T>(&self, cookie: Cookie) -> Option<Result<*mut T, Error>> {
+		let (mut reply, mut error) = (null_mut(), null_mut());
+		if xcb_poll_for_reply(self.connection, cookie.sequence, &mut reply, &mut error) == 0 {
+			return None;
+		}
+		Some(take_reply(reply as *mut T, error))
+	}
+}
+
+impl XcbBackend {
+	unsafe fn property_reply(&self, cookie: Cookie) -> Result<Property, Error> {
+		let mut error = null_mut();
+		let reply = xcb_get_property_reply(self.connection, cookie, &mut error);
+		let reply = take_reply(reply, error)?;
+		let value = xcb_get_property_value(reply) as *const u8;
+		let length = xcb_get_property_value_length(reply).max(0) as usize;
+		let property = Property {
+			typ: (*reply).typ as XAtom,
+			format: (*reply).format,
+			data: if value.is_null() { Vec::new() } else { slice::from_raw_parts(value, length).to_vec() },
+		};
+		free(reply as _);
+		Ok(property)
+	}
+}
+
+/// Sends the request for the pixels in `rect` of `window`, in the Z pixmap format.
+unsafe fn send_get_image(connection: *mut xcb_connection_t, window: XWindow, rect: Rect) -> Cookie {
+	xcb_get_image(
+		connection, Z_PIXMAP, window as u32, rect.x as i16, rect.y as i16,
+		rect.width as u16, rect.height as u16, u32::MAX,
+	)
+}
+
+/// Copies the pixels out of a GetImage reply for `rect` and frees it.
+unsafe fn take_image(reply: *mut GetImageReply, rect: Rect) -> RawImage {
+	let data = slice::from_raw_parts(xcb_get_image_data(reply), xcb_get_image_data_length(reply).max(0) as usize);
+	let image = RawImage { width: rect.width, height: rect.height, depth: (*reply).depth, data: data.to_vec() };
+	free(reply as _);
+	image
+}
+
+/// The reply if the request succeeded, otherwise the error is turned into [Error] and both are freed.
+unsafe fn take_reply<T>(reply: *mut T, error: *mut GenericError) -> Result<*mut T, Error> {
+	if !error.is_null() {
+		if !reply.is_null() {
+			free(reply as _);
+		}
+		return Err(take_error(error));
+	}
+	if reply.is_null() {
+		return Err(Error::RequestFailed(0));
+	}
+	Ok(reply)
+}
+
+/// Turns an error sent by the server into [Error], freeing it.
+unsafe fn take_error(error: *mut GenericError) -> Error {
+	let GenericError { error_code, major_code, minor_code, resource_id, full_sequence, .. } = *error;
+	free(error as _);
+	XError {
+		error_code,
+		request_code: major_code,
+		minor_code: minor_code as u8,
+		resource: resource_id as _,
+		serial: full_sequence as _,
+	}
+	.into()
+}
+
+const CLIENT_MESSAGE: u8 = 33;
+const Z_PIXMAP: u8 = 2;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Cookie {
+	sequence: c_uint,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct GenericError {
+	response_type: u8,
+	error_code: u8,
+	sequence: u16,
+	resource_id: u32,
+	minor_code: u16,
+	major_code: u8,
+	pad0: u8,
+	pad: [u32; 5],
+	full_sequence: u32,
+}
+
+#[repr(C)]
+struct InternAtomReply {
+	response_type: u8,
+	pad0: u8,
+	sequence: u16,
+	length: u32,
+	atom: u32,
+}
+
+#[repr(C)]
+struct GetPropertyReply {
+	response_type: u8,
+	format: u8,
+	sequence: u16,
+	length: u32,
+	typ: u32,
+	bytes_after: u32,
+	value_len: u32,
+	pad0: [u8; 12],
+}
+
+#[repr(C)]
+struct GetImageReply {
+	response_type: u8,
+	depth: u8,
+	sequence: u16,
+	length: u32,
+	visual: u32,
+	pad0: [u8; 20],
+}
+
+#[repr(C)]
+struct GetGeometryReply {
+	response_type: u8,
+	depth: u8,
+	sequence: u16,
+	length: u32,
+	root: u32,
+	x: i16,
+	y: i16,
+	width: u16,
+	height: u16,
+	border_width: u16,
+	pad0: [u8; 2],
+}
+
+#[repr(C)]
+struct TranslateCoordinatesReply {
+	response_type: u8,
+	same_screen: u8,
+	sequence: u16,
+	length: u32,
+	child: u32,
+	dst_x: i16,
+	dst_y: i16,
+}
+
+/// The 32 bytes of a ClientMessage event as they are sent to the server.
+#[repr(C)]
+struct ClientMessageEvent {
+	response_type: u8,
+	format: u8,
+	sequence: u16,
+	window: u32,
+	typ: u32,
+	data: [u32; 5],
+}
+
+#[link(name = "xcb")]
+extern "C" {
+	fn xcb_flush(c: *mut xcb_connection_t) -> c_int;
+	fn xcb_intern_atom(c: *mut xcb_connection_t, only_if_exists: u8, name_len: u16, name: *const c_char) -> Cookie;
+	fn xcb_intern_atom_reply(c: *mut xcb_connection_t, cookie: Cookie, e: *mut *mut GenericError) -> *mut InternAtomReply;
+	fn xcb_get_property(
+		c: *mut xcb_connection_t, delete: u8, window: u32, property: u32, typ: u32, long_offset: u32, long_length: u32,
+	) -> Cookie;
+	fn xcb_get_property_reply(c: *mut xcb_connection_t, cookie: Cookie, e: *mut *mut GenericError) -> *mut GetPropertyReply;
+	fn xcb_get_property_value(reply: *const GetPropertyReply) -> *mut c_void;
+	fn xcb_get_property_value_length(reply: *const GetPropertyReply) -> c_int;
+	fn xcb_send_event_checked(
+		c: *mut xcb_connection_t, propagate: u8, destination: u32, event_mask: u32, event: *const c_char,
+	) -> Cookie;
+	fn xcb_request_check(c: *mut xcb_connection_t, cookie: Cookie) -> *mut GenericError;
+	fn xcb_get_geometry(c: *mut xcb_connection_t, drawable: u32) -> Cookie;
+	fn xcb_get_geometry_reply(c: *mut xcb_connection_t, cookie: Cookie, e: *mut *mut GenericError) -> *mut GetGeometryReply;
+	fn xcb_translate_coordinates(
+		c: *mut xcb_connection_t, src_window: u32, dst_window: u32, src_x: i16, src_y: i16,
+	) -> Cookie;
+	fn xcb_translate_coordinates_reply(
+		c: *mut xcb_connection_t, cookie: Cookie, e: *mut *mut GenericError,
+	) -> *mut TranslateCoordinatesReply;
+	fn xcb_get_image(
+		c: *mut xcb_connection_t, format: u8, drawable: u32, x: i16, y: i16, width: u16, height: u16, plane_mask: u32,
+	) -> Cookie;
+	fn xcb_get_image_reply(c: *mut xcb_connection_t, cookie: Cookie, e: *mut *mut GenericError) -> *mut GetImageReply;
+	fn xcb_get_image_data(reply: *const GetImageReply) -> *mut u8;
+	fn xcb_get_image_data_length(reply: *const GetImageReply) -> c_int;
+	#[cfg(feature = "async")]
+	fn xcb_discard_reply(c: *mut xcb_connection_t, sequence: c_uint);
+	#[cfg(feature = "async")]
+	fn xcb_poll_for_reply(c: *mut xcb_connection_t, request: c_uint, reply: *mut *mut c_void, e: *mut *mut GenericError) -> c_int;
+}
@@ -0,0 +1,146 @@
+use std::{
+	ffi::CString,
+	os::raw::{c_long, c_short, c_void},
+	sync::Arc,
+	slice,
+};
+
+use x11::xlib::{
+	AnyPropertyType, ClientMessage, ClientMessageData, False, SubstructureNotifyMask, SubstructureRedirectMask,
+	True, XAllPlanes, XClientMessageEvent, XDefaultRootWindow, XDestroyImage, XEvent, XFlush, XFree, XGetImage,
+	XInternAtom, XSendEvent, ZPixmap, Atom as XAtom, Window as XWindow,
+};
+
+use crate::{
+	capture::CaptureError,
+	util::{get_window_property, GetWindowPropertyResponse},
+	Atom, Display, Error, Rect, Window,
+};
+
+use super::{Backend, Property, RawImage};
+
+/// Makes every request with Xlib on the display of the session, the default backend.
+pub struct XlibBackend {
+	display: Arc<Display>,
+}
+
+impl XlibBackend {
+	/// Uses the connection of `display`.
+	pub fn new(display: Arc<Display>) -> Self {
+		XlibBackend { display }
+	}
+}
+
+impl Backend for XlibBackend {
+	fn root(&self) -> XWindow {
+		unsafe { XDefaultRootWindow(self.display.as_raw()) }
+	}
+
+	fn intern_atom(&self, name: &str) -> Result<XAtom, Error> {
+		let name = CString::new(name).map_err(|_| Error::RequestFailed(0))?;
+		let (atom, errors) = self.display.with_error_trap(|| unsafe { XInternAtom(self.display.as_raw(), name.as_ptr(), True) });
+		match errors.first() {
+			Some(err) => Err((*err).into()),
+			None => Ok(atom),
+		}
+	}
+
+	fn get_properties(&self, requests: &[(XWindow, XAtom)]) -> Vec<Result<Property, Error>> {
+		requests.iter().map(|&(window, property)| self.read_property(window, property)).collect()
+	}
+
+	fn send_client_message(&self, window: XWindow, message_type: XAtom, data: [u32; 5]) -> Result<(), Error> {
+		let mut message = XClientMessageEvent {
+			type_: ClientMessage,
+			serial: 0,
+			send_event: True,
+			display: self.display.as_raw(),
+			window,
+			message_type,
+			format: 32,
+			data: ClientMessageData::new(),
+		};
+		for (i, value) in data.iter().enumerate() {
+			message.data.set_long(i, *value as c_long);
+		}
+		let mut event = XEvent { client_message: message };
+		let (_, errors) = self.display.with_error_trap(|| unsafe {
+			XSendEvent(
+				self.display.as_raw(), self.root(), False,
+				SubstructureRedirectMask | SubstructureNotifyMask, &mut event,
+			)
+		});
+		match errors.first() {
+			Some(err) => Err((*err).into()),
+			None => Ok(()),
+		}
+	}
+
+	fn geometry(&self, window: XWindow) -> Result<Rect, Error> {
+		let window = Window { window, display: Arc::clone(&self.display) };
+		let attr = window.try_get_attr()?;
+		let (x, y) = window.root_position()?;
+		Ok(Rect::new(x, y, attr.width as u32, attr.height as u32))
+	}
+
+	fn get_image(&self, window: XWindow, rect: Rect) -> Result<RawImage, Error> {
+		let (img, errors) = self.display.with_error_trap(|| unsafe {
+			XGetImage(self.display.as_raw(), window, rect.x, rect.y, rect.width, rect.height, XAllPlanes(), ZPixmap)
+		});
+		if let Some(err) = errors.first() {
+			return Err((*err).into());
+		}
+		if img.is_null() {
+			return Err(Error::Capture(CaptureError::Null));
+		}
+		let image = unsafe {
+			let raw = &*img;
+			let row = (raw.width * raw.bits_per_pixel / 8) as usize;
+			let mut data = Vec::with_capacity(row * raw.height as usize);
+			for y in 0..raw.height as usize {
+				let start = raw.data.add(y * raw.bytes_per_line as usize) as *const u8;
+				data.extend_from_slice(slice::from_raw_parts(start, row));
+			}
+			RawImage { width: raw.width as u32, height: raw.height as u32, depth: raw.depth as u8, data }
+		};
+		unsafe { XDestroyImage(img) };
+		Ok(image)
+	}
+
+	fn flush(&self) {
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+}
+
+impl XlibBackend {
+	/// Reads one property, Xlib hands out format 32 items as longs which are packed into 4 bytes here.
+	fn read_property(&self, window: XWindow, property: XAtom) -> Result<Property, Error> {
+		let window = Window { window, display: Arc::clone(&self.display) };
+		let GetWindowPropertyResponse {
+			actual_type_return: typ,
+			actual_format_return: format,
+			nitems_return: count,
+			proper_return: data,
+			..
+		} = unsafe { get_window_property(&self.display, window, Atom(property), AnyPropertyType as _)? };
+		if data.is_null() {
+			return Ok(Property { typ, format: format as u8, data: Vec::new() });
+		}
+		let count = count as usize;
+		let bytes = unsafe {
+			match format {
+				8 => slice::from_raw_parts(data, count).to_vec(),
+				16 => slice::from_raw_parts(data as *const c_short, count)
+					.iter()
+					.flat_map(|it| (*it as u16).to_ne_bytes())
+					.collect(),
+				_ => slice::from_raw_parts(data as *const c_long, count)
+					.iter()
+					.flat_map(|it| (*it as u32).to_ne_bytes())
+					.collect(),
+			}
+		};
+		unsafe { XFree(data as *mut c_void) };
+		Ok(Property { typ, format: format as u8, data: bytes })
+	}
+}
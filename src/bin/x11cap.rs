@@ -0,0 +1,80 @@
+//! A small screenshot tool built on top of x11_get_windows.
+//!
+//! ```text
+//! x11cap list
+//! x11cap shot [--window <title> | --monitor <n>] <out.png>
+//! ```
+
+use std::{env, process};
+
+use x11_get_windows::{Rect, Session, Window};
+use x11_get_windows::capture::{CaptureSession, Frame, PixelFormat};
+
+const USAGE: &str = "usage:
+	x11cap list
+	x11cap shot [--window <title> | --monitor <n>] <out.png>";
+
+fn main() {
+	let args = env::args().skip(1).collect::<Vec<_>>();
+	let session = Session::open()
+		.unwrap_or_else(|| fail("Could not open the display."));
+	match args.first().map(String::as_str) {
+		Some("list") => list(&session),
+		Some("shot") => shot(&session, &args[1..]),
+		_ => fail(USAGE),
+	}
+}
+
+fn fail(message: &str) -> ! {
+	eprintln!("{}", message);
+	process::exit(1)
+}
+
+fn list(session: &Session) {
+	let windows = session.get_windows()
+		.unwrap_or_else(|_| fail("The window manager does not support _NET_CLIENT_LIST."));
+	for window in windows.iter() {
+		let attr = window.get_attr();
+		let title = window.get_title()
+			.map(|it| it.as_ref().to_string_lossy().into_owned())
+			.unwrap_or_default();
+		println!("0x{:08x} {}x{} {:?}", window.window, attr.width, attr.height, title);
+	}
+}
+
+fn shot(session: &Session, args: &[String]) {
+	let (source, region, out) = match args {
+		[flag, title, out] if flag == "--window" => (find_window(session, title), None, out),
+		[flag, index, out] if flag == "--monitor" => {
+			let index = index.parse::<usize>()
+				.unwrap_or_else(|_| fail("The monitor has to be a number."));
+			let monitor = session.monitors()
+				.get(index)
+				.copied()
+				.unwrap_or_else(|| fail("There is no monitor with that number."));
+			(session.root().clone(), Some(monitor), out)
+		}
+		[out] => (session.root().clone(), None, out),
+		_ => fail(USAGE),
+	};
+	let frame = capture(source, region);
+	frame.save_png(out)
+		.unwrap_or_else(|err| fail(&format!("Could not write {}: {}", out, err)));
+}
+
+fn find_window(session: &Session, title: &str) -> Window {
+	session.get_windows()
+		.unwrap_or_else(|_| fail("The window manager does not support _NET_CLIENT_LIST."))
+		.inner()
+		.into_iter()
+		.find(|it| it.get_title().map(|it| it.as_ref().to_string_lossy().contains(title)).unwrap_or(false))
+		.unwrap_or_else(|| fail("There is no window with that title."))
+}
+
+fn capture(window: Window, region: Option<Rect>) -> Frame {
+	let mut session = CaptureSession::new(window);
+	session.set_region(region);
+	session.capture()
+		.and_then(|img| Frame::from_image(img, PixelFormat::Rgb))
+		.unwrap_or_else(|err| fail(&format!("Could not capture the window: {:?}", err)))
+}
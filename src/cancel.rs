@@ -0,0 +1,64 @@
+use std::{
+	sync::{Arc, Condvar, Mutex},
+	time::{Duration, Instant},
+};
+
+/// Aborts the blocking operations of a display from another thread, e.g. from a Ctrl+C handler.
+///
+/// Every display has one, get it with [crate::Session::cancel_token] and hand clones to other threads.
+/// Once cancelled, waits like [crate::Window::wait_until_stable], [crate::Session::spawn_and_wait_window],
+/// replays of [crate::actions::Actions], capture streams and the event loop of the display return early,
+/// and keep doing so until [Self::reset] is called.
+///
+/// # Example
+/// ```ignore
+/// let token = session.cancel_token();
+/// ctrlc::set_handler(move || token.cancel())?;
+/// for event in session.events() {
+///     println!("{:?}", event);
+/// }
+/// println!("Cancelled.");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+	state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancelToken {
+	/// A token that is not cancelled.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Cancels the operations that use this token, waking the ones that are waiting.
+	pub fn cancel(&self) {
+		let (cancelled, wake) = &*self.state;
+		*cancelled.lock().unwrap_or_else(|it| it.into_inner()) = true;
+		wake.notify_all();
+	}
+
+	/// Whether [Self::cancel] was called since the last [Self::reset].
+	pub fn is_cancelled(&self) -> bool {
+		*self.state.0.lock().unwrap_or_else(|it| it.into_inner())
+	}
+
+	/// Lets operations run again after a cancel.
+	pub fn reset(&self) {
+		*self.state.0.lock().unwrap_or_else(|it| it.into_inner()) = false;
+	}
+
+	/// Sleeps for `duration` unless cancelled before or during it, returns whether it was cancelled.
+	pub fn sleep(&self, duration: Duration) -> bool {
+		let deadline = Instant::now() + duration;
+		let (cancelled, wake) = &*self.state;
+		let mut guard = cancelled.lock().unwrap_or_else(|it| it.into_inner());
+		while !*guard {
+			let left = deadline.saturating_duration_since(Instant::now());
+			if left.is_zero() {
+				return false;
+			}
+			guard = wake.wait_timeout(guard, left).unwrap_or_else(|it| it.into_inner()).0;
+		}
+		true
+	}
+}
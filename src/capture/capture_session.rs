@@ -0,0 +1,258 @@
+use std::time::Instant;
+
+use x11::xlib::{BadWindow, XAllPlanes, XGetImage, XGetSubImage, ZPixmap};
+
+use crate::{Rect, Window, XImg};
+
+use super::{CaptureError, FrameRef, PixelFormat};
+
+/// Holds on to the image memory of a window between captures.
+///
+/// [Window::capture] allocates a new image (and frees it again) for every frame,
+/// a [CaptureSession] grabs every frame into the same memory and only reallocates
+/// when the size of the window changes.
+///
+/// When the `shm` feature is enabled and the X server supports the MIT-SHM extension,
+/// the image lives in a shared memory segment so the pixels don't have to be sent over the socket.
+///
+/// # Example
+/// ```ignore
+/// let mut capture = CaptureSession::new(window);
+/// loop {
+///     let frame = capture.capture().expect("Could not capture the window.");
+///     println!("{}x{}", frame.width(), frame.height());
+/// }
+/// ```
+pub struct CaptureSession {
+	window: Window,
+	source: Window,
+	decorations: bool,
+	region: Option<Rect>,
+	area: Rect,
+	img: Option<XImg>,
+	started: Option<Instant>,
+	#[cfg(feature = "shm")]
+	shm: Option<shm::Segment>,
+}
+
+impl CaptureSession {
+	/// Creates a capture session for the window, nothing is allocated until the first capture.
+	pub fn new(window: Window) -> Self {
+		Self {
+			source: window.clone(),
+			window,
+			decorations: false,
+			region: None,
+			area: Rect::default(),
+			img: None,
+			started: None,
+			#[cfg(feature = "shm")]
+			shm: None,
+		}
+	}
+
+	/// The window that is being captured.
+	pub fn window(&self) -> &Window {
+		&self.window
+	}
+
+	/// The size of the last captured frame.
+	pub fn size(&self) -> (u32, u32) {
+		(self.area.width, self.area.height)
+	}
+
+	/// The window the last frame was grabbed from.
+	///
+	/// This is not [Self::window] when decorations are captured, see [Self::set_decorations].
+	pub fn source(&self) -> &Window {
+		&self.source
+	}
+
+	/// The part of [Self::source] that was grabbed by the last capture.
+	pub fn area(&self) -> Rect {
+		self.area
+	}
+
+	/// Capture the title bar and borders around the window as well, see [Window::capture_decorated].
+	pub fn set_decorations(&mut self, decorations: bool) {
+		self.decorations = decorations;
+	}
+
+	/// Only capture the given part of the window, relative to its top left corner.
+	///
+	/// When decorations are captured the region is relative to the top left corner of the frame.
+	///
+	/// The region is clipped to the window, so it keeps working when the window is resized.
+	/// [None] captures the whole window again.
+	pub fn set_region(&mut self, region: Option<Rect>) {
+		self.region = region;
+	}
+
+	/// Grabs the current content of the window, reusing the memory of the previous frame.
+	///
+	/// Returns [CaptureError::BadWindow] if the window was closed, instead of ending the process.
+	pub fn capture(&mut self) -> Result<&XImg, CaptureError> {
+		let attr = self.window.try_get_attr().map_err(|_| CaptureError::BadWindow(self.window.window))?;
+		let (source, bounds) = if self.decorations {
+			self.window.decorated_area()
+		} else {
+			(self.window.clone(), Rect::new(0, 0, attr.width as u32, attr.height as u32))
+		};
+		// Intersecting also rejects areas that are empty.
+		let area = match self.region {
+			Some(region) => Rect { x: bounds.x + region.x, y: bounds.y + region.y, ..region }.intersect(&bounds),
+			None => bounds.intersect(&bounds),
+		}.ok_or(CaptureError::OutOfBounds)?;
+		if source.window != self.source.window || area.width != self.area.width || area.height != self.area.height {
+			self.release();
+		}
+		self.source = source;
+		self.area = area;
+		let Rect { x, y, width, height } = area;
+
+		#[cfg(feature = "shm")]
+		if self.img.is_none() {
+			let attr = self.source.get_attr();
+			if let Some((img, segment)) = unsafe { shm::Segment::create(&self.source, &attr, width, height) } {
+				self.img = Some(img);
+				self.shm = Some(segment);
+			}
+		}
+		#[cfg(feature = "shm")]
+		if self.shm.is_some() {
+			let img = self.img.as_ref().unwrap();
+			return unsafe { shm::get_image(&self.source, img, x, y) }.map(|()| img);
+		}
+
+		let display = self.source.display.as_raw();
+		// The window can still be closed between reading its attributes and grabbing it.
+		let (res, errors) = self.source.display.with_error_trap(|| match &self.img {
+			Some(img) => {
+				let res = unsafe { XGetSubImage(display, self.source.window, x, y, width, height, XAllPlanes(), ZPixmap, img.as_ptr(), 0, 0) };
+				if res.is_null() { Err(CaptureError::Null) } else { Ok(None) }
+			}
+			None => {
+				let img = unsafe { XGetImage(display, self.source.window, x, y, width, height, XAllPlanes(), ZPixmap) };
+				if img.is_null() { Err(CaptureError::Null) } else { Ok(Some(XImg { img })) }
+			}
+		});
+		if errors.iter().any(|it| it.error_code == BadWindow) {
+			return Err(CaptureError::BadWindow(self.source.window));
+		}
+		if let Some(img) = res? {
+			self.img = Some(img);
+		}
+		Ok(self.img.as_ref().unwrap())
+	}
+
+	/// Like [Self::capture], lending the pixels with their stride and timestamps to an encoder without copying them.
+	pub fn capture_frame(&mut self) -> Result<FrameRef<'_>, CaptureError> {
+		let time = Instant::now();
+		let started = *self.started.get_or_insert(time);
+		let img = self.capture()?;
+		Ok(FrameRef::new(img, time, time - started))
+	}
+
+	/// Grabs the current content of the window and converts it into `buf`.
+	///
+	/// See [Window::capture_into] for the requirements on `buf`.
+	pub fn capture_into(&mut self, buf: &mut [u8], format: PixelFormat) -> Result<(u32, u32), CaptureError> {
+		let img = self.capture()?;
+		img.convert_into(buf, format)?;
+		Ok((img.width(), img.height()))
+	}
+
+	/// Frees the image memory, the next capture will allocate it again.
+	fn release(&mut self) {
+		#[cfg(feature = "shm")]
+		if let (Some(img), Some(segment)) = (&self.img, self.shm.take()) {
+			unsafe { segment.detach(&self.source, img) };
+		}
+		self.img = None;
+	}
+}
+
+impl Drop for CaptureSession {
+	fn drop(&mut self) {
+		self.release();
+	}
+}
+
+#[cfg(feature = "shm")]
+mod shm {
+	use std::ptr::null_mut;
+
+	use x11::xlib::{BadWindow, False, XAllPlanes, XSync, XWindowAttributes, ZPixmap};
+	use x11::xshm::{XShmAttach, XShmCreateImage, XShmDetach, XShmGetImage, XShmQueryExtension, XShmSegmentInfo};
+
+	use crate::capture::CaptureError;
+	use crate::{Window, XImg};
+
+	/// A shared memory segment that is attached to the X server.
+	pub(super) struct Segment(XShmSegmentInfo);
+
+	impl Segment {
+		/// Creates an image backed by shared memory, returns [None] if MIT-SHM can't be used.
+		pub(super) unsafe fn create(window: &Window, attr: &XWindowAttributes, width: u32, height: u32) -> Option<(XImg, Segment)> {
+			let display = window.display.as_raw();
+			if XShmQueryExtension(display) == False {
+				return None;
+			}
+			let mut info = XShmSegmentInfo {
+				shmseg: 0,
+				shmid: -1,
+				shmaddr: null_mut(),
+				readOnly: False,
+			};
+			let img = XShmCreateImage(display, attr.visual, attr.depth as _, ZPixmap, null_mut(), &mut info, width, height);
+			if img.is_null() {
+				return None;
+			}
+			let img = XImg { img };
+			let len = img.as_ref().bytes_per_line as usize * img.as_ref().height as usize;
+			info.shmid = libc::shmget(libc::IPC_PRIVATE, len, libc::IPC_CREAT | 0o600);
+			if info.shmid < 0 {
+				return None;
+			}
+			info.shmaddr = libc::shmat(info.shmid, null_mut(), 0) as _;
+			// The segment is destroyed as soon as both sides have detached from it.
+			libc::shmctl(info.shmid, libc::IPC_RMID, null_mut());
+			if info.shmaddr as isize == -1 {
+				return None;
+			}
+			(*img.as_ptr()).data = info.shmaddr;
+			// Servers on another machine can't attach the segment and only answer with an error.
+			let (attached, errors) = window.display.with_error_trap(|| {
+				let attached = XShmAttach(display, &mut info) != False;
+				XSync(display, False);
+				attached
+			});
+			if !attached || !errors.is_empty() {
+				(*img.as_ptr()).data = null_mut();
+				libc::shmdt(info.shmaddr as _);
+				return None;
+			}
+			Some((img, Segment(info)))
+		}
+
+		/// Detaches the segment, the image must not be used for MIT-SHM afterwards.
+		pub(super) unsafe fn detach(mut self, window: &Window, img: &XImg) {
+			XShmDetach(window.display.as_raw(), &mut self.0);
+			XSync(window.display.as_raw(), False);
+			// XDestroyImage would otherwise try to free the shared memory.
+			(*img.as_ptr()).data = null_mut();
+			libc::shmdt(self.0.shmaddr as _);
+		}
+	}
+
+	/// Grabs the window into an image created by [Segment::create].
+	pub(super) unsafe fn get_image(window: &Window, img: &XImg, x: i32, y: i32) -> Result<(), CaptureError> {
+		let (res, errors) = window.display.with_error_trap(|| {
+			XShmGetImage(window.display.as_raw(), window.window, img.as_ptr(), x, y, XAllPlanes() as _) != False
+		});
+		if errors.iter().any(|it| it.error_code == BadWindow) {
+			return Err(CaptureError::BadWindow(window.window));
+		}
+		if res && errors.is_empty() { Ok(()) } else { Err(CaptureError::Null) }
+	}
+}
@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+use x11::xlib::IsViewable;
+
+use crate::{Rect, Window};
+
+use super::{CaptureError, Frame, PixelFormat};
+
+/// Captures the windows and draws them into one frame, see [crate::Session::capture_windows_composited].
+///
+/// The frame covers the area of all windows, pixels that no window covers are transparent.
+/// Without a compositor the windows are taken from the screen, where the parts that are covered by others are visible.
+pub(crate) fn capture_composited(root: &Window, windows: &[Window]) -> Result<Frame, CaptureError> {
+	// The children of the root are in stacking order from the bottom, the windows are drawn in the order of their frames.
+	let stacking: Vec<_> = root.children().unwrap_or_default().iter().map(|it| it.window).collect();
+	let mut layers = Vec::new();
+	for window in windows {
+		let attr = window.try_get_attr().map_err(|_| CaptureError::BadWindow(window.window))?;
+		let gone = |_| CaptureError::BadWindow(window.window);
+		let (x, y) = window.root_position().map_err(gone)?;
+		let area = Rect::new(x, y, attr.width as u32, attr.height as u32);
+		let frame = window.frame().map_err(gone)?.window;
+		let depth = stacking.iter().position(|it| *it == frame).unwrap_or(stacking.len());
+		layers.push((depth, area, window));
+	}
+	layers.sort_by_key(|(depth, ..)| *depth);
+	let bounds = layers.iter().map(|(_, area, _)| *area).reduce(|a, b| a.union(&b)).ok_or(CaptureError::OutOfBounds)?;
+
+	let from_screen = !root.display.compositor_active();
+	let screen = root.get_attr();
+	let screen = Rect::new(0, 0, screen.width as u32, screen.height as u32);
+	let format = PixelFormat::Bgra;
+	let mut data = vec![0; format.buffer_len(bounds.width, bounds.height)];
+	let mut pixels = Vec::new();
+	let mut drawn = false;
+	for (_, area, window) in layers {
+		// Windows that are not viewable, e.g. minimized ones, are left out.
+		let area = if from_screen {
+			match area.intersect(&screen) {
+				Some(visible) if window.try_get_attr().is_ok_and(|it| it.map_state == IsViewable) => {
+					pixels.resize(format.buffer_len(visible.width, visible.height), 0);
+					if grab_into(root, visible, &mut pixels, format).is_err() {
+						continue;
+					}
+					visible
+				}
+				_ => continue,
+			}
+		} else {
+			pixels.resize(format.buffer_len(area.width, area.height), 0);
+			if window.capture_into(&mut pixels, format).is_err() {
+				continue;
+			}
+			area
+		};
+		drawn = true;
+		let row = area.width as usize * 4;
+		let left = (area.x - bounds.x) as usize * 4;
+		for y in 0..area.height as usize {
+			let start = ((area.y - bounds.y) as usize + y) * bounds.width as usize * 4 + left;
+			data[start..start + row].copy_from_slice(&pixels[y * row..(y + 1) * row]);
+		}
+	}
+	if !drawn {
+		return Err(CaptureError::Null);
+	}
+	Ok(Frame { width: bounds.width, height: bounds.height, format, data, time: Instant::now() })
+}
+
+/// Grabs a part of the window into `buf`, like [Window::capture_into] for a region.
+fn grab_into(window: &Window, rect: Rect, buf: &mut [u8], format: PixelFormat) -> Result<(), CaptureError> {
+	window.get_image(rect)?.convert_into(buf, format)
+}
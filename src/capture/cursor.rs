@@ -0,0 +1,58 @@
+#[cfg(feature = "xfixes")]
+use std::slice;
+
+#[cfg(feature = "xfixes")]
+use x11::xfixes::XFixesGetCursorImage;
+#[cfg(feature = "xfixes")]
+use x11::xlib::XFree;
+
+use crate::Display;
+
+use super::Frame;
+
+/// Draws the mouse cursor on top of a frame whose top left corner is at `origin` on the root window.
+///
+/// Does nothing if the cursor is outside of the frame or the cursor image can't be fetched.
+#[cfg(feature = "xfixes")]
+pub(crate) fn draw_cursor(display: &Display, origin: (i32, i32), frame: &mut Frame) {
+	let cursor = unsafe { XFixesGetCursorImage(display.as_raw()) };
+	if cursor.is_null() {
+		return;
+	}
+	let image = unsafe { &*cursor };
+	let left = image.x as i32 - image.xhot as i32 - origin.0;
+	let top = image.y as i32 - image.yhot as i32 - origin.1;
+	let (width, height) = (image.width as i32, image.height as i32);
+	let pixels = unsafe { slice::from_raw_parts(image.pixels, (width * height) as usize) };
+	let bpp = frame.format.bytes_per_pixel();
+
+	for cy in 0..height {
+		let y = top + cy;
+		if y < 0 || y >= frame.height as i32 {
+			continue;
+		}
+		for cx in 0..width {
+			let x = left + cx;
+			if x < 0 || x >= frame.width as i32 {
+				continue;
+			}
+			// Premultiplied ARGB, stored in the low 32 bits.
+			let argb = pixels[(cy * width + cx) as usize] as u32;
+			let a = argb >> 24;
+			if a == 0 {
+				continue;
+			}
+			let src = ((argb >> 16) & 0xff, (argb >> 8) & 0xff, argb & 0xff);
+			let offset = (y as usize * frame.width as usize + x as usize) * bpp;
+			let out = &mut frame.data[offset..offset + bpp];
+			let (r, g, b) = frame.format.read(out);
+			let blend = |s: u32, d: u8| (s + d as u32 * (255 - a) / 255).min(255) as u8;
+			frame.format.write(out, blend(src.0, r), blend(src.1, g), blend(src.2, b), 255);
+		}
+	}
+	unsafe { XFree(cursor as _) };
+}
+
+/// The cursor image can only be fetched with XFixes.
+#[cfg(not(feature = "xfixes"))]
+pub(crate) fn draw_cursor(_display: &Display, _origin: (i32, i32), _frame: &mut Frame) {}
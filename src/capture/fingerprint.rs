@@ -0,0 +1,122 @@
+use crate::XImg;
+
+/// The characters of [XImg::fingerprint], from dark to bright.
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+impl XImg {
+	/// A 64 bit hash that stays the same when the image only changes a little, the "aHash".
+	///
+	/// Every bit is one cell of an 8x8 grid, set if the cell is brighter than the whole image.
+	/// Compare two hashes with [hash_distance].
+	pub fn average_hash(&self) -> u64 {
+		let cells = gray_grid(self, 8, 8);
+		let mean = (cells.iter().map(|it| *it as u32).sum::<u32>() / 64) as u8;
+		cells.iter().enumerate().fold(0, |hash, (i, it)| if *it > mean { hash | 1 << i } else { hash })
+	}
+
+	/// A 64 bit hash of the gradients of the image, the "dHash".
+	///
+	/// Every bit tells whether a cell of a 9x8 grid is brighter than its right neighbour,
+	/// which is less affected by changes of the brightness or contrast than [Self::average_hash].
+	/// Compare two hashes with [hash_distance].
+	///
+	/// # Example
+	/// ```ignore
+	/// let before = window.capture()?.perceptual_hash();
+	/// window.click(10, 10);
+	/// let changed = hash_distance(before, window.capture()?.perceptual_hash()) > 5;
+	/// ```
+	pub fn perceptual_hash(&self) -> u64 {
+		let cells = gray_grid(self, 9, 8);
+		(0..8)
+			.flat_map(|y| (0..8).map(move |x| (y, x)))
+			.enumerate()
+			.fold(0, |hash, (i, (y, x))| if cells[y * 9 + x] > cells[y * 9 + x + 1] { hash | 1 << i } else { hash })
+	}
+
+	/// A small picture of the image in text, 16 characters wide and 8 lines high, for logs and test failures.
+	///
+	/// Every character shows the brightness of a part of the image, from ` ` for black to `@` for white.
+	pub fn fingerprint(&self) -> String {
+		let cells = gray_grid(self, 16, 8);
+		cells
+			.chunks(16)
+			.map(|row| {
+				let mut line = row.iter().map(|it| SHADES[*it as usize * (SHADES.len() - 1) / 255] as char).collect::<String>();
+				line.push('\n');
+				line
+			})
+			.collect()
+	}
+}
+
+/// The number of bits two hashes differ in, 0 for images that look the same and up to 64.
+///
+/// Images that differ in less than about 5 bits usually look alike.
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+	(a ^ b).count_ones()
+}
+
+/// Shrinks the image into a grid of gray cells, every cell is the average of the pixels it covers.
+fn gray_grid(img: &XImg, cols: usize, rows: usize) -> Vec<u8> {
+	let (width, height) = (img.width() as usize, img.height() as usize);
+	let mut cells = vec![0; cols * rows];
+	if width == 0 || height == 0 {
+		return cells;
+	}
+	let pixels = &**img;
+	for (i, cell) in cells.iter_mut().enumerate() {
+		let (col, row) = (i % cols, i / cols);
+		let (left, right) = (col * width / cols, ((col + 1) * width / cols).max(col * width / cols + 1).min(width));
+		let (top, bottom) = (row * height / rows, ((row + 1) * height / rows).max(row * height / rows + 1).min(height));
+		let mut sum = 0u64;
+		for y in top..bottom {
+			sum += pixels[y * width + left..y * width + right].iter().map(|it| it.grayscale() as u64).sum::<u64>();
+		}
+		let count = ((bottom - top) * (right - left)).max(1) as u64;
+		*cell = (sum / count) as u8;
+	}
+	cells
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// White on the left half and black on the right.
+	fn split_image() -> XImg {
+		XImg::from_fn(16, 8, |x, _| if x < 8 { (255, 255, 255) } else { (0, 0, 0) })
+	}
+
+	#[test]
+	fn average_hash_sets_the_bright_cells() {
+		assert_eq!(split_image().average_hash(), 0x0f0f_0f0f_0f0f_0f0f);
+		assert_eq!(XImg::from_fn(16, 8, |_, _| (90, 90, 90)).average_hash(), 0);
+	}
+
+	#[test]
+	fn perceptual_hash_sets_the_edges() {
+		assert_eq!(split_image().perceptual_hash(), 0x1010_1010_1010_1010);
+		assert_eq!(XImg::from_fn(16, 8, |_, _| (90, 90, 90)).perceptual_hash(), 0);
+	}
+
+	#[test]
+	fn perceptual_hash_ignores_small_changes() {
+		let before = split_image().perceptual_hash();
+		let after = XImg::from_fn(16, 8, |x, y| if x < 8 || (x, y) == (15, 0) { (255, 255, 255) } else { (0, 0, 0) }).perceptual_hash();
+		assert!(hash_distance(before, after) <= 1);
+		assert_eq!(hash_distance(0, u64::MAX), 64);
+	}
+
+	#[test]
+	fn fingerprint_shades_the_cells() {
+		let fingerprint = split_image().fingerprint();
+		assert_eq!(fingerprint.lines().count(), 8);
+		assert!(fingerprint.lines().all(|it| it == "@@@@@@@@        "));
+	}
+
+	#[test]
+	fn empty_images_hash_to_zero() {
+		assert_eq!(XImg::from_fn(0, 0, |_, _| (0, 0, 0)).perceptual_hash(), 0);
+	}
+}
@@ -0,0 +1,213 @@
+use std::{
+	fmt,
+	os::raw::c_ulong,
+};
+
+use x11::xlib::{LSBFirst, Window as XWindow, XGetPixel, XImage};
+
+/// The layout of the pixels written by [crate::Window::capture_into].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+	/// 4 bytes per pixel, in the order blue, green, red, alpha.
+	Bgra,
+	/// 4 bytes per pixel, in the order red, green, blue, alpha.
+	Rgba,
+	/// 3 bytes per pixel, in the order blue, green, red.
+	Bgr,
+	/// 3 bytes per pixel, in the order red, green, blue.
+	Rgb,
+	/// 1 byte per pixel, see [crate::XColor::grayscale] for the weights used.
+	Gray,
+}
+
+impl PixelFormat {
+	/// Number of bytes a single pixel takes in this format.
+	#[inline]
+	pub fn bytes_per_pixel(&self) -> usize {
+		match self {
+			PixelFormat::Bgra | PixelFormat::Rgba => 4,
+			PixelFormat::Bgr | PixelFormat::Rgb => 3,
+			PixelFormat::Gray => 1,
+		}
+	}
+
+	/// Number of bytes needed to hold an image of the given size in this format.
+	#[inline]
+	pub fn buffer_len(&self, width: u32, height: u32) -> usize {
+		width as usize * height as usize * self.bytes_per_pixel()
+	}
+
+	#[inline]
+	pub(crate) fn write(&self, out: &mut [u8], r: u8, g: u8, b: u8, a: u8) {
+		match self {
+			PixelFormat::Bgra => out.copy_from_slice(&[b, g, r, a]),
+			PixelFormat::Rgba => out.copy_from_slice(&[r, g, b, a]),
+			PixelFormat::Bgr => out.copy_from_slice(&[b, g, r]),
+			PixelFormat::Rgb => out.copy_from_slice(&[r, g, b]),
+			PixelFormat::Gray => {
+				let (r, g, b) = (r as u32, g as u32, b as u32);
+				out[0] = (((19595 * r + 38470 * g + 7471 * b) + (1 << 15)) >> 16) as u8;
+			}
+		}
+	}
+
+	/// Reads a pixel written by [PixelFormat::write] back as red, green and blue.
+	#[inline]
+	#[cfg_attr(not(feature = "xfixes"), allow(dead_code))]
+	pub(crate) fn read(&self, px: &[u8]) -> (u8, u8, u8) {
+		match self {
+			PixelFormat::Bgra | PixelFormat::Bgr => (px[2], px[1], px[0]),
+			PixelFormat::Rgba | PixelFormat::Rgb => (px[0], px[1], px[2]),
+			PixelFormat::Gray => (px[0], px[0], px[0]),
+		}
+	}
+}
+
+/// An error that happened while capturing a window into a buffer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CaptureError {
+	/// [x11::xlib::XGetImage] returned a null pointer,
+	/// which happens when the window is not viewable or is off screen.
+	Null,
+	/// The provided buffer is too small, the required length in bytes is given.
+	BufferTooSmall(usize),
+	/// The requested region does not overlap with the window.
+	OutOfBounds,
+	/// A new connection to the X server, needed to capture on another thread, could not be opened.
+	DisplayOpenFailed,
+	/// The window doesn't exist anymore.
+	BadWindow(XWindow),
+}
+
+impl fmt::Display for CaptureError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			CaptureError::Null => write!(f, "the window is not viewable or is off screen"),
+			CaptureError::BufferTooSmall(len) => write!(f, "the buffer is too small, {} bytes are needed", len),
+			CaptureError::OutOfBounds => write!(f, "the region is outside of the window"),
+			CaptureError::DisplayOpenFailed => write!(f, "could not open a connection to the X server"),
+			CaptureError::BadWindow(window) => write!(f, "the window {:#x} does not exist", window),
+		}
+	}
+}
+
+impl std::error::Error for CaptureError {}
+
+/// Where a single colour channel lives inside a pixel value.
+struct Channel {
+	mask: c_ulong,
+	shift: u32,
+	max: c_ulong,
+}
+
+impl Channel {
+	fn new(mask: c_ulong) -> Self {
+		let shift = if mask == 0 { 0 } else { mask.trailing_zeros() };
+		Channel { mask, shift, max: mask >> shift }
+	}
+
+	#[inline]
+	fn get(&self, pixel: c_ulong) -> u8 {
+		let value = (pixel & self.mask) >> self.shift;
+		match self.max {
+			0 => 0,
+			255 => value as u8,
+			max => (value * 255 / max) as u8,
+		}
+	}
+}
+
+/// The format the pixels of the image are already in, [PixelFormat::Bgra] for the usual 24 bit visual.
+///
+/// The fourth byte of every pixel is padding there, not an alpha channel.
+pub(crate) fn native_format(image: &XImage) -> Option<PixelFormat> {
+	let bgra = image.bits_per_pixel == 32
+		&& image.byte_order == LSBFirst
+		&& image.red_mask == 0xff0000
+		&& image.green_mask == 0xff00
+		&& image.blue_mask == 0xff;
+	if bgra { Some(PixelFormat::Bgra) } else { None }
+}
+
+/// Converts an image into `buf` using the requested format.
+///
+/// Rows are written tightly packed, without any padding, starting at the beginning of `buf`.
+///
+/// # Safety
+/// `img` has to point to a valid image, e.g. one returned by [x11::xlib::XGetImage].
+pub(crate) unsafe fn convert_into(img: *mut XImage, buf: &mut [u8], format: PixelFormat) -> Result<(), CaptureError> {
+	convert_into_with_alpha(img, buf, format, 0)
+}
+
+/// Like [convert_into], but also reads the alpha channel from the bits in `alpha_mask`.
+///
+/// With an `alpha_mask` of 0 every pixel is opaque.
+/// The colours of windows with an alpha channel are premultiplied, they are written un-premultiplied.
+///
+/// # Safety
+/// `img` has to point to a valid image, e.g. one returned by [x11::xlib::XGetImage].
+pub(crate) unsafe fn convert_into_with_alpha(img: *mut XImage, buf: &mut [u8], format: PixelFormat, alpha_mask: c_ulong) -> Result<(), CaptureError> {
+	let image = &*img;
+	let width = image.width as usize;
+	let height = image.height as usize;
+	let bpp = format.bytes_per_pixel();
+	let required = width * height * bpp;
+	if buf.len() < required {
+		return Err(CaptureError::BufferTooSmall(required));
+	}
+	// A window can be 0 pixels wide or high, there are no rows to write then.
+	if width == 0 || height == 0 {
+		return Ok(());
+	}
+	let (r, g, b) = (Channel::new(image.red_mask), Channel::new(image.green_mask), Channel::new(image.blue_mask));
+	let alpha = Channel::new(alpha_mask);
+	let write = |out: &mut [u8], pixel: c_ulong| {
+		if alpha_mask == 0 {
+			return format.write(out, r.get(pixel), g.get(pixel), b.get(pixel), 255);
+		}
+		let a = alpha.get(pixel);
+		let straight = |c: u8| if a == 0 { 0 } else { (c as u32 * 255 / a as u32).min(255) as u8 };
+		format.write(out, straight(r.get(pixel)), straight(g.get(pixel)), straight(b.get(pixel)), a);
+	};
+
+	for (y, row) in buf[..required].chunks_exact_mut(width * bpp).enumerate() {
+		if image.bits_per_pixel == 32 {
+			let line = (image.data as *const u8).add(y * image.bytes_per_line as usize);
+			let line = std::slice::from_raw_parts(line, width * 4);
+			for (src, out) in line.chunks_exact(4).zip(row.chunks_exact_mut(bpp)) {
+				let src = [src[0], src[1], src[2], src[3]];
+				let pixel = if image.byte_order == LSBFirst { u32::from_le_bytes(src) } else { u32::from_be_bytes(src) } as c_ulong;
+				write(out, pixel);
+			}
+		} else {
+			for (x, out) in row.chunks_exact_mut(bpp).enumerate() {
+				write(out, XGetPixel(img, x as _, y as _));
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::XImg;
+
+	use super::*;
+
+	#[test]
+	fn convert_into_writes_every_format() {
+		let img = XImg::from_fn(2, 1, |x, _| if x == 0 { (10, 20, 30) } else { (200, 100, 0) });
+		let mut buf = [0; 8];
+		assert_eq!(img.convert_into(&mut buf, PixelFormat::Rgba), Ok(()));
+		assert_eq!(buf, [10, 20, 30, 255, 200, 100, 0, 255]);
+		assert_eq!(img.convert_into(&mut buf[..6], PixelFormat::Bgr), Ok(()));
+		assert_eq!(buf[..6], [30, 20, 10, 0, 100, 200]);
+		assert_eq!(img.convert_into(&mut buf[..5], PixelFormat::Bgra), Err(CaptureError::BufferTooSmall(8)));
+	}
+
+	#[test]
+	fn convert_into_accepts_empty_images() {
+		assert_eq!(XImg::from_fn(0, 3, |_, _| (0, 0, 0)).convert_into(&mut [], PixelFormat::Rgba), Ok(()));
+		assert_eq!(XImg::from_fn(3, 0, |_, _| (0, 0, 0)).convert_into(&mut [], PixelFormat::Gray), Ok(()));
+	}
+}
@@ -0,0 +1,179 @@
+use std::{
+	slice,
+	time::{Duration, Instant},
+};
+
+use crate::XImg;
+#[cfg(feature = "async")]
+use crate::backend::RawImage;
+
+use super::{format::native_format, CaptureError, PixelFormat};
+
+/// A captured image that owns its pixels.
+///
+/// Rows are tightly packed, so `data` is exactly [PixelFormat::buffer_len] bytes long.
+#[derive(Clone, Debug)]
+pub struct Frame {
+	/// Width of the image in pixels.
+	pub width: u32,
+	/// Height of the image in pixels.
+	pub height: u32,
+	/// The layout of the pixels in `data`.
+	pub format: PixelFormat,
+	/// The pixels of the image.
+	pub data: Vec<u8>,
+	/// When the image was grabbed.
+	pub time: Instant,
+}
+
+impl Frame {
+	/// Copies an image into a new frame, converting it to `format`.
+	pub fn from_image(img: &XImg, format: PixelFormat) -> Result<Self, CaptureError> {
+		let mut data = vec![0; format.buffer_len(img.width(), img.height())];
+		img.convert_into(&mut data, format)?;
+		Ok(Frame {
+			width: img.width(),
+			height: img.height(),
+			format,
+			data,
+			time: Instant::now(),
+		})
+	}
+
+	/// Copies the pixels of a [crate::backend::Backend::get_image] reply into a new frame, converting them to `format`.
+	///
+	/// Only the 4 bytes per pixel of windows with a depth of 24 or 32 bits are understood,
+	/// others fail with [CaptureError::Null]. The fourth byte is padding, every pixel is opaque.
+	#[cfg(feature = "async")]
+	pub(crate) fn from_raw(raw: &RawImage, format: PixelFormat) -> Result<Self, CaptureError> {
+		let pixels = raw.width as usize * raw.height as usize;
+		if !matches!(raw.depth, 24 | 32) || raw.data.len() < pixels * 4 {
+			return Err(CaptureError::Null);
+		}
+		let mut data = vec![0; format.buffer_len(raw.width, raw.height)];
+		for (src, out) in raw.data.chunks_exact(4).zip(data.chunks_exact_mut(format.bytes_per_pixel())) {
+			format.write(out, src[2], src[1], src[0], 255);
+		}
+		Ok(Frame {
+			width: raw.width,
+			height: raw.height,
+			format,
+			data,
+			time: Instant::now(),
+		})
+	}
+
+	/// Scales the frame down to `width` pixels, keeping the aspect ratio.
+	///
+	/// Every pixel is the average of the pixels it covers. Frames that are not wider than `width` are copied.
+	pub fn scale_to_width(&self, width: u32) -> Frame {
+		if width == 0 || self.width <= width {
+			return self.clone();
+		}
+		let height = ((self.height as u64 * width as u64) / self.width as u64).max(1) as u32;
+		let bpp = self.format.bytes_per_pixel();
+		let mut data = vec![0; self.format.buffer_len(width, height)];
+		let mut sums = vec![0u32; bpp];
+		for y in 0..height {
+			let (top, bottom) = (y * self.height / height, ((y + 1) * self.height / height).max(y * self.height / height + 1));
+			for x in 0..width {
+				let (left, right) = (x * self.width / width, ((x + 1) * self.width / width).max(x * self.width / width + 1));
+				sums.iter_mut().for_each(|it| *it = 0);
+				for sy in top..bottom {
+					let row = sy as usize * self.width as usize;
+					for sx in left..right {
+						let at = (row + sx as usize) * bpp;
+						sums.iter_mut().zip(&self.data[at..at + bpp]).for_each(|(sum, it)| *sum += *it as u32);
+					}
+				}
+				let count = (bottom - top) * (right - left);
+				let at = (y as usize * width as usize + x as usize) * bpp;
+				data[at..at + bpp].iter_mut().zip(&sums).for_each(|(it, sum)| *it = (sum / count) as u8);
+			}
+		}
+		Frame {
+			width,
+			height,
+			format: self.format,
+			data,
+			time: self.time,
+		}
+	}
+}
+
+/// A frame of a [super::CaptureSession] that lends the image memory of the session instead of copying it.
+///
+/// The pixels stay valid until the next capture of the session, which can't happen while the frame is borrowed.
+/// Rows are [Self::stride] bytes apart and may be padded, hand [Self::as_planes] to an encoder as is.
+///
+/// # Example
+/// ```ignore
+/// let mut capture = CaptureSession::new(window);
+/// loop {
+///     let frame = capture.capture_frame()?;
+///     let [plane] = frame.as_planes();
+///     encoder.encode_bgra(plane.data, plane.stride, frame.width, frame.height, frame.timestamp)?;
+/// }
+/// ```
+#[derive(Copy, Clone)]
+pub struct FrameRef<'a> {
+	/// Width of the image in pixels.
+	pub width: u32,
+	/// Height of the image in pixels.
+	pub height: u32,
+	/// The layout of the pixels, [None] for visuals other than the usual 24 bit one,
+	/// use [Self::to_frame] to convert those.
+	pub format: Option<PixelFormat>,
+	/// When the image was grabbed.
+	pub time: Instant,
+	/// How long after the first frame of the session the image was grabbed, e.g. for the timestamps of a video.
+	pub timestamp: Duration,
+	image: &'a XImg,
+	plane: Plane<'a>,
+}
+
+/// The pixels of one plane of a [FrameRef].
+#[derive(Copy, Clone, Debug)]
+pub struct Plane<'a> {
+	/// The rows of the plane, `stride` bytes apart.
+	pub data: &'a [u8],
+	/// Number of bytes from the start of one row to the next.
+	pub stride: usize,
+}
+
+impl<'a> FrameRef<'a> {
+	pub(crate) fn new(image: &'a XImg, time: Instant, timestamp: Duration) -> Self {
+		let raw = image.as_ref();
+		let stride = raw.bytes_per_line as usize;
+		let data = unsafe { slice::from_raw_parts(raw.data as *const u8, stride * raw.height as usize) };
+		FrameRef {
+			width: image.width(),
+			height: image.height(),
+			format: native_format(raw),
+			time,
+			timestamp,
+			image,
+			plane: Plane { data, stride },
+		}
+	}
+
+	/// The planes of the image, captures are always a single plane of packed pixels.
+	pub fn as_planes(&self) -> [Plane<'a>; 1] {
+		[self.plane]
+	}
+
+	/// Number of bytes from the start of one row to the next.
+	pub fn stride(&self) -> usize {
+		self.plane.stride
+	}
+
+	/// The image the frame lends, e.g. for [XImg::to_nv12].
+	pub fn image(&self) -> &'a XImg {
+		self.image
+	}
+
+	/// Copies the frame into an owned [Frame], converting it to `format`.
+	pub fn to_frame(&self, format: PixelFormat) -> Result<Frame, CaptureError> {
+		Ok(Frame { time: self.time, ..Frame::from_image(self.image, format)? })
+	}
+}
@@ -0,0 +1,172 @@
+use crate::XImg;
+
+use super::PixelFormat;
+
+/// A black and white image, usually made from a capture with [XImg::threshold].
+///
+/// Every pixel takes a byte that is either 0 or 255, use [Mask::to_bits] for 1 bit per pixel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+	/// Width of the mask in pixels.
+	pub width: u32,
+	/// Height of the mask in pixels.
+	pub height: u32,
+	/// One byte for every pixel, rows are tightly packed.
+	pub data: Vec<u8>,
+}
+
+impl Mask {
+	/// Whether the pixel is set, pixels outside of the mask are never set.
+	pub fn get(&self, x: u32, y: u32) -> bool {
+		x < self.width && y < self.height && self.data[(y * self.width + x) as usize] != 0
+	}
+
+	/// Packs the mask into 1 bit per pixel, most significant bit first.
+	///
+	/// Every row starts on a new byte, so a row takes `(width + 7) / 8` bytes.
+	pub fn to_bits(&self) -> Vec<u8> {
+		let stride = (self.width as usize).div_ceil(8);
+		let mut bits = vec![0; stride * self.height as usize];
+		for (row, out) in self.data.chunks_exact(self.width.max(1) as usize).zip(bits.chunks_exact_mut(stride.max(1))) {
+			for (x, _) in row.iter().enumerate().filter(|(_, it)| **it != 0) {
+				out[x / 8] |= 0x80 >> (x % 8);
+			}
+		}
+		bits
+	}
+
+	/// Swaps set and unset pixels, e.g. to turn light text on a dark background into dark text.
+	pub fn invert(&mut self) {
+		self.data.iter_mut().for_each(|it| *it = !*it);
+	}
+}
+
+impl XImg {
+	/// Converts the image to gray scale, one byte for every pixel, see [crate::XColor::grayscale].
+	pub fn to_gray(&self) -> Vec<u8> {
+		let mut gray = vec![0; PixelFormat::Gray.buffer_len(self.width(), self.height())];
+		// The buffer always has the right size.
+		let _ = self.convert_into(&mut gray, PixelFormat::Gray);
+		gray
+	}
+
+	/// Sets every pixel that is brighter than `level`.
+	pub fn threshold(&self, level: u8) -> Mask {
+		self.mask(self.to_gray().into_iter().map(|it| it > level))
+	}
+
+	/// Picks the level for [Self::threshold] that best separates the foreground from the background.
+	///
+	/// This uses Otsu's method, which works well when the image has two distinct brightness levels,
+	/// like text on a plain background.
+	pub fn otsu_level(&self) -> u8 {
+		let gray = self.to_gray();
+		let mut histogram = [0u64; 256];
+		gray.iter().for_each(|it| histogram[*it as usize] += 1);
+		let total = gray.len() as f64;
+		let sum = histogram.iter().enumerate().map(|(i, n)| i as f64 * *n as f64).sum::<f64>();
+
+		let (mut best, mut best_variance) = (0, 0.0);
+		let (mut background, mut background_sum) = (0.0, 0.0);
+		for (level, count) in histogram.iter().enumerate() {
+			background += *count as f64;
+			background_sum += level as f64 * *count as f64;
+			let foreground = total - background;
+			if background == 0.0 || foreground == 0.0 {
+				continue;
+			}
+			let difference = background_sum / background - (sum - background_sum) / foreground;
+			let variance = background * foreground * difference * difference;
+			if variance > best_variance {
+				best = level as u8;
+				best_variance = variance;
+			}
+		}
+		best
+	}
+
+	/// Sets every pixel that is brighter than the average of its surroundings minus `offset`.
+	///
+	/// The surroundings are the pixels at most `radius` pixels away horizontally and vertically.
+	/// Unlike [Self::threshold] this copes with uneven backgrounds, like gradients or shadows.
+	pub fn adaptive_threshold(&self, radius: u32, offset: i32) -> Mask {
+		let gray = self.to_gray();
+		let (width, height) = (self.width() as usize, self.height() as usize);
+
+		// Summed area table with an extra row and column of zeros at the top and left.
+		let mut sums = vec![0u64; (width + 1) * (height + 1)];
+		for y in 0..height {
+			let mut row = 0;
+			for x in 0..width {
+				row += gray[y * width + x] as u64;
+				sums[(y + 1) * (width + 1) + x + 1] = sums[y * (width + 1) + x + 1] + row;
+			}
+		}
+
+		let radius = radius as usize;
+		self.mask((0..width * height).map(|i| {
+			let (x, y) = (i % width, i / width);
+			let (left, top) = (x.saturating_sub(radius), y.saturating_sub(radius));
+			let (right, bottom) = ((x + radius + 1).min(width), (y + radius + 1).min(height));
+			let area = ((right - left) * (bottom - top)) as u64;
+			let sum = sums[bottom * (width + 1) + right] + sums[top * (width + 1) + left]
+				- sums[top * (width + 1) + right] - sums[bottom * (width + 1) + left];
+			gray[i] as i64 > (sum / area) as i64 - offset as i64
+		}))
+	}
+
+	fn mask(&self, pixels: impl Iterator<Item=bool>) -> Mask {
+		Mask {
+			width: self.width(),
+			height: self.height(),
+			data: pixels.map(|it| if it { 255 } else { 0 }).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Light text on the left half on a dark background.
+	fn two_levels() -> XImg {
+		XImg::from_fn(4, 2, |x, _| if x < 2 { (200, 200, 200) } else { (40, 40, 40) })
+	}
+
+	#[test]
+	fn threshold_sets_brighter_pixels() {
+		let mask = two_levels().threshold(100);
+		assert_eq!(mask.data, vec![255, 255, 0, 0, 255, 255, 0, 0]);
+		assert!(mask.get(1, 1));
+		assert!(!mask.get(2, 0));
+		assert!(!mask.get(4, 0));
+	}
+
+	#[test]
+	fn otsu_level_separates_two_levels() {
+		let img = two_levels();
+		let level = img.otsu_level();
+		assert!((40..200).contains(&level));
+		assert_eq!(img.threshold(level), img.threshold(100));
+	}
+
+	#[test]
+	fn adaptive_threshold_copes_with_gradients() {
+		// A dot on every other pixel, on a background that gets brighter to the right.
+		let img = XImg::from_fn(8, 1, |x, _| {
+			let background = 20 + x as u8 * 25;
+			if x % 2 == 0 { (background + 30, background + 30, background + 30) } else { (background, background, background) }
+		});
+		let mask = img.adaptive_threshold(1, 0);
+		assert_eq!(mask.data.iter().step_by(2).filter(|it| **it != 0).count(), 4);
+		assert!(img.threshold(100).data.iter().step_by(2).any(|it| *it == 0));
+	}
+
+	#[test]
+	fn bits_are_packed_most_significant_first() {
+		let mut mask = Mask { width: 10, height: 1, data: vec![255, 0, 0, 0, 0, 0, 0, 0, 0, 255] };
+		assert_eq!(mask.to_bits(), vec![0x80, 0x40]);
+		mask.invert();
+		assert_eq!(mask.to_bits(), vec![0x7f, 0x80]);
+	}
+}
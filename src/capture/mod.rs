@@ -0,0 +1,30 @@
+mod capture_session;
+mod composite;
+mod cursor;
+mod fingerprint;
+mod format;
+mod frame;
+mod mask;
+mod parallel;
+mod png;
+mod search;
+#[cfg(feature = "async")]
+mod stream;
+mod yuv;
+
+pub use self::{
+	capture_session::CaptureSession,
+	fingerprint::hash_distance,
+	format::*,
+	frame::{Frame, FrameRef, Plane},
+	mask::Mask,
+	parallel::CaptureOptions,
+	yuv::yuv420_len,
+};
+pub(crate) use self::{
+	composite::capture_composited,
+	cursor::draw_cursor,
+	parallel::capture_parallel,
+};
+#[cfg(feature = "async")]
+pub use self::stream::CaptureStream;
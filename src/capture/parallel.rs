@@ -0,0 +1,69 @@
+use std::{
+	collections::HashMap,
+	thread,
+};
+
+use x11::xlib::Window as XWindow;
+
+use crate::{Display, Window};
+
+use super::{CaptureError, CaptureSession, Frame, PixelFormat};
+
+/// Options for [crate::Session::capture_all].
+#[derive(Copy, Clone, Debug)]
+pub struct CaptureOptions {
+	/// The layout of the pixels of the frames.
+	pub format: PixelFormat,
+	/// Capture the title bar and borders as well, see [CaptureSession::set_decorations].
+	pub decorations: bool,
+	/// How many display connections are used at the same time, 0 uses one for every available core.
+	pub threads: usize,
+}
+
+impl Default for CaptureOptions {
+	fn default() -> Self {
+		Self {
+			format: PixelFormat::Bgra,
+			decorations: false,
+			threads: 0,
+		}
+	}
+}
+
+/// Captures the windows on separate threads, every thread opens its own connection with [Display::open].
+pub(crate) fn capture_parallel(ids: &[XWindow], options: CaptureOptions) -> HashMap<XWindow, Result<Frame, CaptureError>> {
+	let threads = match options.threads {
+		0 => thread::available_parallelism().map(|it| it.get()).unwrap_or(1),
+		n => n,
+	};
+	let per_thread = ids.len().div_ceil(threads).max(1);
+
+	thread::scope(|scope| {
+		let handles = ids
+			.chunks(per_thread)
+			.map(|chunk| scope.spawn(move || capture_chunk(chunk, options)))
+			.collect::<Vec<_>>();
+		handles
+			.into_iter()
+			.flat_map(|it| it.join().unwrap_or_default())
+			.collect()
+	})
+}
+
+fn capture_chunk(ids: &[XWindow], options: CaptureOptions) -> Vec<(XWindow, Result<Frame, CaptureError>)> {
+	let display = match Display::open() {
+		Some(display) => display.shared(),
+		None => return ids.iter().map(|id| (*id, Err(CaptureError::DisplayOpenFailed))).collect(),
+	};
+	ids.iter()
+		.map(|id| {
+			let mut session = CaptureSession::new(Window {
+				window: *id,
+				display: display.clone(),
+			});
+			session.set_decorations(options.decorations);
+			let frame = session.capture().and_then(|img| Frame::from_image(img, options.format));
+			(*id, frame)
+		})
+		.collect()
+}
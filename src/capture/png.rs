@@ -0,0 +1,162 @@
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::Path,
+};
+
+use super::{Frame, PixelFormat};
+
+/// The largest amount of data a single stored deflate block can hold.
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+impl Frame {
+	/// Encodes this frame as a PNG image.
+	///
+	/// The image data is stored uncompressed, which keeps this small and fast
+	/// at the cost of files that are about as large as the raw pixels.
+	/// Alpha is only written for [PixelFormat::Bgra] and [PixelFormat::Rgba].
+	pub fn write_png<W: Write>(&self, mut out: W) -> io::Result<()> {
+		let (color_type, channels) = match self.format {
+			PixelFormat::Bgra | PixelFormat::Rgba => (6, 4),
+			PixelFormat::Bgr | PixelFormat::Rgb => (2, 3),
+			PixelFormat::Gray => (0, 1),
+		};
+		out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+		let mut header = Vec::with_capacity(13);
+		header.extend_from_slice(&self.width.to_be_bytes());
+		header.extend_from_slice(&self.height.to_be_bytes());
+		header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+		write_chunk(&mut out, b"IHDR", &header)?;
+
+		// Every row starts with the filter type, 0 means no filtering.
+		let stride = self.width as usize * channels;
+		let mut raw = Vec::with_capacity((stride + 1) * self.height as usize);
+		for row in self.data.chunks_exact(stride.max(1)).take(self.height as usize) {
+			raw.push(0);
+			match self.format {
+				PixelFormat::Bgra | PixelFormat::Bgr => {
+					for px in row.chunks_exact(channels) {
+						raw.extend_from_slice(&[px[2], px[1], px[0]]);
+						raw.extend_from_slice(&px[3..]);
+					}
+				}
+				_ => raw.extend_from_slice(row),
+			}
+		}
+		write_chunk(&mut out, b"IDAT", &zlib_stored(&raw))?;
+		write_chunk(&mut out, b"IEND", &[])?;
+		out.flush()
+	}
+
+	/// Encodes this frame as a PNG image into a new file, see [Self::write_png].
+	pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+		self.write_png(BufWriter::new(File::create(path)?))
+	}
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+	out.write_all(&(data.len() as u32).to_be_bytes())?;
+	out.write_all(kind)?;
+	out.write_all(data)?;
+	let crc = crc32(crc32(0xffff_ffff, kind), data) ^ 0xffff_ffff;
+	out.write_all(&crc.to_be_bytes())
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+	let blocks = data.len() / MAX_STORED_BLOCK + 1;
+	let mut out = Vec::with_capacity(data.len() + blocks * 5 + 6);
+	out.extend_from_slice(&[0x78, 0x01]);
+	let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+	if chunks.peek().is_none() {
+		out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+	}
+	while let Some(chunk) = chunks.next() {
+		let last = chunks.peek().is_none() as u8;
+		let len = chunk.len() as u16;
+		out.push(last);
+		out.extend_from_slice(&len.to_le_bytes());
+		out.extend_from_slice(&(!len).to_le_bytes());
+		out.extend_from_slice(chunk);
+	}
+	out.extend_from_slice(&adler32(data).to_be_bytes());
+	out
+}
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+	for &byte in data {
+		crc ^= byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 == 1 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+		}
+	}
+	crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+	let (mut a, mut b) = (1u32, 0u32);
+	for chunk in data.chunks(5552) {
+		for &byte in chunk {
+			a += byte as u32;
+			b += a;
+		}
+		a %= 65521;
+		b %= 65521;
+	}
+	(b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Instant;
+
+	use super::*;
+
+	#[test]
+	fn checksums_match_the_reference_values() {
+		assert_eq!(crc32(0xffff_ffff, b"123456789") ^ 0xffff_ffff, 0xcbf4_3926);
+		assert_eq!(crc32(0xffff_ffff, b"IEND") ^ 0xffff_ffff, 0xae42_6082);
+		assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+		assert_eq!(adler32(&[]), 1);
+	}
+
+	#[test]
+	fn zlib_stored_splits_into_blocks() {
+		assert_eq!(zlib_stored(&[]), vec![0x78, 0x01, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]);
+		assert_eq!(zlib_stored(b"ab")[..7], [0x78, 0x01, 1, 2, 0, 0xfd, 0xff]);
+
+		let data = vec![7; MAX_STORED_BLOCK + 1];
+		let stream = zlib_stored(&data);
+		assert_eq!(stream.len(), 2 + 5 + MAX_STORED_BLOCK + 5 + 1 + 4);
+		assert_eq!(stream[2..7], [0, 0xff, 0xff, 0, 0]);
+		assert_eq!(stream[7 + MAX_STORED_BLOCK..12 + MAX_STORED_BLOCK], [1, 1, 0, 0xfe, 0xff]);
+	}
+
+	#[test]
+	fn write_png_swaps_bgra_to_rgba() {
+		let frame = Frame { width: 2, height: 1, format: PixelFormat::Bgra, data: vec![1, 2, 3, 4, 5, 6, 7, 8], time: Instant::now() };
+		let mut png = Vec::new();
+		frame.write_png(&mut png).unwrap();
+
+		assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+		// The header chunk, with the size, 8 bits per channel and color type 6.
+		assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+		assert_eq!(png[16..29], [0, 0, 0, 2, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+		// The data chunk holds a single stored block with the filter byte and the pixels.
+		let idat = &png[33..];
+		assert_eq!(idat[4..8], *b"IDAT");
+		assert_eq!(idat[8 + 7..8 + 16], [0, 3, 2, 1, 4, 7, 6, 5, 8]);
+		assert_eq!(png[png.len() - 12..], [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+	}
+
+	#[test]
+	fn write_png_keeps_gray_rows() {
+		let frame = Frame { width: 3, height: 2, format: PixelFormat::Gray, data: vec![1, 2, 3, 4, 5, 6], time: Instant::now() };
+		let mut png = Vec::new();
+		frame.write_png(&mut png).unwrap();
+		assert_eq!(png[25], 0);
+		let idat = &png[33..];
+		assert_eq!(idat[8 + 7..8 + 15], [0, 1, 2, 3, 0, 4, 5, 6]);
+	}
+}
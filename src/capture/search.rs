@@ -0,0 +1,43 @@
+use crate::Rect;
+
+use super::Frame;
+
+impl Frame {
+	/// Finds where `needle` is in this frame, the first match from the top left.
+	///
+	/// A position matches if no colour channel of any pixel differs by more than `tolerance`,
+	/// 0 only finds exact copies. Both frames have to use the same [super::PixelFormat].
+	///
+	/// # Example
+	/// ```ignore
+	/// let button = Frame::from_image(&button_window.capture()?, PixelFormat::Rgb)?;
+	/// let screen = Frame::from_image(&session.root().capture()?, PixelFormat::Rgb)?;
+	/// let area = screen.find(&button, 8);
+	/// ```
+	pub fn find(&self, needle: &Frame, tolerance: u8) -> Option<Rect> {
+		if needle.format != self.format || needle.width == 0 || needle.height == 0
+			|| needle.width > self.width || needle.height > self.height {
+			return None;
+		}
+		let bpp = self.format.bytes_per_pixel();
+		let (width, needle_width) = (self.width as usize, needle.width as usize);
+		let row_len = needle_width * bpp;
+		let close = |a: &[u8], b: &[u8]| a.iter().zip(b).all(|(a, b)| a.abs_diff(*b) <= tolerance);
+		for y in 0..=(self.height - needle.height) as usize {
+			for x in 0..=(self.width - needle.width) as usize {
+				let at = |row: usize| ((y + row) * width + x) * bpp;
+				// The first pixel rules out most positions before a whole row is compared.
+				if !close(&self.data[at(0)..at(0) + bpp], &needle.data[..bpp]) {
+					continue;
+				}
+				let found = (0..needle.height as usize).all(|row| {
+					close(&self.data[at(row)..at(row) + row_len], &needle.data[row * row_len..(row + 1) * row_len])
+				});
+				if found {
+					return Some(Rect::new(x as i32, y as i32, needle.width, needle.height));
+				}
+			}
+		}
+		None
+	}
+}
@@ -0,0 +1,188 @@
+use std::{
+	future::poll_fn,
+	io,
+	os::unix::io::{BorrowedFd, OwnedFd},
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+	time::Duration,
+};
+
+use futures_core::Stream;
+use tokio::{
+	io::unix::AsyncFd,
+	time::{interval, Interval, MissedTickBehavior},
+};
+use x11::xlib::BadMatch;
+
+use crate::{
+	backend::{ImageRequest, SizeRequest, XcbBackend},
+	Error, Rect, Window,
+};
+
+use super::{CaptureError, Frame, PixelFormat};
+
+/// Frames of a window produced at a fixed interval, created by [crate::Window::capture_stream_async].
+///
+/// Every frame is requested with XCB on the connection of the window, and the stream waits for the reply
+/// with tokio's [AsyncFd] on [crate::Display::connection_fd], so the thread of the executor never blocks on the server.
+/// The frames are paced by a tokio [Interval], a frame that is still on its way when the next one is due
+/// makes that one be skipped. The size of the window is asked for with every frame, so a window that was
+/// resized is captured at its new size. Create and poll the stream on a tokio runtime with IO and time enabled.
+///
+/// # Example
+/// ```ignore
+/// use futures::StreamExt;
+/// let mut stream = window.capture_stream_async(Duration::from_millis(33), PixelFormat::Rgba)?;
+/// while let Some(frame) = stream.next().await {
+///     encoder.push(frame.expect("Could not capture the window."));
+/// }
+/// ```
+pub struct CaptureStream {
+	window: Window,
+	backend: XcbBackend,
+	fd: AsyncFd<OwnedFd>,
+	ticks: Interval,
+	format: PixelFormat,
+	size: Option<(u32, u32)>,
+	pending: Option<Pending>,
+}
+
+/// The requests of the next frame, the size of the window is asked for before its pixels.
+struct Pending {
+	size: SizeRequest,
+	image: Option<ImageRequest>,
+	/// The size once its reply was taken.
+	known: Option<(u32, u32)>,
+}
+
+impl CaptureStream {
+	pub(crate) fn new(window: Window, period: Duration, format: PixelFormat) -> io::Result<Self> {
+		// Every stream registers a descriptor of its own, the reactor refuses one that is registered already.
+		let fd = unsafe { BorrowedFd::borrow_raw(window.display.connection_fd()) }.try_clone_to_owned()?;
+		let mut ticks = interval(period);
+		ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+		Ok(Self {
+			backend: XcbBackend::new(Arc::clone(&window.display)),
+			fd: AsyncFd::new(fd)?,
+			window,
+			ticks,
+			format,
+			size: None,
+			pending: None,
+		})
+	}
+
+	/// Waits for the next frame.
+	pub async fn next(&mut self) -> Option<Result<Frame, Error>> {
+		poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+	}
+
+	/// The window the frames are captured from.
+	pub fn window(&self) -> &Window {
+		&self.window
+	}
+
+	/// Asks for the size of the window and, once it is known, for its pixels.
+	fn request(&mut self) {
+		let window = self.window.window;
+		let size = self.backend.request_size(window);
+		let image = self.size.map(|(width, height)| self.backend.request_image(window, Rect::new(0, 0, width, height)));
+		self.pending = Some(Pending { size, image, known: None });
+	}
+
+	/// The frame once the replies of the requests arrived, the requests are sent again right away
+	/// if the window was resized meanwhile.
+	fn finish(&mut self, mut pending: Pending) -> Result<Option<Result<Frame, Error>>, Pending> {
+		let size = match pending.known {
+			Some(size) => size,
+			None => match self.backend.poll_size(&pending.size) {
+				Some(Ok(size)) => {
+					pending.known = Some(size);
+					size
+				}
+				Some(Err(err)) => {
+					if let Some(image) = pending.image {
+						self.backend.discard_image(image);
+					}
+					return Ok(Some(Err(err)));
+				}
+				None => return Err(pending),
+			},
+		};
+		let image = match &pending.image {
+			// The replies come in order, the one of the image can only follow the size.
+			Some(image) => match self.backend.poll_image(image) {
+				Some(image) => image,
+				None => return Err(pending),
+			},
+			None => {
+				self.size = Some(size);
+				self.request();
+				return Ok(None);
+			}
+		};
+		if self.size != Some(size) {
+			// A window that shrank fails with BadMatch, one that grew is cut off, so neither is kept.
+			self.size = Some(size);
+			self.request();
+			return Ok(None);
+		}
+		Ok(Some(match image {
+			Ok(raw) => Frame::from_raw(&raw, self.format).map_err(Error::from),
+			// Windows that aren't viewable can't be captured, like with XGetImage.
+			Err(Error::RequestFailed(code)) if code == BadMatch as _ => Err(Error::Capture(CaptureError::Null)),
+			Err(err) => Err(err),
+		}))
+	}
+}
+
+impl Drop for CaptureStream {
+	fn drop(&mut self) {
+		// Replies nobody waits for would stay in the queue of XCB for as long as the connection is open.
+		if let Some(pending) = self.pending.take() {
+			if pending.known.is_none() {
+				self.backend.discard_size(pending.size);
+			}
+			if let Some(image) = pending.image {
+				self.backend.discard_image(image);
+			}
+		}
+	}
+}
+
+impl Stream for CaptureStream {
+	type Item = Result<Frame, Error>;
+
+	/// Polls for the next frame, the stream ends once the [crate::CancelToken] of the display is cancelled
+	/// or the runtime can't wait for the connection anymore.
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		loop {
+			if this.window.display.cancel_token().is_cancelled() {
+				return Poll::Ready(None);
+			}
+			let pending = match this.pending.take() {
+				Some(pending) => pending,
+				None => {
+					if this.ticks.poll_tick(cx).is_pending() {
+						return Poll::Pending;
+					}
+					this.request();
+					continue;
+				}
+			};
+			match this.finish(pending) {
+				Ok(Some(frame)) => return Poll::Ready(Some(frame)),
+				Ok(None) => continue,
+				Err(pending) => this.pending = Some(pending),
+			}
+			// The readiness is only cleared after the replies were looked for, so none of them is missed.
+			match this.fd.poll_read_ready(cx) {
+				Poll::Ready(Ok(mut guard)) => guard.clear_ready(),
+				Poll::Ready(Err(_)) => return Poll::Ready(None),
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+	}
+}
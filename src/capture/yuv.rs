@@ -0,0 +1,165 @@
+use std::{borrow::Cow, slice};
+
+use super::{format::native_format, CaptureError, PixelFormat};
+use crate::XImg;
+
+impl XImg {
+	/// The image in the I420 layout most video encoders take, e.g. x264 and libvpx.
+	///
+	/// A full size plane of luma is followed by a plane of blue and a plane of red chroma,
+	/// each half as wide and high, see [yuv420_len] for their size.
+	/// The colours use the BT.601 weights in limited range, luma goes from 16 to 235,
+	/// and every chroma value is the average of a 2x2 block, smaller at odd edges.
+	pub fn to_i420(&self) -> Vec<u8> {
+		let mut buf = vec![0; yuv420_len(self.width(), self.height())];
+		write_yuv420(self, &mut buf, false);
+		buf
+	}
+
+	/// Like [Self::to_i420], in the NV12 layout hardware encoders like VA-API and NVENC take.
+	///
+	/// The full size plane of luma is followed by a single plane of interleaved blue and red chroma.
+	pub fn to_nv12(&self) -> Vec<u8> {
+		let mut buf = vec![0; yuv420_len(self.width(), self.height())];
+		write_yuv420(self, &mut buf, true);
+		buf
+	}
+
+	/// Like [Self::to_i420], into a buffer that can be reused for every frame.
+	pub fn i420_into(&self, buf: &mut [u8]) -> Result<(), CaptureError> {
+		let required = yuv420_len(self.width(), self.height());
+		if buf.len() < required {
+			return Err(CaptureError::BufferTooSmall(required));
+		}
+		write_yuv420(self, &mut buf[..required], false);
+		Ok(())
+	}
+
+	/// Like [Self::to_nv12], into a buffer that can be reused for every frame.
+	pub fn nv12_into(&self, buf: &mut [u8]) -> Result<(), CaptureError> {
+		let required = yuv420_len(self.width(), self.height());
+		if buf.len() < required {
+			return Err(CaptureError::BufferTooSmall(required));
+		}
+		write_yuv420(self, &mut buf[..required], true);
+		Ok(())
+	}
+}
+
+/// Number of bytes of an I420 or NV12 image of the given size, odd sizes round the chroma planes up.
+pub fn yuv420_len(width: u32, height: u32) -> usize {
+	let (width, height) = (width as usize, height as usize);
+	width * height + 2 * width.div_ceil(2) * height.div_ceil(2)
+}
+
+/// Writes the planes into `out`, which has exactly [yuv420_len] bytes.
+fn write_yuv420(img: &XImg, out: &mut [u8], interleaved: bool) {
+	let (width, height) = (img.width() as usize, img.height() as usize);
+	if width == 0 || height == 0 {
+		return;
+	}
+	let (pixels, stride) = bgra_pixels(img);
+	let (luma, chroma) = out.split_at_mut(width * height);
+	for (y, row) in luma.chunks_exact_mut(width).enumerate() {
+		let line = &pixels[y * stride..y * stride + width * 4];
+		for (out, px) in row.iter_mut().zip(line.chunks_exact(4)) {
+			*out = luma_of(px[2] as u32, px[1] as u32, px[0] as u32);
+		}
+	}
+
+	let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+	let plane = chroma_width * chroma_height;
+	for cy in 0..chroma_height {
+		let top = &pixels[cy * 2 * stride..];
+		let bottom = &pixels[(cy * 2 + 1).min(height - 1) * stride..];
+		for cx in 0..chroma_width {
+			let (left, right) = (cx * 8, (cx * 2 + 1).min(width - 1) * 4);
+			let sum = |c: usize| {
+				(top[left + c] as u32 + top[right + c] as u32 + bottom[left + c] as u32 + bottom[right + c] as u32 + 2) / 4
+			};
+			let (u, v) = chroma_of(sum(2) as i32, sum(1) as i32, sum(0) as i32);
+			let i = cy * chroma_width + cx;
+			if interleaved {
+				chroma[i * 2] = u;
+				chroma[i * 2 + 1] = v;
+			} else {
+				chroma[i] = u;
+				chroma[plane + i] = v;
+			}
+		}
+	}
+}
+
+#[inline]
+fn luma_of(r: u32, g: u32, b: u32) -> u8 {
+	(((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8
+}
+
+#[inline]
+fn chroma_of(r: i32, g: i32, b: i32) -> (u8, u8) {
+	let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+	let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+	(u as u8, v as u8)
+}
+
+/// The pixels as blue, green, red and a padding byte, with the number of bytes per row.
+///
+/// The usual 24 bit visual is read in place, other visuals are converted with [XImg::convert_into] first.
+fn bgra_pixels(img: &XImg) -> (Cow<'_, [u8]>, usize) {
+	let image = img.as_ref();
+	if native_format(image).is_some() {
+		let stride = image.bytes_per_line as usize;
+		let data = unsafe { slice::from_raw_parts(image.data as *const u8, stride * image.height as usize) };
+		return (Cow::Borrowed(data), stride);
+	}
+	let mut buf = vec![0; PixelFormat::Bgra.buffer_len(img.width(), img.height())];
+	// Can't fail, the buffer has the size of the image.
+	let _ = img.convert_into(&mut buf, PixelFormat::Bgra);
+	(Cow::Owned(buf), img.width() as usize * 4)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn odd_sizes_round_the_chroma_planes_up() {
+		assert_eq!(yuv420_len(4, 2), 8 + 2 * 2);
+		assert_eq!(yuv420_len(3, 3), 9 + 2 * 4);
+		assert_eq!(yuv420_len(0, 0), 0);
+	}
+
+	#[test]
+	fn colours_use_limited_range_bt601() {
+		assert_eq!(luma_of(0, 0, 0), 16);
+		assert_eq!(luma_of(255, 255, 255), 235);
+		assert_eq!(chroma_of(255, 255, 255), (128, 128));
+		assert_eq!((luma_of(255, 0, 0), chroma_of(255, 0, 0)), (82, (90, 240)));
+	}
+
+	#[test]
+	fn i420_and_nv12_lay_out_the_planes() {
+		let img = XImg::from_fn(3, 3, |_, _| (255, 0, 0));
+		let mut i420 = vec![82; 9];
+		i420.extend_from_slice(&[90; 4]);
+		i420.extend_from_slice(&[240; 4]);
+		assert_eq!(img.to_i420(), i420);
+
+		let mut nv12 = vec![82; 9];
+		(0..4).for_each(|_| nv12.extend_from_slice(&[90, 240]));
+		assert_eq!(img.to_nv12(), nv12);
+	}
+
+	#[test]
+	fn chroma_is_the_average_of_a_block() {
+		let img = XImg::from_fn(2, 1, |x, _| if x == 0 { (255, 255, 255) } else { (0, 0, 0) });
+		assert_eq!(img.to_i420(), vec![235, 16, 128, 128]);
+	}
+
+	#[test]
+	fn into_rejects_small_buffers() {
+		let img = XImg::from_fn(3, 3, |_, _| (0, 0, 0));
+		assert_eq!(img.i420_into(&mut [0; 16]), Err(CaptureError::BufferTooSmall(17)));
+		assert_eq!(img.nv12_into(&mut [0; 17]), Ok(()));
+	}
+}
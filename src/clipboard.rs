@@ -0,0 +1,360 @@
+use std::{
+	ffi::CString,
+	fmt,
+	mem::MaybeUninit,
+	mem::size_of,
+	os::raw::{c_int, c_long, c_short, c_void},
+	slice,
+	thread,
+	time::{Duration, Instant},
+};
+
+use x11::xlib::{
+	Atom as XAtom, AnyPropertyType, CurrentTime, False, PropModeReplace, SelectionClear, SelectionNotify, SelectionRequest,
+	Window as XWindow, XA_ATOM, XA_PRIMARY, XA_STRING, XChangeProperty, XCheckTypedWindowEvent, XConvertSelection, XCreateSimpleWindow,
+	XDefaultRootWindow, XDeleteProperty, XDestroyWindow, XEvent, XFlush, XFree, XGetSelectionOwner, XGetWindowProperty,
+	XInternAtom, XNextEvent, XSelectionEvent, XSelectionRequestEvent, XSendEvent, XSetSelectionOwner,
+};
+
+use crate::Display;
+
+/// How often the connection is checked for selection events while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+/// How long the owner of the selection gets to hand out its contents.
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+/// How long the window gets to ask for the text after the shortcut or middle click was sent.
+const PASTE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long requests are still answered after the text was handed out, some applications ask twice.
+const GRACE: Duration = Duration::from_millis(50);
+
+/// The error returned by [crate::Window::paste_text] and [crate::Window::paste_primary_at].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PasteError {
+	/// The clipboard, or the primary selection, could not be taken over.
+	NotOwned,
+	/// The window didn't ask for the text after the shortcut or middle click, it may not have the focus.
+	NotRequested,
+}
+
+impl fmt::Display for PasteError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PasteError::NotOwned => write!(f, "could not take over the selection"),
+			PasteError::NotRequested => write!(f, "the window did not ask for the pasted text"),
+		}
+	}
+}
+
+impl std::error::Error for PasteError {}
+
+/// The selection that is pasted from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Selection {
+	/// `CLIPBOARD`, pasted with a shortcut like Ctrl+V.
+	Clipboard,
+	/// `PRIMARY`, the selected text that is pasted with the middle button.
+	Primary,
+}
+
+/// The atoms used to talk about the selections.
+#[derive(Copy, Clone)]
+struct Atoms {
+	clipboard: XAtom,
+	targets: XAtom,
+	utf8: XAtom,
+	text: XAtom,
+	property: XAtom,
+	incr: XAtom,
+	multiple: XAtom,
+	timestamp: XAtom,
+}
+
+impl Atoms {
+	fn new(display: &Display) -> Self {
+		let atom = |name: &str| {
+			let name = CString::new(name).unwrap();
+			unsafe { XInternAtom(display.as_raw(), name.as_ptr(), False) }
+		};
+		Atoms {
+			clipboard: atom("CLIPBOARD"),
+			targets: atom("TARGETS"),
+			utf8: atom("UTF8_STRING"),
+			text: atom("TEXT"),
+			property: atom("X11_GET_WINDOWS_SELECTION"),
+			incr: atom("INCR"),
+			multiple: atom("MULTIPLE"),
+			timestamp: atom("TIMESTAMP"),
+		}
+	}
+}
+
+/// The contents of a selection for one target, as the owner handed them out.
+struct Saved {
+	target: XAtom,
+	typ: XAtom,
+	format: c_int,
+	/// The items as Xlib returns them, format 32 items are `c_long`s.
+	data: Vec<u8>,
+	count: usize,
+}
+
+/// An invisible window that owns the clipboard or the primary selection while it holds some text.
+struct Owner<'a> {
+	display: &'a Display,
+	window: XWindow,
+	selection: XAtom,
+	atoms: Atoms,
+}
+
+impl<'a> Owner<'a> {
+	fn new(display: &'a Display, selection: Selection) -> Self {
+		let window = unsafe {
+			let root = XDefaultRootWindow(display.as_raw());
+			XCreateSimpleWindow(display.as_raw(), root, 0, 0, 1, 1, 0, 0, 0)
+		};
+		let atoms = Atoms::new(display);
+		let selection = match selection {
+			Selection::Clipboard => atoms.clipboard,
+			Selection::Primary => XA_PRIMARY,
+		};
+		Owner { display, window, selection, atoms }
+	}
+
+	/// Asks the owner of the selection for its contents in every target it lists in `TARGETS`.
+	///
+	/// Returns [None] if there is no owner. Targets whose contents aren't handed out in time are left out,
+	/// as are large contents, which are handed out in parts with `INCR`. Owners that don't answer for `TARGETS`
+	/// are asked for their text only.
+	fn read(&self) -> Option<Vec<Saved>> {
+		if unsafe { XGetSelectionOwner(self.display.as_raw(), self.selection) } == 0 {
+			return None;
+		}
+		let Atoms { targets, utf8, multiple, timestamp, .. } = self.atoms;
+		let listed = match self.read_target(targets) {
+			Some(saved) if saved.typ == XA_ATOM && saved.format == 32 => {
+				let atoms = unsafe { slice::from_raw_parts(saved.data.as_ptr() as *const c_long, saved.count) };
+				atoms.iter().map(|it| *it as XAtom).collect()
+			}
+			_ => vec![utf8],
+		};
+		// These are answered by the owner itself, they aren't contents.
+		let skipped = [targets, multiple, timestamp];
+		let mut saved = Vec::new();
+		for target in listed {
+			if target != 0 && !skipped.contains(&target) && saved.iter().all(|it: &Saved| it.target != target) {
+				saved.extend(self.read_target(target));
+			}
+		}
+		Some(saved)
+	}
+
+	/// Asks the owner of the selection for its contents in `target`.
+	fn read_target(&self, target: XAtom) -> Option<Saved> {
+		let property = self.atoms.property;
+		unsafe {
+			XConvertSelection(self.display.as_raw(), self.selection, target, property, self.window, CurrentTime);
+			XFlush(self.display.as_raw());
+		}
+		let event = self.wait_for(SelectionNotify, Instant::now() + READ_TIMEOUT)?;
+		if unsafe { event.selection }.property == 0 {
+			return None;
+		}
+		let (mut typ, mut format, mut count, mut after, mut data) = (0, 0, 0, 0, std::ptr::null_mut());
+		unsafe {
+			XGetWindowProperty(
+				self.display.as_raw(), self.window, property, 0, c_int::MAX as _, 1, AnyPropertyType as _,
+				&mut typ, &mut format, &mut count, &mut after, &mut data,
+			)
+		};
+		if data.is_null() {
+			return None;
+		}
+		let item = match format {
+			8 => Some(1),
+			16 => Some(size_of::<c_short>()),
+			32 => Some(size_of::<c_long>()),
+			_ => None,
+		};
+		let saved = item.filter(|_| typ != 0 && typ != self.atoms.incr).map(|item| Saved {
+			target,
+			typ,
+			format,
+			data: unsafe { slice::from_raw_parts(data, count as usize * item) }.to_vec(),
+			count: count as usize,
+		});
+		unsafe { XFree(data as *mut c_void) };
+		saved
+	}
+
+	/// Takes over the selection, returns false if another client got it.
+	fn own(&self) -> bool {
+		unsafe {
+			XSetSelectionOwner(self.display.as_raw(), self.selection, self.window, CurrentTime);
+			XGetSelectionOwner(self.display.as_raw(), self.selection) == self.window
+		}
+	}
+
+	fn wait_for(&self, typ: c_int, deadline: Instant) -> Option<XEvent> {
+		let mut event = MaybeUninit::<XEvent>::uninit();
+		while Instant::now() < deadline {
+			if unsafe { XCheckTypedWindowEvent(self.display.as_raw(), self.window, typ, event.as_mut_ptr()) } != 0 {
+				return Some(unsafe { event.assume_init() });
+			}
+			thread::sleep(POLL_INTERVAL);
+		}
+		None
+	}
+
+	/// Answers the requests for the text until the deadline, which is moved up once the text was handed out.
+	///
+	/// Returns whether the text was handed out at least once.
+	fn serve_until(&self, text: &[u8], deadline: Instant) -> bool {
+		let mut served = false;
+		let mut deadline = deadline;
+		while let Some(event) = self.wait_for(SelectionRequest, deadline) {
+			if answer(self.display, &self.atoms, &unsafe { event.selection_request }, text) {
+				if !served {
+					deadline = deadline.min(Instant::now() + GRACE);
+				}
+				served = true;
+			}
+		}
+		served
+	}
+}
+
+impl Drop for Owner<'_> {
+	fn drop(&mut self) {
+		unsafe {
+			XDestroyWindow(self.display.as_raw(), self.window);
+			XFlush(self.display.as_raw());
+		}
+	}
+}
+
+/// Writes the text into the property the requestor asked for, and tells it whether that worked.
+///
+/// Returns whether the text itself was handed out, not just the list of targets.
+fn answer(display: &Display, atoms: &Atoms, request: &XSelectionRequestEvent, text: &[u8]) -> bool {
+	// Old clients leave out the property, the target is used as the property then.
+	let property = if request.property == 0 { request.target } else { request.property };
+	let target = request.target;
+	let handed_out = unsafe {
+		if target == atoms.targets {
+			let targets = [atoms.targets, atoms.utf8, atoms.text, XA_STRING];
+			XChangeProperty(display.as_raw(), request.requestor, property, XA_ATOM, 32, PropModeReplace, targets.as_ptr() as _, targets.len() as c_int);
+			Some(false)
+		} else if target == atoms.utf8 || target == atoms.text || target == XA_STRING {
+			let typ = if target == atoms.text { atoms.utf8 } else { target };
+			XChangeProperty(display.as_raw(), request.requestor, property, typ, 8, PropModeReplace, text.as_ptr(), text.len() as c_int);
+			Some(true)
+		} else {
+			None
+		}
+	};
+	let mut reply = XEvent {
+		selection: XSelectionEvent {
+			type_: SelectionNotify,
+			serial: 0,
+			send_event: 1,
+			display: display.as_raw(),
+			requestor: request.requestor,
+			selection: request.selection,
+			target,
+			property: if handed_out.is_some() { property } else { 0 },
+			time: request.time,
+		},
+	};
+	unsafe {
+		XSendEvent(display.as_raw(), request.requestor, False, 0, &mut reply);
+		XFlush(display.as_raw());
+	}
+	handed_out.unwrap_or(false)
+}
+
+/// Writes the saved contents for the target the requestor asked for into its property, like [answer].
+fn answer_saved(display: &Display, atoms: &Atoms, request: &XSelectionRequestEvent, contents: &[Saved]) {
+	let property = if request.property == 0 { request.target } else { request.property };
+	let target = request.target;
+	let handed_out = unsafe {
+		if target == atoms.targets {
+			let targets = std::iter::once(atoms.targets).chain(contents.iter().map(|it| it.target)).collect::<Vec<_>>();
+			XChangeProperty(display.as_raw(), request.requestor, property, XA_ATOM, 32, PropModeReplace, targets.as_ptr() as _, targets.len() as c_int);
+			true
+		} else if let Some(saved) = contents.iter().find(|it| it.target == target) {
+			XChangeProperty(display.as_raw(), request.requestor, property, saved.typ, saved.format, PropModeReplace, saved.data.as_ptr(), saved.count as c_int);
+			true
+		} else {
+			false
+		}
+	};
+	let mut reply = XEvent {
+		selection: XSelectionEvent {
+			type_: SelectionNotify,
+			serial: 0,
+			send_event: 1,
+			display: display.as_raw(),
+			requestor: request.requestor,
+			selection: request.selection,
+			target,
+			property: if handed_out { property } else { 0 },
+			time: request.time,
+		},
+	};
+	unsafe {
+		XSendEvent(display.as_raw(), request.requestor, False, 0, &mut reply);
+		XFlush(display.as_raw());
+	}
+}
+
+/// Owns the selection with `text` while `paste` runs, and until the pasted window asked for it.
+///
+/// The previous contents are put back afterwards by a worker thread with its own connection,
+/// in every target the previous owner handed them out in, images and files as well as text.
+/// The thread owns the selection until another client takes it over.
+pub(crate) fn paste_with(display: &Display, selection: Selection, text: &str, paste: impl FnOnce()) -> Result<(), PasteError> {
+	let owner = Owner::new(display, selection);
+	let previous = owner.read();
+	unsafe { XDeleteProperty(display.as_raw(), owner.window, owner.atoms.property) };
+	if !owner.own() {
+		return Err(PasteError::NotOwned);
+	}
+	paste();
+	let served = owner.serve_until(text.as_bytes(), Instant::now() + PASTE_TIMEOUT);
+	match previous {
+		Some(previous) if !previous.is_empty() => restore(selection, previous),
+		_ => unsafe {
+			XSetSelectionOwner(display.as_raw(), owner.selection, 0, CurrentTime);
+		},
+	}
+	if served { Ok(()) } else { Err(PasteError::NotRequested) }
+}
+
+/// Owns the selection with the saved contents on a worker thread, until another client takes it over.
+fn restore(selection: Selection, contents: Vec<Saved>) {
+	let _ = thread::Builder::new().name("x11-clipboard".into()).spawn(move || {
+		let display = match Display::open() {
+			Some(display) => display,
+			None => return,
+		};
+		let owner = Owner::new(&display, selection);
+		if !owner.own() {
+			return;
+		}
+		unsafe { XFlush(display.as_raw()) };
+		let mut event = MaybeUninit::<XEvent>::uninit();
+		loop {
+			let event = unsafe {
+				XNextEvent(display.as_raw(), event.as_mut_ptr());
+				event.assume_init()
+			};
+			match event.get_type() {
+				typ if typ == SelectionRequest => {
+					answer_saved(&display, &owner.atoms, &unsafe { event.selection_request }, &contents);
+				}
+				typ if typ == SelectionClear => break,
+				_ => {}
+			}
+		}
+	});
+}
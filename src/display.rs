@@ -2,32 +2,116 @@ use x11::xlib::{
     Display as XDisplay,
     XOpenDisplay,
     XCloseDisplay,
+    XkbSetDetectableAutoRepeat,
+    XInitThreads,
+    XConnectionNumber,
+    XDefaultScreen,
+    XGetSelectionOwner,
+    XFlush,
+    XSync,
+    Time,
+    XA_STRING,
+    XA_WM_NAME,
+    XChangeProperty,
+    XCreateWindow,
+    XDefaultRootWindow,
+    XDestroyWindow,
+    XEvent,
+    XSetWindowAttributes,
+    XWindowEvent,
+    CWEventMask,
+    InputOnly,
+    PropertyChangeMask,
+    PropModeAppend,
+    MappingNotify,
+    XRefreshKeyboardMapping,
 };
 use std::{
     ops::Drop,
-    ptr::null,
+    os::raw::c_int,
+    os::unix::io::{AsRawFd, RawFd},
+    ptr::{null, null_mut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, Once, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+use std::sync::Arc;
+use crate::{
+    Atom,
+    CancelToken,
+    event::{backend::InputBackend, EventMasks, Histories, MaskPolicy},
+    grab::AutoRepeatGuard,
+    keys::{self, Keymap},
+    Error,
+    trap::{self, XError},
 };
-use std::rc::Rc;
 
 /// The Display Struct is just a wrapper of a [*mut Display] from XLib.
 /// 
 /// When this struct is dropped, the reference will be dropped using [XCloseDisplay].
-pub struct Display(pub *mut XDisplay);
+/// The settings of the connection are kept next to the pointer, get the pointer with [Self::as_raw].
+///
+/// A display can be shared between threads in an [Arc], as the [crate::Session] and the [crate::Window]s
+/// do. Xlib locks the connection around every call since [XInitThreads] runs before the first one is opened,
+/// and the requests of an error trap are kept together, see [Self::with_error_trap].
+pub struct Display(
+    /// The pointer to the raw Display.
+    #[deprecated(note = "use Display::as_raw, the tuple field only stays for existing code")]
+    pub *mut XDisplay,
+    State,
+);
+
+/// The settings and state of a connection, kept next to its pointer.
+struct State {
+    input_backend: Mutex<InputBackend>,
+    neutral_modifiers: AtomicBool,
+    histories: Mutex<Histories>,
+    cancel_token: CancelToken,
+    event_masks: Mutex<EventMasks>,
+    auto_flush: AtomicBool,
+    combo_gap: Mutex<Duration>,
+    server_clock: OnceLock<(Time, Instant)>,
+    last_event_time: Mutex<Time>,
+    keymap: Mutex<Option<Arc<Keymap>>>,
+    xkb_event_base: OnceLock<Option<c_int>>,
+}
+
+// Xlib is initialised for threads before the first connection, see [init_threads],
+// and the state next to the pointer is behind locks.
+unsafe impl Send for Display {}
+unsafe impl Sync for Display {}
+
+/// Calls [XInitThreads] once, it has to come before any other Xlib call of the process.
+fn init_threads() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| unsafe { XInitThreads(); });
+}
+
+/// Locks the state, a panic while it was locked leaves it usable.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|it| it.into_inner())
+}
+
 impl Display {
     /// Opens a connection to the x11 server.
     /// 
-    /// Will return an error of [Null] if the returned Display pointer is a null pointer.
+    /// Will return [None] if the returned Display pointer is a null pointer.
+    /// Xlib is initialised for threads with [XInitThreads] before the first connection is opened,
+    /// so open it with this before any other Xlib call of the program.
     pub fn open() -> Option<Self> {
+        init_threads();
         let x_display = unsafe { XOpenDisplay( null() ) };
         if x_display.is_null() {
             return None
         }
-        Some(Display(x_display))
+        Some(unsafe { Self::from_raw(x_display) })
     }
 
-    /// Create [Rc] for sharing in internal lib
-    pub fn shared(self) -> Rc<Self> {
-        Rc::new(self)
+    /// Create [Arc] for sharing in internal lib
+    pub fn shared(self) -> Arc<Self> {
+        Arc::new(self)
     }
     
     /// Consumes the safe wrapper and returns a pointer to the raw Display.
@@ -35,20 +119,273 @@ impl Display {
     /// Use this if you want to get more out of the display that this crate cannot provide.
     /// # Safety
     /// this is safe operation, but you will have to free manually or use [Self::from_raw] to use automatic destructor
-    pub unsafe fn into_raw(self) -> *mut XDisplay {
-        self.0
+    #[allow(deprecated)]
+    pub unsafe fn into_raw(mut self) -> *mut XDisplay {
+        // The connection is not closed when the wrapper is dropped without a pointer.
+        std::mem::replace(&mut self.0, null_mut())
     }
     /// Wraps a raw display pointer with a safe wrapper.
     /// 
     /// Ensure that this pointer is the only pointer as the connection is closed when this struct is dropped.
     /// # Safety
-    /// this is safe operation as long as you didn't construct [Display] using [Display::from_raw] multiple time
+    /// this is safe operation as long as you didn't construct [Display] using [Display::from_raw] multiple time,
+    /// and [XInitThreads] was called before the pointer was opened if the display is used on more than one thread
     pub unsafe fn from_raw(display: *mut XDisplay) -> Self {
-        Display(display)
+        #[allow(deprecated)]
+        Display(display, State {
+            input_backend: Mutex::default(),
+            neutral_modifiers: AtomicBool::default(),
+            histories: Mutex::default(),
+            cancel_token: CancelToken::new(),
+            event_masks: Mutex::default(),
+            auto_flush: AtomicBool::default(),
+            combo_gap: Mutex::default(),
+            server_clock: OnceLock::new(),
+            last_event_time: Mutex::default(),
+            keymap: Mutex::default(),
+            xkb_event_base: OnceLock::new(),
+        })
+    }
+
+    /// A display without a connection, for a [crate::Session] whose requests all go to a [crate::backend::MockBackend].
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn disconnected() -> Self {
+        // The pointer is null, so nothing is closed when it is dropped.
+        unsafe { Self::from_raw(null_mut()) }
+    }
+
+    /// The pointer to the raw Display, for calling Xlib functions this crate doesn't wrap.
+    /// 
+    /// The pointer stays owned by this struct, it is only valid while the struct is alive.
+    #[inline]
+    #[allow(deprecated)]
+    pub fn as_raw(&self) -> *mut XDisplay {
+        self.0
+    }
+
+    /// The way events are sent by the windows of this display, see [InputBackend].
+    pub fn input_backend(&self) -> InputBackend {
+        *lock(&self.1.input_backend)
+    }
+    /// Changes the way events are sent by the windows of this display.
+    /// 
+    /// Returns [Error::ExtensionMissing] if the X server lacks the extension the backend needs,
+    /// in which case the backend is left unchanged.
+    pub fn set_input_backend(&self, backend: InputBackend) -> Result<(), Error> {
+        #[cfg(feature = "xtest")]
+        if backend == InputBackend::XTest && !crate::event::xtest::is_supported(self) {
+            return Err(Error::ExtensionMissing("XTEST"));
+        }
+        *lock(&self.1.input_backend) = backend;
+        Ok(())
+    }
+    /// Whether modifiers the user holds are released while input is faked, see [Self::set_neutral_modifiers].
+    pub fn neutral_modifiers(&self) -> bool {
+        self.1.neutral_modifiers.load(Ordering::Relaxed)
+    }
+    /// Chooses whether the modifier keys the user physically holds are released while keys and buttons are faked.
+    /// 
+    /// With this on, a user holding Shift doesn't turn scripted input into capitals or selections:
+    /// the held modifiers are released before every faked key or button event and pressed again after it.
+    /// Caps Lock and Num Lock are left alone. This only has an effect with the XTest [InputBackend],
+    /// events sent with XSendEvent carry their own modifiers anyway.
+    pub fn set_neutral_modifiers(&self, neutral: bool) {
+        self.1.neutral_modifiers.store(neutral, Ordering::Relaxed);
+    }
+    /// The pause between the keys of a shortcut, see [Self::set_combo_gap].
+    pub fn combo_gap(&self) -> Duration {
+        *lock(&self.1.combo_gap)
+    }
+    /// Chooses how long [crate::Window::send_combo] waits after every press and release of a shortcut.
+    /// 
+    /// By default all the keys of a shortcut are sent at once. Some older applications only see
+    /// the modifiers when they were pressed a moment before the key, with a gap Ctrl is pressed,
+    /// then C after the gap, and so on until both are released.
+    pub fn set_combo_gap(&self, gap: Duration) {
+        *lock(&self.1.combo_gap) = gap;
+    }
+    /// The keyboard mapping of the server, loaded once and shared by everything that types on this connection.
+    /// 
+    /// The copy is loaded again after the mapping or the active group changed. The connection selects the XKB
+    /// events for that the first time, and the changes are noticed in the queue of the connection and in
+    /// the events the watchers and loops of this crate read. Call [Self::invalidate_keymap] when reading
+    /// the events of the connection with Xlib directly.
+    pub fn keymap(&self) -> Arc<Keymap> {
+        let xkb_event_base = *self.1.xkb_event_base.get_or_init(|| keys::select_mapping_events(self));
+        if lock(&self.1.keymap).is_some() && keys::keymap_changed_in_queue(self, xkb_event_base) {
+            self.invalidate_keymap();
+        }
+        let mut keymap = lock(&self.1.keymap);
+        Arc::clone(keymap.get_or_insert_with(|| Arc::new(Keymap::load(self))))
+    }
+
+    /// Drops the copy of [Self::keymap], the next call loads the mapping again.
+    pub fn invalidate_keymap(&self) {
+        lock(&self.1.keymap).take();
+    }
+
+    /// Invalidates the keymap if the event read from the connection changed the mapping.
+    pub(crate) fn note_event(&self, event: &XEvent) {
+        if let Some(xkb_event_base) = self.1.xkb_event_base.get() {
+            if keys::changes_keymap(event, *xkb_event_base) {
+                if event.get_type() == MappingNotify {
+                    // Xlib keeps a copy of its own for XKeysymToKeycode and the lookups.
+                    let mut mapping = unsafe { event.mapping };
+                    unsafe { XRefreshKeyboardMapping(&mut mapping) };
+                }
+                self.invalidate_keymap();
+            }
+        }
+    }
+
+    /// A timestamp for synthesized events, on the clock of the server.
+    /// 
+    /// The time of the server is read once with a zero-length property change on a hidden window,
+    /// later timestamps add the time passed since then. Every call returns a later time than the one before,
+    /// so toolkits that drop events older than the last one, or measure double clicks, see them in order.
+    /// It never returns [x11::xlib::CurrentTime].
+    pub(crate) fn event_time(&self) -> Time {
+        let (base, read) = *self.1.server_clock.get_or_init(|| (self.server_time(), Instant::now()));
+        let mut last = lock(&self.1.last_event_time);
+        *last = (base + read.elapsed().as_millis() as Time).max(*last + 1);
+        *last
+    }
+
+    /// Reads the current time of the server from the PropertyNotify of an empty append to a window of its own.
+    fn server_time(&self) -> Time {
+        unsafe {
+            let mut attributes: XSetWindowAttributes = std::mem::zeroed();
+            attributes.event_mask = PropertyChangeMask;
+            let root = XDefaultRootWindow(self.as_raw());
+            let window = XCreateWindow(self.as_raw(), root, -1, -1, 1, 1, 0, 0, InputOnly as _, null_mut(), CWEventMask, &mut attributes);
+            XChangeProperty(self.as_raw(), window, XA_WM_NAME, XA_STRING, 8, PropModeAppend, [0u8; 0].as_ptr(), 0);
+            let mut event = XEvent { pad: [0; 24] };
+            XWindowEvent(self.as_raw(), window, PropertyChangeMask, &mut event);
+            XDestroyWindow(self.as_raw(), window);
+            XFlush(self.as_raw());
+            event.property.time
+        }
+    }
+    /// What happens to the events the watchers of this connection selected once they are dropped, see [MaskPolicy].
+    pub fn mask_policy(&self) -> MaskPolicy {
+        self.event_masks().policy()
+    }
+    /// Chooses whether the events the watchers selected are deselected again once they are dropped.
+    /// 
+    /// Watchers like [crate::Window::watch_geometry] always add their events to the ones the connection
+    /// selected for the window before, [MaskPolicy::Restore] takes them off again afterwards.
+    pub fn set_mask_policy(&self, policy: MaskPolicy) {
+        self.event_masks().set_policy(policy);
+    }
+    /// Sends the requests waiting in Xlib's output buffer to the server, see [XFlush].
+    /// 
+    /// Xlib buffers requests until the buffer is full, a reply is needed or it is flushed,
+    /// so requests may not happen until then. Methods that finish something, like [crate::Window::type_text],
+    /// flush themselves, [crate::Window::send] and [crate::Window::focus] wait for the server anyway
+    /// to catch its errors. Only the fake input of XTest waits for a flush unless [Self::set_auto_flush] is on.
+    pub fn flush(&self) {
+        unsafe { XFlush(self.as_raw()) };
+    }
+    /// Flushes the requests and waits until the server has handled all of them, see [XSync].
+    /// 
+    /// With `discard` the events that arrived meanwhile and were not read yet are thrown away.
+    pub fn sync(&self, discard: bool) {
+        unsafe { XSync(self.as_raw(), discard as _) };
+    }
+    /// Whether every event faked with XTest is flushed right away, see [Self::set_auto_flush].
+    pub fn auto_flush(&self) -> bool {
+        self.1.auto_flush.load(Ordering::Relaxed)
+    }
+    /// Chooses whether the input faked with XTest, see [InputBackend], flushes the connection
+    /// after every request, so it reaches the server at once. [crate::Window::send] and [crate::Window::focus]
+    /// always wait for the server to report errors of windows that are gone.
+    /// 
+    /// This is off by default, as flushing every event is slow for long sequences,
+    /// see [crate::Window::send_batch] for sending many events with one flush.
+    pub fn set_auto_flush(&self, auto_flush: bool) {
+        self.1.auto_flush.store(auto_flush, Ordering::Relaxed);
+    }
+    /// Flushes the connection if [Self::auto_flush] is on.
+    #[cfg_attr(not(feature = "xtest"), allow(dead_code))]
+    pub(crate) fn flush_if_auto(&self) {
+        if self.auto_flush() {
+            self.flush();
+        }
+    }
+    /// Chooses whether this connection sees the repeats of a held key without release events.
+    /// 
+    /// With detectable auto repeat, a held key repeats as presses without releases in between,
+    /// so the repeats can be told apart from the key being pressed again.
+    /// Returns [Error::ExtensionMissing] if the server can't do that.
+    pub fn set_detectable_auto_repeat(&self, detectable: bool) -> Result<(), Error> {
+        let mut supported = 0;
+        unsafe { XkbSetDetectableAutoRepeat(self.as_raw(), detectable as _, &mut supported) };
+        if supported == 0 { Err(Error::ExtensionMissing("XKEYBOARD")) } else { Ok(()) }
+    }
+    /// Turns the auto repeat of the keyboard on or off until the returned guard is dropped.
+    /// 
+    /// This is a setting of the whole server, not only of this connection,
+    /// so the previous setting is restored by the guard, see [AutoRepeatGuard::keep] to keep it.
+    pub fn set_auto_repeat(&self, enabled: bool) -> AutoRepeatGuard<'_> {
+        AutoRepeatGuard::new(self, enabled)
+    }
+    /// Runs `requests` and returns the errors the server answered them with, instead of
+    /// letting Xlib's default handler end the process.
+    /// 
+    /// This waits for the server to answer every request, so keep the closure short.
+    /// Other threads that use the display wait until the trap is over.
+    /// Errors of other connections and of requests made outside of a trap are left to the
+    /// handler that was installed before the first trap.
+    /// # Example
+    /// ```ignore
+    /// let (attr, errors) = display.with_error_trap(|| window.get_attr());
+    /// if errors.iter().any(|it| it.error_code == x11::xlib::BadWindow) {
+    ///     println!("The window is gone.");
+    /// }
+    /// ```
+    pub fn with_error_trap<R>(&self, requests: impl FnOnce() -> R) -> (R, Vec<XError>) {
+        trap::trap(self, requests)
+    }
+    /// The token that aborts the blocking operations on this connection, see [CancelToken].
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.1.cancel_token
+    }
+    /// The file descriptor of the connection to the server, from [XConnectionNumber].
+    /// 
+    /// It becomes readable when events arrive, so it can be registered with an event loop
+    /// like tokio's `AsyncFd`; read the events with [crate::event::EventLoop::poll] once it is.
+    pub fn connection_fd(&self) -> RawFd {
+        unsafe { XConnectionNumber(self.as_raw()) }
+    }
+    /// Whether a compositor runs on the default screen, which owns the `_NET_WM_CM_Sn` selection.
+    /// 
+    /// With a compositor, windows keep the pixels that other windows cover and can be translucent,
+    /// so captures of covered windows are correct and overlays can be transparent.
+    pub fn compositor_active(&self) -> bool {
+        let name = format!("_NET_WM_CM_S{}", unsafe { XDefaultScreen(self.as_raw()) });
+        match Atom::new(self, name) {
+            Ok(atom) if atom.0 != 0 => unsafe { XGetSelectionOwner(self.as_raw(), atom.0) != 0 },
+            _ => false,
+        }
+    }
+    /// The windows whose changes are recorded on this connection, see [crate::Window::record_history].
+    pub(crate) fn histories(&self) -> MutexGuard<'_, Histories> {
+        lock(&self.1.histories)
+    }
+    /// The events the crate selected on windows of this connection, see [MaskPolicy].
+    pub(crate) fn event_masks(&self) -> MutexGuard<'_, EventMasks> {
+        lock(&self.1.event_masks)
+    }
+}
+impl AsRawFd for Display {
+    fn as_raw_fd(&self) -> RawFd {
+        self.connection_fd()
     }
 }
 impl Drop for Display {
     fn drop(&mut self) {
-        unsafe { XCloseDisplay(self.0) };
+        if !self.as_raw().is_null() {
+            unsafe { XCloseDisplay(self.as_raw()) };
+        }
     }
 }
@@ -0,0 +1,68 @@
+use std::{
+	fmt,
+	os::raw::c_int,
+};
+
+use x11::xlib::Window as XWindow;
+
+use crate::capture::CaptureError;
+
+/// The errors of this crate that are not about one part of it only.
+///
+/// [Error::AtomMissing] and [Error::PropertyMissing] usually mean that the window manager
+/// doesn't follow the "Extended Window Manager Hints" convention as far as this crate needs,
+/// while [Error::BadWindow] means that the window is gone.
+/// If you get one of the former with a common Desktop Environment, please make an issue on the GitHub repo,
+/// giving the OS; architecture; and/or desktop environment; of your computer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The atom with this name doesn't exist on the server, so no client ever set the property.
+	AtomMissing(&'static str),
+	/// The window doesn't have the property with this name.
+	PropertyMissing(&'static str),
+	/// The property has a type or a format, the number of bits of every item, that was not expected.
+	PropertyFormatUnexpected(c_int),
+	/// The window doesn't exist, it was probably closed since its ID was found.
+	BadWindow(XWindow),
+	/// The server answered a request with this error code, see [x11::xlib::BadValue] and the like.
+	RequestFailed(c_int),
+	/// The server lacks the extension with this name.
+	ExtensionMissing(&'static str),
+	/// A connection to the X server could not be opened.
+	DisplayOpenFailed,
+	/// Capturing the window failed.
+	Capture(CaptureError),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::AtomMissing(name) => write!(f, "the atom {} does not exist", name),
+			Error::PropertyMissing(name) => write!(f, "the window has no {} property", name),
+			Error::PropertyFormatUnexpected(format) => write!(f, "the property has an unexpected format of {} bits", format),
+			Error::BadWindow(window) => write!(f, "the window {:#x} does not exist", window),
+			Error::RequestFailed(code) => write!(f, "the request failed with error code {}", code),
+			Error::ExtensionMissing(name) => write!(f, "the X server lacks the {} extension", name),
+			Error::DisplayOpenFailed => write!(f, "could not open a connection to the X server"),
+			Error::Capture(err) => write!(f, "could not capture the window: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Capture(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<CaptureError> for Error {
+	fn from(err: CaptureError) -> Self {
+		match err {
+			CaptureError::BadWindow(window) => Error::BadWindow(window),
+			err => Error::Capture(err),
+		}
+	}
+}
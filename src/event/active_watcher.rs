@@ -0,0 +1,59 @@
+use x11::xlib::{Atom as XAtom, PropertyChangeMask, PropertyNotify, Window as XWindow};
+
+use crate::{Session, Window};
+
+use super::{EventLoop, Subscription};
+
+/// Yields the window that became active every time the focus changes, see [Session::watch_active_window].
+///
+/// Changes of `_NET_ACTIVE_WINDOW` on the root window are reported, so without a window manager
+/// that keeps it nothing is yielded. Moments without an active window, e.g. on an empty desktop, are skipped.
+///
+/// Every call to [Iterator::next] waits for the next change, and ends when the [crate::CancelToken]
+/// of the session is cancelled. Other events of the connection are dropped while waiting,
+/// hotkeys registered with [Session::register_hotkey] still run.
+pub struct ActiveWindowWatcher<'a> {
+	session: &'a Session,
+	events: EventLoop<'a>,
+	active_list: XAtom,
+	last: Option<XWindow>,
+	_subscription: Subscription<'a>,
+}
+
+impl<'a> ActiveWindowWatcher<'a> {
+	pub(crate) fn new(session: &'a Session) -> Self {
+		let subscription = Subscription::new(&session.display, session.root().window, PropertyChangeMask);
+		ActiveWindowWatcher {
+			session,
+			events: EventLoop::new(session),
+			active_list: session.active_list().0,
+			last: Window::active_window(session).ok().map(|it| it.window),
+			_subscription: subscription,
+		}
+	}
+}
+
+impl Iterator for ActiveWindowWatcher<'_> {
+	type Item = Window;
+
+	fn next(&mut self) -> Option<Window> {
+		loop {
+			let event = self.events.next_raw()?;
+			if event.get_type() != PropertyNotify {
+				continue;
+			}
+			let property = unsafe { event.property };
+			if property.window != self.session.root().window || property.atom != self.active_list {
+				continue;
+			}
+			let active = match Window::active_window(self.session) {
+				Ok(it) if it.window != 0 => it,
+				_ => continue,
+			};
+			if self.last != Some(active.window) {
+				self.last = Some(active.window);
+				return Some(active);
+			}
+		}
+	}
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use x11::xlib::Window as XWindow;
+
+use crate::{Session, Window};
+
+use super::{WindowChange, WindowWatcher};
+
+/// The windows of one application, by their `WM_CLASS` or by the `_NET_WM_PID` of their process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum App {
+	/// Windows whose instance or class name in `WM_CLASS` is this, ignoring case, e.g. `firefox`.
+	Class(String),
+	/// Windows of the process with this ID.
+	Pid(u32),
+}
+
+impl App {
+	/// Whether the window belongs to the application.
+	pub fn matches(&self, window: &Window) -> bool {
+		match self {
+			App::Class(class) => window
+				.class()
+				.ok()
+				.flatten()
+				.is_some_and(|(name, it)| name.eq_ignore_ascii_case(class) || it.eq_ignore_ascii_case(class)),
+			App::Pid(pid) => window.pid() == Some(*pid),
+		}
+	}
+}
+
+impl From<&str> for App {
+	fn from(class: &str) -> Self {
+		App::Class(class.to_owned())
+	}
+}
+
+impl From<String> for App {
+	fn from(class: String) -> Self {
+		App::Class(class)
+	}
+}
+
+impl From<u32> for App {
+	fn from(pid: u32) -> Self {
+		App::Pid(pid)
+	}
+}
+
+/// Like [WindowWatcher], only for the windows of one application, see [Session::watch_app_windows].
+///
+/// Destroyed windows can't be asked for their class anymore, so the IDs of the matching windows
+/// are remembered from the moment the watcher is created.
+pub struct AppWindowWatcher<'a> {
+	windows: WindowWatcher<'a>,
+	app: App,
+	matched: HashSet<XWindow>,
+}
+
+impl<'a> AppWindowWatcher<'a> {
+	pub(crate) fn new(session: &'a Session, app: App) -> Self {
+		let windows = WindowWatcher::new(session);
+		let matched = session
+			.get_windows()
+			.map(|it| it.iter().filter(|it| app.matches(it)).map(|it| it.window).collect())
+			.unwrap_or_default();
+		AppWindowWatcher { windows, app, matched }
+	}
+
+	/// The application whose windows are reported.
+	pub fn app(&self) -> &App {
+		&self.app
+	}
+}
+
+impl Iterator for AppWindowWatcher<'_> {
+	type Item = WindowChange;
+
+	fn next(&mut self) -> Option<WindowChange> {
+		loop {
+			match self.windows.next()? {
+				WindowChange::Created(window) if self.app.matches(&window) => {
+					self.matched.insert(window.window);
+					return Some(WindowChange::Created(window));
+				}
+				WindowChange::Destroyed(window) if self.matched.remove(&window) => {
+					return Some(WindowChange::Destroyed(window));
+				}
+				_ => {}
+			}
+		}
+	}
+}
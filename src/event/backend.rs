@@ -0,0 +1,17 @@
+/// How the events synthesized by [crate::Window::send_key] and [crate::Window::send_btn]
+/// reach their target, see [crate::Display::set_input_backend].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum InputBackend {
+	/// Sends the events straight to the window with [x11::xlib::XSendEvent].
+	///
+	/// This needs no extensions, but the events are marked as sent by a client,
+	/// which Chrome, games and many toolkits ignore.
+	#[default]
+	SendEvent,
+	/// Fakes the events with the XTest extension, as if they came from real hardware.
+	///
+	/// The events go to whatever window has the input focus, or is under the pointer for buttons,
+	/// so focus the window first with [crate::Window::focus].
+	#[cfg(feature = "xtest")]
+	XTest,
+}
@@ -0,0 +1,206 @@
+use std::os::raw::{c_int, c_uint};
+use std::sync::Arc;
+
+use x11::xlib::{
+	Button1MotionMask, Button2MotionMask, Button3MotionMask, Button4MotionMask, Button5MotionMask, ButtonMotionMask, KeyCode,
+	KeySym, MotionNotify, PointerMotionMask, True, Window as XWindow, XButtonEvent, XEvent, XMotionEvent,
+};
+
+use super::btn_event::{ButtonType, MouseButton};
+use super::key_event::KeyType;
+use super::EventOptions;
+#[cfg(feature = "xtest")]
+use x11::xlib::XFlush;
+#[cfg(feature = "xtest")]
+use super::{backend::InputBackend, btn_event::pointer_mapping, xtest};
+use crate::keys::Remap;
+use crate::{Error, Window};
+
+/// One event of [Window::send_batch], with the same arguments as the method that sends it alone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PreparedEvent {
+	/// A key press or release by keysym, see [Window::send_key].
+	Key {
+		/// Whether the key is pressed or released.
+		typ: KeyType,
+		/// The keysym of the key, e.g. [x11::keysym::XK_Return].
+		keysym: KeySym,
+		/// The modifiers in the state of the event, e.g. [x11::xlib::ShiftMask].
+		modifiers: c_uint,
+	},
+	/// A key press or release by key code, see [Window::send_keycode].
+	KeyCode {
+		/// Whether the key is pressed or released.
+		typ: KeyType,
+		/// The key code of the key.
+		code: KeyCode,
+		/// The modifiers in the state of the event.
+		modifiers: c_uint,
+	},
+	/// A button press or release, see [Window::send_btn].
+	Button {
+		/// Whether the button is pressed or released.
+		typ: ButtonType,
+		/// The logical button.
+		button: MouseButton,
+		/// The position relative to the window.
+		x: c_int,
+		/// The position relative to the window.
+		y: c_int,
+		/// The modifiers in the state of the event.
+		modifiers: c_uint,
+	},
+	/// Pointer motion, see [Window::send_motion].
+	Motion {
+		/// The position relative to the window.
+		x: c_int,
+		/// The position relative to the window.
+		y: c_int,
+		/// The state of the event, e.g. [x11::xlib::Button1Mask] while a button is held.
+		modifiers: c_uint,
+	},
+}
+
+impl PreparedEvent {
+	/// The press and release of a key.
+	pub fn tap(keysym: KeySym, modifiers: c_uint) -> [Self; 2] {
+		[
+			PreparedEvent::Key { typ: KeyType::Press, keysym, modifiers },
+			PreparedEvent::Key { typ: KeyType::Release, keysym, modifiers },
+		]
+	}
+
+	/// The press and release of a button at `x` and `y`, relative to the window.
+	pub fn click(button: impl Into<MouseButton>, x: c_int, y: c_int) -> [Self; 2] {
+		let button = button.into();
+		[
+			PreparedEvent::Button { typ: ButtonType::Press, button, x, y, modifiers: 0 },
+			PreparedEvent::Button { typ: ButtonType::Release, button, x, y, modifiers: 0 },
+		]
+	}
+}
+
+impl Window {
+	/// Sends all the events in order and waits for the server once at the end.
+	///
+	/// Sending events one by one looks up the keyboard mapping, the position of the window
+	/// and the child under the pointer for every event, each a round trip to the server.
+	/// A batch does these once, so long key sequences are sent much faster.
+	/// Keysyms that are not on the current layout are bound to spare key codes like in [Self::tap_key],
+	/// the others are sent like [Self::send_key], [Self::send_btn] and [Self::send_motion] would.
+	/// With the XTest backend held modifiers are released once around the whole batch, see [crate::Display::neutral_modifiers].
+	/// # Example
+	/// ```ignore
+	/// use x11::keysym::{XK_Tab, XK_Return};
+	/// let mut events = Vec::new();
+	/// events.extend(PreparedEvent::tap(XK_Tab as KeySym, 0));
+	/// events.extend(PreparedEvent::tap(XK_Tab as KeySym, 0));
+	/// events.extend(PreparedEvent::tap(XK_Return as KeySym, 0));
+	/// win.send_batch(&events)?;
+	/// ```
+	pub fn send_batch(&self, events: &[PreparedEvent]) -> Result<(), Error> {
+		let keymap = self.display.keymap();
+		let mut remap = Remap::new(Arc::clone(&self.display), &keymap);
+		// Keysyms are resolved up front, binding spare keys needs round trips of its own.
+		let events = events
+			.iter()
+			.filter_map(|event| match *event {
+				PreparedEvent::Key { typ, keysym, modifiers } => {
+					let (code, level) = remap.resolve(&keymap, keysym)?;
+					Some(PreparedEvent::KeyCode { typ, code, modifiers: modifiers | level })
+				}
+				event => Some(event),
+			})
+			.collect::<Vec<_>>();
+		let (root_x, root_y) = self.root_position()?;
+
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			let mapping = pointer_mapping(&self.display);
+			let fake = || {
+				for event in &events {
+					match *event {
+						PreparedEvent::KeyCode { typ, code, modifiers } => {
+							xtest::fake_key(&self.display, code, typ == KeyType::Press, modifiers);
+						}
+						PreparedEvent::Button { typ, button, x, y, modifiers } => {
+							xtest::fake_motion(&self.display, root_x + x, root_y + y);
+							xtest::fake_button(&self.display, button.physical_in(&mapping), typ == ButtonType::Press, modifiers);
+						}
+						PreparedEvent::Motion { x, y, .. } => xtest::fake_motion(&self.display, root_x + x, root_y + y),
+						PreparedEvent::Key { .. } => {}
+					}
+				}
+			};
+			if self.display.neutral_modifiers() { xtest::without_held_modifiers(&self.display, fake) } else { fake() }
+			unsafe { XFlush(self.display.as_raw()) };
+			return Ok(());
+		}
+
+		let root = Window::default_root_window(Arc::clone(&self.display)).window;
+		// Presses and releases are mostly at the same position, so the last child found is kept.
+		let mut last_child: Option<((c_int, c_int), XWindow)> = None;
+		let mut child_at = |x: c_int, y: c_int| match last_child {
+			Some((position, child)) if position == (x, y) => child,
+			_ => {
+				let child = self.child_at(x, y).ok().flatten().map_or(0, |it| it.window);
+				last_child = Some(((x, y), child));
+				child
+			}
+		};
+		// One trap around all the events, it waits for the server once like the flush did.
+		self.trapped(|| for event in &events {
+			match *event {
+				PreparedEvent::KeyCode { typ, code, modifiers } => {
+					self.post(self.key_event(typ, code, modifiers, &EventOptions::default()), typ.mask());
+				}
+				PreparedEvent::Button { typ, button, x, y, modifiers } => {
+					let ev = XEvent {
+						button: XButtonEvent {
+							type_: typ.into(),
+							serial: 0,
+							send_event: 1,
+							display: self.display.as_raw(),
+							window: self.window,
+							root,
+							subwindow: child_at(x, y),
+							time: self.display.event_time(),
+							x,
+							y,
+							x_root: root_x + x,
+							y_root: root_y + y,
+							state: modifiers,
+							button: button.number(),
+							same_screen: True,
+						}
+					};
+					self.post(ev, typ.mask());
+				}
+				PreparedEvent::Motion { x, y, modifiers } => {
+					let ev = XEvent {
+						motion: XMotionEvent {
+							type_: MotionNotify,
+							serial: 0,
+							send_event: 1,
+							display: self.display.as_raw(),
+							window: self.window,
+							root,
+							subwindow: child_at(x, y),
+							time: self.display.event_time(),
+							x,
+							y,
+							x_root: root_x + x,
+							y_root: root_y + y,
+							state: modifiers,
+							is_hint: 0,
+							same_screen: True,
+						}
+					};
+					let buttons = Button1MotionMask | Button2MotionMask | Button3MotionMask | Button4MotionMask | Button5MotionMask;
+					self.post(ev, PointerMotionMask | ButtonMotionMask | buttons);
+				}
+				PreparedEvent::Key { .. } => {}
+			}
+		})
+	}
+}
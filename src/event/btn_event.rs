@@ -1,10 +1,19 @@
 use std::ffi::c_int;
-use std::os::raw::c_long;
+use std::os::raw::{c_long, c_uint};
 
-use x11::xlib::{ButtonPress, ButtonPressMask, ButtonRelease, ButtonReleaseMask};
+use x11::xlib::{
+	Button1, Button1Mask, Button2, Button3, Button4, Button5, ButtonPress, ButtonPressMask, ButtonRelease, ButtonReleaseMask,
+	XGetPointerMapping,
+};
 
+use crate::Display;
+
+/// Button event type
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ButtonType {
+	/// Button press
 	Press,
+	/// Button release
 	Release,
 }
 
@@ -34,4 +43,119 @@ impl From<ButtonType> for c_int {
 			}
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Direction of the scroll wheel, see [crate::Window::scroll].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScrollDirection {
+	/// Scroll up, button 4
+	Up,
+	/// Scroll down, button 5
+	Down,
+	/// Scroll left, button 6
+	Left,
+	/// Scroll right, button 7
+	Right,
+}
+
+impl ScrollDirection {
+	/// Get the button that X uses for one step of the wheel in this direction
+	#[inline]
+	pub fn button(&self) -> c_uint {
+		match self {
+			ScrollDirection::Up => Button4,
+			ScrollDirection::Down => Button5,
+			ScrollDirection::Left => 6,
+			ScrollDirection::Right => 7,
+		}
+	}
+}
+
+/// A logical mouse button, what applications see after the pointer mapping of the server.
+///
+/// Raw button numbers like [x11::xlib::Button1] convert into this, so they can still be passed
+/// wherever a [MouseButton] is expected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+	/// The primary button, button 1.
+	Left,
+	/// The wheel or middle button, button 2.
+	Middle,
+	/// The secondary button, button 3.
+	Right,
+	/// One step of the wheel up, button 4.
+	ScrollUp,
+	/// One step of the wheel down, button 5.
+	ScrollDown,
+	/// The back button on the side of many mice, button 8.
+	X1,
+	/// The forward button on the side of many mice, button 9.
+	X2,
+	/// Any other button by its number, e.g. 6 and 7 for scrolling sideways.
+	Other(u8),
+}
+
+impl MouseButton {
+	/// The number of the logical button, as in the events applications receive.
+	pub fn number(&self) -> c_uint {
+		match self {
+			MouseButton::Left => Button1,
+			MouseButton::Middle => Button2,
+			MouseButton::Right => Button3,
+			MouseButton::ScrollUp => Button4,
+			MouseButton::ScrollDown => Button5,
+			MouseButton::X1 => 8,
+			MouseButton::X2 => 9,
+			MouseButton::Other(number) => *number as c_uint,
+		}
+	}
+
+	/// The bit of this button in the state of events while it is held, 0 for buttons above 5 that have none.
+	pub fn mask(&self) -> c_uint {
+		match self.number() {
+			number @ 1..=5 => Button1Mask << (number - 1),
+			_ => 0,
+		}
+	}
+
+	/// The physical button the server maps to this one, which is what XTest fakes.
+	///
+	/// Left-handed configurations usually swap 1 and 3, buttons that nothing maps to keep their number.
+	pub(crate) fn physical(&self, display: &Display) -> c_uint {
+		self.physical_in(&pointer_mapping(display))
+	}
+
+	/// Like [Self::physical], with the pointer mapping already read by [pointer_mapping].
+	pub(crate) fn physical_in(&self, mapping: &[u8]) -> c_uint {
+		let number = self.number();
+		mapping.iter().position(|it| *it as c_uint == number).map_or(number, |it| it as c_uint + 1)
+	}
+}
+
+impl From<c_uint> for MouseButton {
+	fn from(number: c_uint) -> Self {
+		match number {
+			typ if typ == Button1 => MouseButton::Left,
+			typ if typ == Button2 => MouseButton::Middle,
+			typ if typ == Button3 => MouseButton::Right,
+			typ if typ == Button4 => MouseButton::ScrollUp,
+			typ if typ == Button5 => MouseButton::ScrollDown,
+			8 => MouseButton::X1,
+			9 => MouseButton::X2,
+			number => MouseButton::Other(number as u8),
+		}
+	}
+}
+
+impl From<ScrollDirection> for MouseButton {
+	fn from(direction: ScrollDirection) -> Self {
+		MouseButton::from(direction.button())
+	}
+}
+
+/// The logical button of every physical button of the pointer, the first is for button 1.
+pub(crate) fn pointer_mapping(display: &Display) -> Vec<u8> {
+	let mut map = [0u8; 256];
+	let count = unsafe { XGetPointerMapping(display.as_raw(), map.as_mut_ptr(), map.len() as _) };
+	map[..count.clamp(0, 256) as usize].to_vec()
+}
@@ -0,0 +1,41 @@
+use std::ffi::c_int;
+use std::os::raw::c_long;
+
+use x11::xlib::{EnterNotify, EnterWindowMask, LeaveNotify, LeaveWindowMask};
+
+/// Crossing event type
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CrossingType {
+	/// The pointer entered the window
+	Enter,
+	/// The pointer left the window
+	Leave,
+}
+
+impl CrossingType {
+	/// Get type mask
+	#[inline]
+	pub fn mask(&self) -> c_long {
+		match self {
+			CrossingType::Enter => {
+				EnterWindowMask
+			}
+			CrossingType::Leave => {
+				LeaveWindowMask
+			}
+		}
+	}
+}
+
+impl From<CrossingType> for c_int {
+	fn from(value: CrossingType) -> Self {
+		match value {
+			CrossingType::Enter => {
+				EnterNotify
+			}
+			CrossingType::Leave => {
+				LeaveNotify
+			}
+		}
+	}
+}
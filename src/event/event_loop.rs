@@ -0,0 +1,145 @@
+use std::{
+	mem::MaybeUninit,
+	os::raw::c_long,
+	time::Duration,
+};
+
+use x11::xlib::{KeyPress, XEvent, XFlush, XNextEvent, XPending, XSelectInput};
+
+use crate::{Display, Session, Window};
+
+use super::{Event, Subscription};
+
+/// How often [EventLoop::next] and the watchers look for new events and for a cancel while waiting.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Receives the events of the windows that were selected with [Self::select].
+///
+/// Events are read from the connection of the session, so hotkeys registered with
+/// [Session::register_hotkey] run while the loop reads events as well.
+///
+/// # Example
+/// ```ignore
+/// use x11::xlib::{PropertyChangeMask, StructureNotifyMask};
+/// let events = session.event_loop();
+/// events.select(&window, PropertyChangeMask | StructureNotifyMask);
+/// events.run(|event| {
+///     println!("{:?}", event);
+///     !matches!(event, Event::Destroy { .. })
+/// });
+/// ```
+pub struct EventLoop<'a> {
+	session: &'a Session,
+}
+
+impl<'a> EventLoop<'a> {
+	pub(crate) fn new(session: &'a Session) -> Self {
+		EventLoop { session }
+	}
+
+	/// Chooses which events of the window are received, e.g. [x11::xlib::PropertyChangeMask].
+	///
+	/// This replaces the mask selected for the window by this connection before,
+	/// a mask of 0 stops receiving events from the window.
+	pub fn select(&self, window: &Window, mask: c_long) {
+		unsafe {
+			XSelectInput(self.session.display.as_raw(), window.window, mask);
+			XFlush(self.session.display.as_raw());
+		}
+	}
+
+	/// Adds `mask` to the events received from the window until the returned guard is dropped.
+	///
+	/// Unlike [Self::select] this keeps what was selected before, by the watchers of the crate or by hand,
+	/// and only takes off the events that nothing else needs afterwards, see [crate::Display::set_mask_policy].
+	/// # Example
+	/// ```ignore
+	/// let _focus = events.subscribe(&window, FocusChangeMask);
+	/// for title in window.watch_title() {
+	///     // Both the title and the focus changes of the window are received here.
+	/// }
+	/// ```
+	pub fn subscribe(&self, window: &Window, mask: c_long) -> Subscription<'a> {
+		Subscription::new(&self.session.display, window.window, mask)
+	}
+
+	/// Waits for the next event, [None] once the [crate::CancelToken] of the session is cancelled.
+	///
+	/// The connection is checked for new events every 10 milliseconds while waiting,
+	/// so that a cancel from another thread is noticed.
+	pub fn next(&self) -> Option<Event> {
+		self.next_raw().map(|it| Event::from(&it))
+	}
+
+	/// Gets the next event if one has arrived already, without waiting.
+	pub fn poll(&self) -> Option<Event> {
+		if unsafe { XPending(self.session.display.as_raw()) } > 0 {
+			self.next()
+		} else {
+			None
+		}
+	}
+
+	/// Iterates over the events as they arrive, waiting for every one of them.
+	///
+	/// The iterator ends once the session is cancelled, otherwise stop with `break` or an adapter like [Iterator::take_while].
+	pub fn iter(&self) -> Events<'a> {
+		Events { events: EventLoop { session: self.session } }
+	}
+
+	/// Calls `handler` for every event until it returns false or the session is cancelled.
+	pub fn run(&self, mut handler: impl FnMut(Event) -> bool) {
+		while let Some(event) = self.next() {
+			if !handler(event) {
+				break;
+			}
+		}
+	}
+
+	#[cfg(feature = "async")]
+	pub(crate) fn display(&self) -> &crate::Display {
+		&self.session.display
+	}
+
+	/// Like [Self::next] without converting the event, for the watchers that need the raw fields.
+	pub(crate) fn next_raw(&self) -> Option<XEvent> {
+		let event = wait_event(&self.session.display)?;
+		if event.get_type() == KeyPress {
+			self.session.run_hotkey(unsafe { &event.key });
+		}
+		Some(event)
+	}
+}
+
+/// Waits for the next event of the connection and records it in the histories,
+/// [None] once the [crate::CancelToken] of the display is cancelled.
+pub(crate) fn wait_event(display: &Display) -> Option<XEvent> {
+	let token = display.cancel_token();
+	while !token.is_cancelled() {
+		if unsafe { XPending(display.as_raw()) } > 0 {
+			let mut event = MaybeUninit::<XEvent>::uninit();
+			let event = unsafe {
+				XNextEvent(display.as_raw(), event.as_mut_ptr());
+				event.assume_init()
+			};
+			display.histories().record(display, &event);
+			display.note_event(&event);
+			return Some(event);
+		}
+		token.sleep(CANCEL_POLL_INTERVAL);
+	}
+	None
+}
+
+/// The events of an [EventLoop] as an iterator, see [EventLoop::iter] and [Session::events].
+pub struct Events<'a> {
+	events: EventLoop<'a>,
+}
+
+impl Iterator for Events<'_> {
+	type Item = Event;
+
+	fn next(&mut self) -> Option<Event> {
+		self.events.next()
+	}
+}
@@ -0,0 +1,37 @@
+use std::os::raw::c_int;
+
+use x11::xlib::{Time, Window as XWindow};
+
+/// The fields of synthesized key and button events that the crate usually fills in itself,
+/// see [crate::Window::send_key_with] and [crate::Window::send_btn_with].
+///
+/// Some toolkits route key events by the pointer position in them, or only accept events
+/// for a child window that is named in `subwindow`. Fields that are [None] get the usual values.
+/// These only apply to events sent with XSendEvent, the server fills in the events faked with XTest.
+///
+/// # Example
+/// ```ignore
+/// let options = EventOptions { position: Some((120, 40)), subwindow: Some(canvas), ..Default::default() };
+/// win.send_key_with(KeyType::Press, XK_space, 0, &options);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EventOptions {
+	/// Where the pointer is in the event, relative to the window.
+	///
+	/// Key events are at 1, 1 by default, button events at the position they are sent to.
+	pub position: Option<(c_int, c_int)>,
+	/// The timestamp of the event in milliseconds, like the timestamps of the server.
+	///
+	/// Defaults to [x11::xlib::CurrentTime], or to increasing timestamps for clicks.
+	pub time: Option<Time>,
+	/// The child window the event is for, defaults to the child at the position for buttons and to none for keys.
+	pub subwindow: Option<XWindow>,
+	/// Whether the pointer is on the same screen as the window.
+	pub same_screen: bool,
+}
+
+impl Default for EventOptions {
+	fn default() -> Self {
+		EventOptions { position: None, time: None, subwindow: None, same_screen: true }
+	}
+}
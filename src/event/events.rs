@@ -0,0 +1,249 @@
+use std::os::raw::{c_int, c_long, c_uint};
+
+use x11::xlib::{
+	Atom as XAtom, ButtonPress, ButtonRelease, ClientMessage, ConfigureNotify, CreateNotify, DestroyNotify, EnterNotify, Expose, FocusIn,
+	FocusOut, KeyCode, KeyPress, KeySym, KeyRelease, LeaveNotify, MapNotify, MotionNotify, PropertyDelete, PropertyNotify,
+	ReparentNotify, UnmapNotify, Window as XWindow, XEvent,
+};
+
+use crate::Rect;
+
+use super::key_event::{decode, DecodedKey};
+
+/// An event received from the X server, see [super::EventLoop].
+///
+/// Positions are relative to `window`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+	/// A key was pressed.
+	KeyPress {
+		/// The window that got the event.
+		window: XWindow,
+		/// The key code of the key.
+		keycode: KeyCode,
+		/// The keysym of the key with the modifiers applied, see [super::key_event::decode].
+		keysym: KeySym,
+		/// The character the key types, [None] for keys like Shift or F1.
+		character: Option<char>,
+		/// The modifiers and buttons that were held.
+		state: c_uint,
+	},
+	/// A key was released.
+	KeyRelease {
+		/// The window that got the event.
+		window: XWindow,
+		/// The key code of the key.
+		keycode: KeyCode,
+		/// The keysym of the key with the modifiers applied, see [super::key_event::decode].
+		keysym: KeySym,
+		/// The character the key types, [None] for keys like Shift or F1.
+		character: Option<char>,
+		/// The modifiers and buttons that were held.
+		state: c_uint,
+	},
+	/// A mouse button was pressed.
+	ButtonPress {
+		/// The window that got the event.
+		window: XWindow,
+		/// The button, e.g. [x11::xlib::Button1].
+		button: c_uint,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+		/// The modifiers and buttons that were held.
+		state: c_uint,
+	},
+	/// A mouse button was released.
+	ButtonRelease {
+		/// The window that got the event.
+		window: XWindow,
+		/// The button, e.g. [x11::xlib::Button1].
+		button: c_uint,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+		/// The modifiers and buttons that were held.
+		state: c_uint,
+	},
+	/// The pointer moved.
+	Motion {
+		/// The window that got the event.
+		window: XWindow,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+		/// The modifiers and buttons that were held.
+		state: c_uint,
+	},
+	/// The pointer entered the window.
+	Enter {
+		/// The window that got the event.
+		window: XWindow,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+	},
+	/// The pointer left the window.
+	Leave {
+		/// The window that got the event.
+		window: XWindow,
+		/// Horizontal position.
+		x: c_int,
+		/// Vertical position.
+		y: c_int,
+	},
+	/// The window got the input focus.
+	FocusIn {
+		/// The window that got the event.
+		window: XWindow,
+	},
+	/// The window lost the input focus.
+	FocusOut {
+		/// The window that got the event.
+		window: XWindow,
+	},
+	/// A property of the window changed or was deleted.
+	Property {
+		/// The window that got the event.
+		window: XWindow,
+		/// The name of the property, compare it with [crate::Atom::new].
+		atom: XAtom,
+		/// Whether the property was deleted instead of changed.
+		deleted: bool,
+	},
+	/// The window was moved, resized or restacked.
+	Configure {
+		/// The window that got the event.
+		window: XWindow,
+		/// The new position, relative to the parent, and the new size.
+		rect: Rect,
+	},
+	/// A child window was created.
+	Create {
+		/// The parent of the new window.
+		parent: XWindow,
+		/// The new window.
+		window: XWindow,
+	},
+	/// The window was destroyed.
+	Destroy {
+		/// The window that got the event.
+		window: XWindow,
+	},
+	/// The window was mapped.
+	Map {
+		/// The window that got the event.
+		window: XWindow,
+	},
+	/// The window was unmapped.
+	Unmap {
+		/// The window that got the event.
+		window: XWindow,
+	},
+	/// A part of the window has to be drawn again.
+	Expose {
+		/// The window that got the event.
+		window: XWindow,
+		/// The part that has to be drawn.
+		rect: Rect,
+		/// How many more expose events for the window follow this one.
+		count: c_int,
+	},
+	/// The window got a new parent, e.g. the frame of a window manager.
+	Reparent {
+		/// The window that got the event.
+		window: XWindow,
+		/// The new parent.
+		parent: XWindow,
+	},
+	/// Another client sent a message, e.g. `WM_DELETE_WINDOW` in `WM_PROTOCOLS`.
+	ClientMessage {
+		/// The window the message is for.
+		window: XWindow,
+		/// What the message is about, compare it with [crate::Atom::new].
+		message_type: XAtom,
+		/// Whether the data is made of 8, 16 or 32 bit items.
+		format: c_int,
+		/// The data as five 32 bit items.
+		data: [c_long; 5],
+	},
+	/// Any other event, with its type, e.g. [x11::xlib::SelectionNotify].
+	Other(c_int),
+}
+
+impl From<&XEvent> for Event {
+	fn from(event: &XEvent) -> Self {
+		let typ = event.get_type();
+		// Every arm only reads the member of the union that belongs to the type.
+		unsafe {
+			match typ {
+				_ if typ == KeyPress || typ == KeyRelease => {
+					let key = event.key;
+					let (window, keycode, state) = (key.window, key.keycode as KeyCode, key.state);
+					let DecodedKey { keysym, text } = decode(&key);
+					let character = text.chars().next();
+					if typ == KeyPress {
+						Event::KeyPress { window, keycode, keysym, character, state }
+					} else {
+						Event::KeyRelease { window, keycode, keysym, character, state }
+					}
+				}
+				_ if typ == ButtonPress || typ == ButtonRelease => {
+					let btn = event.button;
+					let (window, button, x, y, state) = (btn.window, btn.button, btn.x, btn.y, btn.state);
+					if typ == ButtonPress {
+						Event::ButtonPress { window, button, x, y, state }
+					} else {
+						Event::ButtonRelease { window, button, x, y, state }
+					}
+				}
+				_ if typ == MotionNotify => {
+					let motion = event.motion;
+					Event::Motion { window: motion.window, x: motion.x, y: motion.y, state: motion.state }
+				}
+				_ if typ == EnterNotify => {
+					let crossing = event.crossing;
+					Event::Enter { window: crossing.window, x: crossing.x, y: crossing.y }
+				}
+				_ if typ == LeaveNotify => {
+					let crossing = event.crossing;
+					Event::Leave { window: crossing.window, x: crossing.x, y: crossing.y }
+				}
+				_ if typ == FocusIn => Event::FocusIn { window: event.focus_change.window },
+				_ if typ == FocusOut => Event::FocusOut { window: event.focus_change.window },
+				_ if typ == PropertyNotify => {
+					let property = event.property;
+					Event::Property { window: property.window, atom: property.atom, deleted: property.state == PropertyDelete }
+				}
+				_ if typ == ConfigureNotify => {
+					let configure = event.configure;
+					let rect = Rect::new(configure.x, configure.y, configure.width as u32, configure.height as u32);
+					Event::Configure { window: configure.window, rect }
+				}
+				_ if typ == CreateNotify => Event::Create { parent: event.create_window.parent, window: event.create_window.window },
+				_ if typ == DestroyNotify => Event::Destroy { window: event.destroy_window.window },
+				_ if typ == MapNotify => Event::Map { window: event.map.window },
+				_ if typ == UnmapNotify => Event::Unmap { window: event.unmap.window },
+				_ if typ == Expose => {
+					let expose = event.expose;
+					let rect = Rect::new(expose.x, expose.y, expose.width as u32, expose.height as u32);
+					Event::Expose { window: expose.window, rect, count: expose.count }
+				}
+				_ if typ == ReparentNotify => Event::Reparent { window: event.reparent.window, parent: event.reparent.parent },
+				_ if typ == ClientMessage => {
+					let message = event.client_message;
+					let mut data = [0; 5];
+					for (i, item) in data.iter_mut().enumerate() {
+						*item = message.data.get_long(i);
+					}
+					Event::ClientMessage { window: message.window, message_type: message.message_type, format: message.format, data }
+				}
+				_ => Event::Other(typ),
+			}
+		}
+	}
+}
@@ -0,0 +1,54 @@
+use x11::xlib::{ConfigureNotify, DestroyNotify, StructureNotifyMask};
+
+use crate::{Rect, Window};
+
+use super::{event_loop::wait_event, Subscription};
+
+/// Yields the area of a window every time it is moved or resized, see [Window::watch_geometry].
+///
+/// The area is the one [Window::capture] covers, the window without its decorations relative to the root window.
+///
+/// Every call to [Iterator::next] waits for the next change, and ends when the window is destroyed
+/// or the [crate::CancelToken] of the display is cancelled. Other events of the connection are dropped while waiting.
+pub struct GeometryWatcher<'a> {
+	window: &'a Window,
+	last: Rect,
+	_subscription: Subscription<'a>,
+}
+
+impl<'a> GeometryWatcher<'a> {
+	pub(crate) fn new(window: &'a Window) -> Self {
+		let subscription = Subscription::new(&window.display, window.window, StructureNotifyMask);
+		let attr = window.get_attr();
+		let (x, y) = window.root_position().unwrap_or_default();
+		GeometryWatcher { window, last: Rect::new(x, y, attr.width as u32, attr.height as u32), _subscription: subscription }
+	}
+}
+
+impl Iterator for GeometryWatcher<'_> {
+	type Item = Rect;
+
+	fn next(&mut self) -> Option<Rect> {
+		loop {
+			let event = wait_event(&self.window.display)?;
+			let typ = event.get_type();
+			if typ == DestroyNotify && unsafe { event.destroy_window }.window == self.window.window {
+				return None;
+			}
+			if typ != ConfigureNotify {
+				continue;
+			}
+			let configure = unsafe { event.configure };
+			if configure.window != self.window.window {
+				continue;
+			}
+			// The position in the event is relative to the frame, unless the window manager sent it.
+			let (x, y) = self.window.root_position().ok()?;
+			let rect = Rect::new(x, y, configure.width as u32, configure.height as u32);
+			if rect != self.last {
+				self.last = rect;
+				return Some(rect);
+			}
+		}
+	}
+}
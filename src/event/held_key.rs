@@ -0,0 +1,50 @@
+use std::os::raw::c_uint;
+
+use x11::xlib::{KeyCode, XFlush};
+
+use crate::keys::Remap;
+use crate::Window;
+
+use super::key_event::KeyType;
+
+/// A key that is held down until this is dropped, see [Window::hold_key].
+#[must_use = "the key is released as soon as it is dropped"]
+pub struct HeldKey<'a> {
+	window: &'a Window,
+	code: KeyCode,
+	modifiers: c_uint,
+	// Keeps a temporary binding of the key code alive until the key is released.
+	_remap: Remap,
+}
+
+impl<'a> HeldKey<'a> {
+	pub(crate) fn new(window: &'a Window, code: KeyCode, modifiers: c_uint, remap: Remap) -> Self {
+		window.send_keycode(KeyType::Press, code, modifiers);
+		unsafe { XFlush(window.display.as_raw()) };
+		HeldKey { window, code, modifiers, _remap: remap }
+	}
+
+	/// The key code that is held.
+	pub fn keycode(&self) -> KeyCode {
+		self.code
+	}
+
+	/// Sends the press again, like the auto repeat of a real keyboard does.
+	///
+	/// Events sent with [x11::xlib::XSendEvent] are not repeated by the server,
+	/// call this periodically for applications that expect repeats while a key is held.
+	pub fn refresh(&self) {
+		self.window.send_keycode(KeyType::Press, self.code, self.modifiers);
+		unsafe { XFlush(self.window.display.as_raw()) };
+	}
+
+	/// Releases the key now, which is the same as dropping this.
+	pub fn release(self) {}
+}
+
+impl Drop for HeldKey<'_> {
+	fn drop(&mut self) {
+		self.window.send_keycode(KeyType::Release, self.code, self.modifiers);
+		unsafe { XFlush(self.window.display.as_raw()) };
+	}
+}
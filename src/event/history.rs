@@ -0,0 +1,82 @@
+use std::{
+	collections::{HashMap, VecDeque},
+	mem::MaybeUninit,
+	os::raw::c_long,
+	time::Instant,
+};
+
+use x11::xlib::{PropertyChangeMask, StructureNotifyMask, Window as XWindow, XCheckWindowEvent, XEvent};
+
+use crate::{Atom, Display};
+
+use super::Event;
+
+/// The events a window with a history is selected for.
+pub(crate) const HISTORY_MASK: c_long = PropertyChangeMask | StructureNotifyMask;
+
+/// The id the events of a history are selected with, the ids of [super::Subscription]s start at 1.
+pub(crate) const HISTORY_SUBSCRIPTION: u64 = 0;
+
+/// A change of a window, kept by [crate::Window::record_history].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+	/// When the change was read from the connection, which is a bit after it happened.
+	pub time: Instant,
+	/// What changed, a [Event::Property], [Event::Configure], [Event::Map], [Event::Unmap] or [Event::Destroy].
+	pub event: Event,
+	/// The name of the property for [Event::Property], e.g. `_NET_WM_STATE`.
+	pub property: Option<String>,
+}
+
+/// The recent changes of every window whose history is recorded on a connection.
+#[derive(Default)]
+pub(crate) struct Histories(HashMap<XWindow, (usize, VecDeque<HistoryEntry>)>);
+
+impl Histories {
+	pub(crate) fn start(&mut self, window: XWindow, capacity: usize) {
+		let (size, entries) = self.0.entry(window).or_default();
+		*size = capacity.max(1);
+		while entries.len() > *size {
+			entries.pop_front();
+		}
+	}
+
+	pub(crate) fn stop(&mut self, window: XWindow) {
+		self.0.remove(&window);
+	}
+
+	pub(crate) fn entries(&self, window: XWindow) -> Vec<HistoryEntry> {
+		self.0.get(&window).map(|(_, entries)| entries.iter().cloned().collect()).unwrap_or_default()
+	}
+
+	pub(crate) fn is_recording(&self, window: XWindow) -> bool {
+		self.0.contains_key(&window)
+	}
+
+	/// Adds the event to the history of its window, if that is recorded and the event is a change.
+	pub(crate) fn record(&mut self, display: &Display, event: &XEvent) {
+		let event = Event::from(event);
+		let (window, property) = match event {
+			Event::Property { window, atom, .. } => (window, Some(atom)),
+			Event::Configure { window, .. } | Event::Map { window } | Event::Unmap { window } | Event::Destroy { window } => (window, None),
+			_ => return,
+		};
+		let (size, entries) = match self.0.get_mut(&window) {
+			Some(it) => it,
+			None => return,
+		};
+		if entries.len() == *size {
+			entries.pop_front();
+		}
+		let property = property.and_then(|atom| Atom(atom).name(display));
+		entries.push_back(HistoryEntry { time: Instant::now(), event, property });
+	}
+
+	/// Takes the pending changes of the window off the connection and records them.
+	pub(crate) fn drain(&mut self, display: &Display, window: XWindow) {
+		let mut event = MaybeUninit::<XEvent>::uninit();
+		while unsafe { XCheckWindowEvent(display.as_raw(), window, HISTORY_MASK, event.as_mut_ptr()) } != 0 {
+			self.record(display, unsafe { &event.assume_init() });
+		}
+	}
+}
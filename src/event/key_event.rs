@@ -1,9 +1,12 @@
 use std::ffi::c_int;
 use std::os::raw::c_long;
 
-use x11::xlib::{KeyPress, KeyPressMask, KeyRelease, KeyReleaseMask};
+use x11::xlib::{KeyPress, KeyPressMask, KeyRelease, KeyReleaseMask, KeySym, XKeyEvent, XLookupString};
+
+use crate::keys::char_from_keysym;
 
 /// Key event type
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum KeyType {
 	/// Key press
 	Press,
@@ -37,4 +40,32 @@ impl From<KeyType> for c_int {
 			}
 		}
 	}
-}
\ No newline at end of file
+}
+/// The keysym and text of a received key event, see [decode].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodedKey {
+	/// The keysym of the key with the modifiers of the event applied, e.g. `A` when Shift was held.
+	pub keysym: KeySym,
+	/// What the key types, empty for keys like Shift or F1.
+	///
+	/// With Control held this is the control character, e.g. `"\u{3}"` for Control+C.
+	pub text: String,
+}
+
+/// Decodes a key event to the keysym and text it types, honoring Shift, Caps Lock, Num Lock and the layout group.
+///
+/// Text comes from [XLookupString], which only knows Latin-1,
+/// other characters are found from Unicode keysyms with [char_from_keysym].
+pub fn decode(event: &XKeyEvent) -> DecodedKey {
+	let mut event = *event;
+	let mut buf = [0u8; 32];
+	let mut keysym = 0;
+	let len = unsafe {
+		XLookupString(&mut event, buf.as_mut_ptr() as _, buf.len() as _, &mut keysym, std::ptr::null_mut())
+	};
+	let text = match len {
+		len if len > 0 => buf[..len as usize].iter().map(|&it| it as char).collect(),
+		_ => char_from_keysym(keysym).map(String::from).unwrap_or_default(),
+	};
+	DecodedKey { keysym, text }
+}
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, mem::MaybeUninit, os::raw::c_long};
+
+use x11::xlib::{Window as XWindow, XFlush, XGetWindowAttributes, XSelectInput, XWindowAttributes};
+
+use crate::Display;
+
+/// Whether the events the crate selects on a window are deselected once it stops watching, see [Display::set_mask_policy].
+///
+/// Event masks are kept per connection, so neither policy changes what the application
+/// that owns the window, or any other client, receives. They only decide what this connection
+/// keeps receiving, which matters to code that selected events on the same windows itself.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MaskPolicy {
+	/// Events are added to the mask the connection already selected for the window,
+	/// and the ones that were added are taken off it again once no watcher needs them anymore.
+	#[default]
+	Restore,
+	/// Events are added like with [MaskPolicy::Restore], and stay selected when the watchers are dropped.
+	Keep,
+}
+
+/// The events the crate selected on windows of a connection, on top of what was selected before.
+#[derive(Default)]
+pub(crate) struct EventMasks {
+	policy: MaskPolicy,
+	next_id: u64,
+	windows: HashMap<XWindow, Selected>,
+}
+
+#[derive(Default)]
+struct Selected {
+	/// The bits the crate added, that were not selected when it asked for them.
+	added: c_long,
+	subscriptions: Vec<(u64, c_long)>,
+}
+
+impl EventMasks {
+	pub(crate) fn policy(&self) -> MaskPolicy {
+		self.policy
+	}
+
+	pub(crate) fn set_policy(&mut self, policy: MaskPolicy) {
+		self.policy = policy;
+	}
+
+	pub(crate) fn next_id(&mut self) -> u64 {
+		self.next_id += 1;
+		self.next_id
+	}
+}
+
+/// Adds `mask` to the events selected for the window, as the subscription `id`.
+///
+/// Selecting with an `id` that is already subscribed to the window replaces its mask.
+pub(crate) fn select(display: &Display, window: XWindow, id: u64, mask: c_long) {
+	let (current, _) = display.with_error_trap(|| your_event_mask(display, window));
+	let current = match current {
+		Some(current) => current,
+		None => return,
+	};
+	let mut masks = display.event_masks();
+	let selected = masks.windows.entry(window).or_default();
+	selected.added |= mask & !current;
+	match selected.subscriptions.iter_mut().find(|(it, _)| *it == id) {
+		Some((_, it)) => *it = mask,
+		None => selected.subscriptions.push((id, mask)),
+	}
+	if current | mask != current {
+		unsafe {
+			XSelectInput(display.as_raw(), window, current | mask);
+			XFlush(display.as_raw());
+		}
+	}
+}
+
+/// Ends the subscription `id` to the window, deselecting the events only it needed with [MaskPolicy::Restore].
+///
+/// Events that were selected before the crate asked for them stay selected.
+pub(crate) fn release(display: &Display, window: XWindow, id: u64) {
+	let (policy, unneeded) = {
+		let mut masks = display.event_masks();
+		let policy = masks.policy;
+		let selected = match masks.windows.get_mut(&window) {
+			Some(selected) => selected,
+			None => return,
+		};
+		selected.subscriptions.retain(|(it, _)| *it != id);
+		let needed = selected.subscriptions.iter().fold(0, |mask, (_, it)| mask | it);
+		let unneeded = selected.added & !needed;
+		selected.added &= needed;
+		if selected.subscriptions.is_empty() {
+			masks.windows.remove(&window);
+		}
+		(policy, unneeded)
+	};
+	if policy == MaskPolicy::Keep || unneeded == 0 {
+		return;
+	}
+	// The window may be gone already, which is fine.
+	display.with_error_trap(|| {
+		if let Some(current) = your_event_mask(display, window) {
+			unsafe { XSelectInput(display.as_raw(), window, current & !unneeded) };
+		}
+	});
+}
+
+fn your_event_mask(display: &Display, window: XWindow) -> Option<c_long> {
+	let mut attr = MaybeUninit::<XWindowAttributes>::uninit();
+	if unsafe { XGetWindowAttributes(display.as_raw(), window, attr.as_mut_ptr()) } == 0 {
+		return None;
+	}
+	Some(unsafe { attr.assume_init() }.your_event_mask)
+}
+
+/// Events selected on a window by the crate, or with [super::EventLoop::subscribe], until this is dropped.
+///
+/// What happens to the events then depends on the [MaskPolicy] of the display.
+pub struct Subscription<'a> {
+	display: &'a Display,
+	window: XWindow,
+	id: u64,
+}
+
+impl<'a> Subscription<'a> {
+	/// Adds `mask` to the events the connection receives from the window.
+	pub(crate) fn new(display: &'a Display, window: XWindow, mask: c_long) -> Self {
+		let id = display.event_masks().next_id();
+		select(display, window, id, mask);
+		Subscription { display, window, id }
+	}
+
+	/// The window the events are selected on.
+	pub fn window(&self) -> XWindow {
+		self.window
+	}
+}
+
+impl Drop for Subscription<'_> {
+	fn drop(&mut self) {
+		release(self.display, self.window, self.id);
+	}
+}
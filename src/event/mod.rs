@@ -1,2 +1,49 @@
+/// Keyboard events.
 pub mod key_event;
+/// Keys that stay pressed while a guard is alive.
+pub mod held_key;
+/// Mouse button events.
 pub mod btn_event;
+/// Pointer crossing events.
+pub mod crossing_event;
+/// How synthesized events are delivered.
+pub mod backend;
+/// Planning pointer movements along curved paths.
+pub mod motion;
+mod active_watcher;
+mod app_watcher;
+mod batch;
+mod event_loop;
+mod event_options;
+mod events;
+mod geometry_watcher;
+mod history;
+mod masks;
+#[cfg(feature = "async")]
+mod stream;
+mod property_watcher;
+mod stacking_watcher;
+mod title_watcher;
+mod window_watcher;
+#[cfg(feature = "xtest")]
+pub(crate) mod xtest;
+
+pub use self::{
+	active_watcher::ActiveWindowWatcher,
+	app_watcher::{App, AppWindowWatcher},
+	batch::PreparedEvent,
+	event_loop::{EventLoop, Events},
+	event_options::EventOptions,
+	events::Event,
+	geometry_watcher::GeometryWatcher,
+	history::HistoryEntry,
+	masks::{MaskPolicy, Subscription},
+	property_watcher::{PropertyChange, PropertyWatcher},
+	stacking_watcher::{Restack, StackingWatcher},
+	title_watcher::TitleWatcher,
+	window_watcher::{WindowChange, WindowWatcher},
+};
+#[cfg(feature = "async")]
+pub use self::stream::EventStream;
+pub(crate) use self::history::{Histories, HISTORY_MASK, HISTORY_SUBSCRIPTION};
+pub(crate) use self::masks::{EventMasks, release as release_mask, select as select_mask};
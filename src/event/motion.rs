@@ -0,0 +1,128 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How the speed of the pointer changes along a path, see [MotionProfile].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+	/// Constant speed.
+	Linear,
+	/// Speeds up at the start and slows down at the end, like a hand does.
+	#[default]
+	EaseInOut,
+	/// Starts fast and slows down when it gets close to the target.
+	EaseOut,
+}
+
+impl Easing {
+	/// Maps the elapsed fraction of the movement to the travelled fraction of the path.
+	pub fn apply(&self, t: f64) -> f64 {
+		let t = t.clamp(0.0, 1.0);
+		match self {
+			Easing::Linear => t,
+			Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+			Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+		}
+	}
+}
+
+/// Describes how the pointer moves from one point to another, see [crate::Window::move_pointer].
+///
+/// The pointer follows a cubic Bezier curve with randomly placed control points,
+/// with a little noise added to every step, instead of jumping straight to the target.
+/// This makes hover effects fire along the way and looks less like a script.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionProfile {
+	/// How long the whole movement takes.
+	pub duration: Duration,
+	/// Time between two motion events.
+	pub step: Duration,
+	/// How far the curve bends away from the straight line, as a fraction of the distance.
+	pub curvature: f64,
+	/// The largest random offset added to every point, in pixels.
+	pub jitter: f64,
+	/// How the speed changes along the path.
+	pub easing: Easing,
+	/// Seed for the random curve and noise, the same seed always gives the same path.
+	///
+	/// With `None` a new seed is taken from the clock for every movement.
+	pub seed: Option<u64>,
+}
+
+impl Default for MotionProfile {
+	fn default() -> Self {
+		MotionProfile {
+			duration: Duration::from_millis(400),
+			step: Duration::from_millis(10),
+			curvature: 0.2,
+			jitter: 1.0,
+			easing: Easing::default(),
+			seed: None,
+		}
+	}
+}
+
+impl MotionProfile {
+	/// A profile that moves in a straight line at constant speed, without any randomness.
+	pub fn linear(duration: Duration) -> Self {
+		MotionProfile {
+			duration,
+			curvature: 0.0,
+			jitter: 0.0,
+			easing: Easing::Linear,
+			..Default::default()
+		}
+	}
+
+	/// Plans the points the pointer passes from `from` to `to`, one for every [Self::step].
+	///
+	/// `from` itself is not included, the last point is always exactly `to`.
+	pub fn path(&self, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+		let mut rng = Rng::new(self.seed.unwrap_or_else(|| {
+			SystemTime::now().duration_since(UNIX_EPOCH).map(|it| it.as_nanos() as u64).unwrap_or(0)
+		}));
+		let steps = (self.duration.as_nanos() / self.step.as_nanos().max(1)).max(1) as usize;
+
+		let (x0, y0) = (from.0 as f64, from.1 as f64);
+		let (x3, y3) = (to.0 as f64, to.1 as f64);
+		let (dx, dy) = (x3 - x0, y3 - y0);
+		// The control points sit a third and two thirds along the line, pushed sideways.
+		let bend = |rng: &mut Rng, along: f64| {
+			let side = rng.next_signed() * self.curvature;
+			(x0 + dx * along - dy * side, y0 + dy * along + dx * side)
+		};
+		let (x1, y1) = bend(&mut rng, 1.0 / 3.0);
+		let (x2, y2) = bend(&mut rng, 2.0 / 3.0);
+
+		(1..=steps)
+			.map(|i| {
+				if i == steps {
+					return to;
+				}
+				let t = self.easing.apply(i as f64 / steps as f64);
+				let u = 1.0 - t;
+				let x = u * u * u * x0 + 3.0 * u * u * t * x1 + 3.0 * u * t * t * x2 + t * t * t * x3;
+				let y = u * u * u * y0 + 3.0 * u * u * t * y1 + 3.0 * u * t * t * y2 + t * t * t * y3;
+				let x = x + rng.next_signed() * self.jitter;
+				let y = y + rng.next_signed() * self.jitter;
+				(x.round() as i32, y.round() as i32)
+			})
+			.collect()
+	}
+}
+
+/// A small xorshift generator, good enough for noise that only has to look random.
+struct Rng(u64);
+
+impl Rng {
+	fn new(seed: u64) -> Self {
+		// Xorshift never leaves zero, so that state is avoided.
+		Rng((seed ^ 0x9e37_79b9_7f4a_7c15).max(1))
+	}
+
+	/// A number between -1 and 1.
+	fn next_signed(&mut self) -> f64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		(self.0 >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+	}
+}
@@ -0,0 +1,74 @@
+use x11::xlib::{Atom as XAtom, DestroyNotify, PropertyChangeMask, PropertyDelete, PropertyNotify, StructureNotifyMask};
+
+use crate::{Atom, Window, NET_WM_NAME};
+
+use super::{event_loop::wait_event, Subscription};
+
+/// A property of a window that changed, see [PropertyWatcher].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyChange {
+	/// The atom of the property.
+	pub atom: XAtom,
+	/// The name of the property, e.g. `_NET_WM_STATE`.
+	pub name: String,
+	/// Whether the property was deleted instead of changed.
+	pub deleted: bool,
+}
+
+/// Yields the properties of a window every time one changes, see [Window::watch_properties].
+///
+/// Every call to [Iterator::next] waits for the next change, and ends when the window is destroyed
+/// or the [crate::CancelToken] of the display is cancelled. Other events of the connection are dropped while waiting.
+pub struct PropertyWatcher<'a> {
+	window: &'a Window,
+	names: Option<Vec<String>>,
+	_subscription: Subscription<'a>,
+}
+
+impl<'a> PropertyWatcher<'a> {
+	pub(crate) fn new(window: &'a Window) -> Self {
+		let subscription = Subscription::new(&window.display, window.window, PropertyChangeMask | StructureNotifyMask);
+		PropertyWatcher { window, names: None, _subscription: subscription }
+	}
+
+	/// Only reports the properties called one of `names`, e.g. `_NET_WM_STATE`.
+	pub fn only(mut self, names: &[&str]) -> Self {
+		self.names = Some(names.iter().map(|it| it.to_string()).collect());
+		self
+	}
+
+	/// Only reports changes of the title, `WM_NAME` and `_NET_WM_NAME`.
+	///
+	/// Both are usually set one after the other, use [Window::watch_title] to get every new title once.
+	pub fn titles(self) -> Self {
+		self.only(&["WM_NAME", NET_WM_NAME])
+	}
+}
+
+impl Iterator for PropertyWatcher<'_> {
+	type Item = PropertyChange;
+
+	fn next(&mut self) -> Option<PropertyChange> {
+		loop {
+			let event = wait_event(&self.window.display)?;
+			let typ = event.get_type();
+			if typ == DestroyNotify && unsafe { event.destroy_window }.window == self.window.window {
+				return None;
+			}
+			if typ != PropertyNotify {
+				continue;
+			}
+			let property = unsafe { event.property };
+			if property.window != self.window.window {
+				continue;
+			}
+			let name = match Atom(property.atom).name(&self.window.display) {
+				Some(it) => it,
+				None => continue,
+			};
+			if self.names.as_ref().is_none_or(|it| it.contains(&name)) {
+				return Some(PropertyChange { atom: property.atom, name, deleted: property.state == PropertyDelete });
+			}
+		}
+	}
+}
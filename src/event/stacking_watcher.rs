@@ -0,0 +1,162 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use x11::xlib::{Atom as XAtom, PropertyChangeMask, PropertyNotify, Window as XWindow};
+
+use crate::{
+	Atom, Session, Window, NET_CLIENT_LIST_STACKING,
+};
+
+use super::{EventLoop, Subscription};
+
+/// A window that moved in the stacking order, see [StackingWatcher].
+#[derive(Clone)]
+pub struct Restack {
+	/// The window that was raised or lowered.
+	pub window: Window,
+	/// The window right above it now, [None] if it is on top.
+	pub above: Option<XWindow>,
+	/// The window right below it now, [None] if it is at the bottom.
+	pub below: Option<XWindow>,
+}
+
+/// Yields the windows that move above or below others, see [Session::watch_stacking].
+///
+/// Changes of `_NET_CLIENT_LIST_STACKING` on the root window are compared with the order before,
+/// the windows that keep their place relative to most others are left out, so raising one window
+/// reports only that one. Windows that are created or destroyed are not reported, see [Session::watch_windows].
+///
+/// Every call to [Iterator::next] waits for the next change, and ends when the [crate::CancelToken]
+/// of the session is cancelled. Other events of the connection are dropped while waiting,
+/// hotkeys registered with [Session::register_hotkey] still run.
+pub struct StackingWatcher<'a> {
+	session: &'a Session,
+	events: EventLoop<'a>,
+	stacking_list: XAtom,
+	stacking: Vec<XWindow>,
+	pending: VecDeque<Restack>,
+	_subscription: Subscription<'a>,
+}
+
+impl<'a> StackingWatcher<'a> {
+	pub(crate) fn new(session: &'a Session) -> Self {
+		let subscription = Subscription::new(&session.display, session.root().window, PropertyChangeMask);
+		let mut watcher = StackingWatcher {
+			session,
+			events: EventLoop::new(session),
+			stacking_list: Atom::new(&session.display, NET_CLIENT_LIST_STACKING).map_or(0, |it| it.0),
+			stacking: Vec::new(),
+			pending: VecDeque::new(),
+			_subscription: subscription,
+		};
+		watcher.stacking = watcher.read_stacking();
+		watcher
+	}
+
+	/// The client windows in their current stacking order, from the bottom to the top.
+	pub fn stacking(&self) -> &[XWindow] {
+		&self.stacking
+	}
+
+	fn read_stacking(&self) -> Vec<XWindow> {
+		if self.stacking_list == 0 {
+			return Vec::new();
+		}
+		let backend = self.session.backend();
+		backend
+			.get_property(backend.root(), self.stacking_list)
+			.map(|it| it.values().into_iter().map(|it| it as XWindow).collect())
+			.unwrap_or_default()
+	}
+
+	/// Queues the windows that moved since the last time.
+	fn diff_stacking(&mut self) {
+		let now = self.read_stacking();
+		let before: Vec<_> = self.stacking.iter().copied().filter(|it| now.contains(it)).collect();
+		let after: Vec<_> = now.iter().copied().filter(|it| before.contains(it)).collect();
+		let kept = longest_common_order(&before, &after);
+		for window in after.iter().filter(|it| !kept.contains(it)) {
+			let index = now.iter().position(|it| it == window).unwrap_or_default();
+			self.pending.push_back(Restack {
+				window: Window { window: *window, display: Arc::clone(&self.session.display) },
+				above: now.get(index + 1).copied(),
+				below: index.checked_sub(1).map(|it| now[it]),
+			});
+		}
+		self.stacking = now;
+	}
+}
+
+impl Iterator for StackingWatcher<'_> {
+	type Item = Restack;
+
+	fn next(&mut self) -> Option<Restack> {
+		loop {
+			if let Some(restack) = self.pending.pop_front() {
+				return Some(restack);
+			}
+			let event = self.events.next_raw()?;
+			if event.get_type() != PropertyNotify {
+				continue;
+			}
+			let property = unsafe { event.property };
+			if property.window == self.session.root().window && property.atom == self.stacking_list {
+				self.diff_stacking();
+			}
+		}
+	}
+}
+
+/// The longest list of windows that are in the same order in both, the windows that didn't move.
+fn longest_common_order(before: &[XWindow], after: &[XWindow]) -> Vec<XWindow> {
+	let columns = after.len() + 1;
+	let mut lengths = vec![0usize; (before.len() + 1) * columns];
+	for i in (0..before.len()).rev() {
+		for j in (0..after.len()).rev() {
+			lengths[i * columns + j] = if before[i] == after[j] {
+				lengths[(i + 1) * columns + j + 1] + 1
+			} else {
+				lengths[(i + 1) * columns + j].max(lengths[i * columns + j + 1])
+			};
+		}
+	}
+	let (mut i, mut j, mut kept) = (0, 0, Vec::new());
+	while i < before.len() && j < after.len() {
+		if before[i] == after[j] {
+			kept.push(before[i]);
+			i += 1;
+			j += 1;
+		} else if lengths[(i + 1) * columns + j] >= lengths[i * columns + j + 1] {
+			i += 1;
+		} else {
+			j += 1;
+		}
+	}
+	kept
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn raising_a_window_keeps_the_others() {
+		assert_eq!(longest_common_order(&[1, 2, 3, 4], &[1, 3, 4, 2]), vec![1, 3, 4]);
+	}
+
+	#[test]
+	fn lowering_a_window_keeps_the_others() {
+		assert_eq!(longest_common_order(&[1, 2, 3, 4], &[4, 1, 2, 3]), vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn unchanged_and_empty_orders() {
+		assert_eq!(longest_common_order(&[1, 2, 3], &[1, 2, 3]), vec![1, 2, 3]);
+		assert_eq!(longest_common_order(&[], &[1, 2]), Vec::<XWindow>::new());
+		assert_eq!(longest_common_order(&[1, 2], &[]), Vec::<XWindow>::new());
+	}
+
+	#[test]
+	fn swapped_windows_keep_one_of_them() {
+		assert_eq!(longest_common_order(&[1, 2, 3, 4], &[2, 1, 4, 3]).len(), 2);
+	}
+}
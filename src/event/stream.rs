@@ -0,0 +1,69 @@
+use std::{
+	future::poll_fn,
+	io,
+	os::unix::io::{BorrowedFd, OwnedFd},
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::io::unix::AsyncFd;
+
+use super::{Event, EventLoop};
+
+/// Events of the windows selected with [EventLoop::select] as a [Stream], see [crate::Session::event_stream].
+///
+/// Queued events are returned right away, otherwise the stream waits for [crate::Display::connection_fd]
+/// to become readable with tokio's [AsyncFd] and reads what arrived with `XPending`. No thread is started
+/// and none is blocked, create and poll the stream on a tokio runtime with IO enabled.
+///
+/// # Example
+/// ```ignore
+/// use futures::StreamExt;
+/// session.event_loop().select(&window, PropertyChangeMask);
+/// let mut events = session.event_stream()?;
+/// while let Some(event) = events.next().await {
+///     println!("{:?}", event);
+/// }
+/// ```
+pub struct EventStream<'a> {
+	events: EventLoop<'a>,
+	fd: AsyncFd<OwnedFd>,
+}
+
+impl<'a> EventStream<'a> {
+	pub(crate) fn new(events: EventLoop<'a>) -> io::Result<Self> {
+		// Every stream registers a descriptor of its own, the reactor refuses one that is registered already.
+		let fd = unsafe { BorrowedFd::borrow_raw(events.display().connection_fd()) }.try_clone_to_owned()?;
+		Ok(EventStream { events, fd: AsyncFd::new(fd)? })
+	}
+
+	/// Waits for the next event.
+	pub async fn next(&mut self) -> Option<Event> {
+		poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+	}
+}
+
+impl Stream for EventStream<'_> {
+	type Item = Event;
+
+	/// Polls for the next event, the stream ends once the [crate::CancelToken] of the session is cancelled
+	/// or the runtime can't wait for the connection anymore.
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+		let this = self.get_mut();
+		loop {
+			if this.events.display().cancel_token().is_cancelled() {
+				return Poll::Ready(None);
+			}
+			if let Some(event) = this.events.poll() {
+				return Poll::Ready(Some(event));
+			}
+			// The readiness is only cleared after the connection was read, so no event is missed.
+			match this.fd.poll_read_ready(cx) {
+				Poll::Ready(Ok(mut guard)) => guard.clear_ready(),
+				Poll::Ready(Err(_)) => return Poll::Ready(None),
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+	}
+}
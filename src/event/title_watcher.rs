@@ -0,0 +1,61 @@
+use x11::xlib::{Atom as XAtom, BadWindow, DestroyNotify, PropertyChangeMask, PropertyNotify, StructureNotifyMask, XA_WM_NAME};
+
+use crate::{Atom, NET_WM_NAME, Window};
+
+use super::{event_loop::wait_event, Subscription};
+
+/// Yields the new title of a window every time it changes, see [Window::watch_title].
+///
+/// Every call to [Iterator::next] waits until the title changes, and ends when the window is destroyed
+/// or the [crate::CancelToken] of the display is cancelled. Other events of the connection are dropped while waiting.
+pub struct TitleWatcher<'a> {
+	window: &'a Window,
+	net_wm_name: XAtom,
+	last: Option<String>,
+	_subscription: Subscription<'a>,
+}
+
+impl<'a> TitleWatcher<'a> {
+	pub(crate) fn new(window: &'a Window) -> Self {
+		let subscription = Subscription::new(&window.display, window.window, PropertyChangeMask | StructureNotifyMask);
+		TitleWatcher {
+			window,
+			net_wm_name: Atom::new(&window.display, NET_WM_NAME).map_or(0, |it| it.0),
+			last: window.title(),
+			_subscription: subscription,
+		}
+	}
+}
+
+impl Iterator for TitleWatcher<'_> {
+	type Item = String;
+
+	fn next(&mut self) -> Option<String> {
+		loop {
+			let event = wait_event(&self.window.display)?;
+			let typ = event.get_type();
+			if typ == DestroyNotify && unsafe { event.destroy_window }.window == self.window.window {
+				return None;
+			}
+			if typ != PropertyNotify {
+				continue;
+			}
+			let property = unsafe { event.property };
+			let is_title = property.atom == XA_WM_NAME || (self.net_wm_name != 0 && property.atom == self.net_wm_name);
+			if property.window != self.window.window || !is_title {
+				continue;
+			}
+			// Both names are usually set one after the other, only report real changes.
+			// The window can be destroyed before its DestroyNotify is read.
+			let (title, errors) = self.window.display.with_error_trap(|| self.window.title());
+			if errors.iter().any(|it| it.error_code == BadWindow) {
+				return None;
+			}
+			let title = title?;
+			if self.last.as_ref() != Some(&title) {
+				self.last = Some(title.clone());
+				return Some(title);
+			}
+		}
+	}
+}
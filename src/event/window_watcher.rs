@@ -0,0 +1,104 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use x11::xlib::{
+	Atom as XAtom, CreateNotify, DestroyNotify, PropertyChangeMask, PropertyNotify, SubstructureNotifyMask,
+	Window as XWindow,
+};
+
+use crate::{Session, Window};
+
+use super::{EventLoop, Subscription};
+
+/// A window that appeared or went away, see [WindowWatcher].
+#[derive(Clone)]
+pub enum WindowChange {
+	/// A window was created, or added to `_NET_CLIENT_LIST`.
+	Created(Window),
+	/// A window was destroyed, or removed from `_NET_CLIENT_LIST`.
+	Destroyed(XWindow),
+}
+
+/// Yields the windows that are created and destroyed, see [Session::watch_windows].
+///
+/// With a window manager that keeps `_NET_CLIENT_LIST` the changes of that list are reported,
+/// so frames, menus and tooltips are left out and the windows are the same ones [Session::get_windows] returns.
+/// Without it, every child of the root window that is created or destroyed is reported.
+///
+/// Every call to [Iterator::next] waits for the next change, and ends when the [crate::CancelToken]
+/// of the session is cancelled. Other events of the connection are dropped while waiting,
+/// hotkeys registered with [Session::register_hotkey] still run.
+pub struct WindowWatcher<'a> {
+	session: &'a Session,
+	events: EventLoop<'a>,
+	client_list: XAtom,
+	known: Vec<XWindow>,
+	pending: VecDeque<WindowChange>,
+	_subscription: Subscription<'a>,
+}
+
+impl<'a> WindowWatcher<'a> {
+	pub(crate) fn new(session: &'a Session) -> Self {
+		let mask = SubstructureNotifyMask | PropertyChangeMask;
+		let subscription = Subscription::new(&session.display, session.root().window, mask);
+		WindowWatcher {
+			session,
+			events: EventLoop::new(session),
+			client_list: session.client_list().0,
+			known: client_ids(session),
+			pending: VecDeque::new(),
+			_subscription: subscription,
+		}
+	}
+
+	/// Queues the windows that were added to or removed from the client list since the last time.
+	fn diff_client_list(&mut self) {
+		let now = client_ids(self.session);
+		for gone in self.known.iter().filter(|it| !now.contains(it)) {
+			self.pending.push_back(WindowChange::Destroyed(*gone));
+		}
+		let created: Vec<_> = now.iter().filter(|it| !self.known.contains(it)).map(|it| self.window(*it)).collect();
+		self.pending.extend(created.into_iter().map(WindowChange::Created));
+		self.known = now;
+	}
+
+	fn window(&self, window: XWindow) -> Window {
+		Window { window, display: Arc::clone(&self.session.display) }
+	}
+}
+
+impl Iterator for WindowWatcher<'_> {
+	type Item = WindowChange;
+
+	fn next(&mut self) -> Option<WindowChange> {
+		loop {
+			if let Some(change) = self.pending.pop_front() {
+				return Some(change);
+			}
+			let event = self.events.next_raw()?;
+			let root = self.session.root().window;
+			let typ = event.get_type();
+			if self.client_list != 0 {
+				if typ == PropertyNotify {
+					let property = unsafe { event.property };
+					if property.window == root && property.atom == self.client_list {
+						self.diff_client_list();
+					}
+				}
+			} else if typ == CreateNotify {
+				let create = unsafe { event.create_window };
+				if create.parent == root {
+					self.pending.push_back(WindowChange::Created(self.window(create.window)));
+				}
+			} else if typ == DestroyNotify {
+				let destroy = unsafe { event.destroy_window };
+				if destroy.event == root {
+					self.pending.push_back(WindowChange::Destroyed(destroy.window));
+				}
+			}
+		}
+	}
+}
+
+fn client_ids(session: &Session) -> Vec<XWindow> {
+	session.get_windows().map(|it| it.iter().map(|it| it.window).collect()).unwrap_or_default()
+}
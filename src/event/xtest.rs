@@ -0,0 +1,126 @@
+use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+use std::slice;
+
+use x11::xlib::{CurrentTime, Display as XDisplay, False, KeyCode, LockMask, True, XFlush, XFreeModifiermap, XGetModifierMapping, XQueryKeymap};
+use x11::xtest::{XTestFakeButtonEvent, XTestFakeKeyEvent, XTestFakeMotionEvent, XTestQueryExtension};
+
+use crate::{Display, Error};
+use crate::keys::num_lock_mask;
+
+// The binding in the x11 crate has an extra argument, this is the signature from XTest.h.
+extern "C" {
+	fn XTestFakeRelativeMotionEvent(display: *mut XDisplay, dx: c_int, dy: c_int, delay: c_ulong) -> c_int;
+}
+
+/// Whether the X server supports the XTest extension.
+pub(crate) fn is_supported(display: &Display) -> bool {
+	let (mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 0);
+	unsafe { XTestQueryExtension(display.as_raw(), &mut event_base, &mut error_base, &mut major, &mut minor) != 0 }
+}
+
+/// Gets a key code for every modifier in the `modifiers` mask, e.g. [x11::xlib::ShiftMask].
+pub(crate) fn modifier_keycodes(display: &Display, modifiers: c_uint) -> Vec<KeyCode> {
+	if modifiers == 0 {
+		return Vec::new();
+	}
+	let map = unsafe { XGetModifierMapping(display.as_raw()) };
+	if map.is_null() {
+		return Vec::new();
+	}
+	let per_modifier = unsafe { (*map).max_keypermod } as usize;
+	let codes = unsafe { slice::from_raw_parts((*map).modifiermap, per_modifier * 8) };
+	let res = (0..8)
+		.filter(|bit| modifiers & (1 << bit) != 0)
+		.filter_map(|bit| codes[bit * per_modifier..(bit + 1) * per_modifier].iter().copied().find(|it| *it != 0))
+		.collect();
+	unsafe { XFreeModifiermap(map) };
+	res
+}
+
+/// Fakes a key event, the modifiers are pressed before a key press and released after a key release.
+pub(crate) fn fake_key(display: &Display, keycode: KeyCode, press: bool, modifiers: c_uint) {
+	let modifiers = modifier_keycodes(display, modifiers);
+	unsafe {
+		if press {
+			modifiers.iter().for_each(|it| { XTestFakeKeyEvent(display.as_raw(), *it as _, True, CurrentTime); });
+			XTestFakeKeyEvent(display.as_raw(), keycode as _, True, CurrentTime);
+		} else {
+			XTestFakeKeyEvent(display.as_raw(), keycode as _, False, CurrentTime);
+			modifiers.iter().rev().for_each(|it| { XTestFakeKeyEvent(display.as_raw(), *it as _, False, CurrentTime); });
+		}
+	}
+}
+
+/// Like [fake_key] and [fake_button] together, for [crate::Session::fake_key] and the like,
+/// without the modifiers the user holds if [Display::neutral_modifiers] is on.
+pub(crate) fn fake(display: &Display, send: impl FnOnce()) -> Result<(), Error> {
+	if !is_supported(display) {
+		return Err(Error::ExtensionMissing("XTEST"));
+	}
+	if display.neutral_modifiers() { without_held_modifiers(display, send) } else { send() }
+	unsafe { XFlush(display.as_raw()) };
+	Ok(())
+}
+
+/// Moves the pointer to a position on the root window.
+pub(crate) fn fake_motion(display: &Display, x: i32, y: i32) {
+	// A screen of -1 means the screen the pointer is on.
+	unsafe { XTestFakeMotionEvent(display.as_raw(), -1, x, y, CurrentTime) };
+}
+
+/// Moves the pointer by `dx` and `dy` from its current position.
+///
+/// Unlike warping, this is seen as motion by applications that read raw pointer deltas.
+pub(crate) fn fake_relative_motion(display: &Display, dx: i32, dy: i32) {
+	unsafe { XTestFakeRelativeMotionEvent(display.as_raw(), dx, dy, CurrentTime) };
+}
+
+/// Fakes a button event at the current position of the pointer.
+pub(crate) fn fake_button(display: &Display, button: c_uint, press: bool, modifiers: c_uint) {
+	let modifiers = modifier_keycodes(display, modifiers);
+	unsafe {
+		if press {
+			modifiers.iter().for_each(|it| { XTestFakeKeyEvent(display.as_raw(), *it as _, True, CurrentTime); });
+			XTestFakeButtonEvent(display.as_raw(), button, True, CurrentTime);
+		} else {
+			XTestFakeButtonEvent(display.as_raw(), button, False, CurrentTime);
+			modifiers.iter().rev().for_each(|it| { XTestFakeKeyEvent(display.as_raw(), *it as _, False, CurrentTime); });
+		}
+	}
+}
+
+/// Gets the key codes of the modifier keys that are physically held down right now.
+///
+/// Caps Lock and Num Lock are locks rather than held keys, releasing them would toggle them, so they are left out.
+fn held_modifier_keycodes(display: &Display) -> Vec<KeyCode> {
+	let mut keys = [0 as c_char; 32];
+	unsafe { XQueryKeymap(display.as_raw(), keys.as_mut_ptr()) };
+	let is_down = |code: KeyCode| keys[code as usize / 8] as u8 & (1 << (code % 8)) != 0;
+	let locks = LockMask | num_lock_mask(display);
+	let map = unsafe { XGetModifierMapping(display.as_raw()) };
+	if map.is_null() {
+		return Vec::new();
+	}
+	let per_modifier = unsafe { (*map).max_keypermod } as usize;
+	let codes = unsafe { slice::from_raw_parts((*map).modifiermap, per_modifier * 8) };
+	let mut held = (0..8)
+		.filter(|bit| locks & (1 << bit) == 0)
+		.flat_map(|bit| codes[bit * per_modifier..(bit + 1) * per_modifier].iter().copied())
+		.filter(|code| *code != 0 && is_down(*code))
+		.collect::<Vec<_>>();
+	unsafe { XFreeModifiermap(map) };
+	held.sort_unstable();
+	held.dedup();
+	held
+}
+
+/// Releases the modifier keys the user holds, runs `send` and presses them again.
+///
+/// Used when [Display::neutral_modifiers] is on, so a held Shift doesn't change the faked input.
+pub(crate) fn without_held_modifiers<R>(display: &Display, send: impl FnOnce() -> R) -> R {
+	let held = held_modifier_keycodes(display);
+	unsafe { held.iter().for_each(|it| { XTestFakeKeyEvent(display.as_raw(), *it as _, False, CurrentTime); }) };
+	let res = send();
+	unsafe { held.iter().for_each(|it| { XTestFakeKeyEvent(display.as_raw(), *it as _, True, CurrentTime); }) };
+	res
+}
@@ -0,0 +1,40 @@
+use std::mem::MaybeUninit;
+
+use x11::xlib::{AutoRepeatModeOff, XAutoRepeatOff, XAutoRepeatOn, XFlush, XGetKeyboardControl};
+
+use crate::Display;
+
+/// Restores the auto repeat of the keyboard when dropped, see [Display::set_auto_repeat].
+#[must_use = "auto repeat is restored as soon as the guard is dropped"]
+pub struct AutoRepeatGuard<'a> {
+	display: &'a Display,
+	previous: bool,
+}
+
+impl<'a> AutoRepeatGuard<'a> {
+	pub(crate) fn new(display: &'a Display, enabled: bool) -> Self {
+		let mut state = MaybeUninit::zeroed();
+		unsafe { XGetKeyboardControl(display.as_raw(), state.as_mut_ptr()) };
+		let previous = unsafe { state.assume_init() }.global_auto_repeat != AutoRepeatModeOff;
+		set(display, enabled);
+		AutoRepeatGuard { display, previous }
+	}
+
+	/// Keeps the new setting after this is dropped.
+	pub fn keep(self) {
+		std::mem::forget(self);
+	}
+}
+
+impl Drop for AutoRepeatGuard<'_> {
+	fn drop(&mut self) {
+		set(self.display, self.previous);
+	}
+}
+
+fn set(display: &Display, enabled: bool) {
+	unsafe {
+		if enabled { XAutoRepeatOn(display.as_raw()) } else { XAutoRepeatOff(display.as_raw()) };
+		XFlush(display.as_raw());
+	}
+}
@@ -0,0 +1,123 @@
+use std::ops::BitOr;
+use std::os::raw::c_int;
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+use x11::xfixes::{PointerBarrier as XPointerBarrier, XFixesCreatePointerBarrier, XFixesDestroyPointerBarrier, XFixesQueryExtension, XFixesQueryVersion};
+use x11::xlib::{Window as XWindow, XFlush};
+
+use crate::{Display, Error};
+
+/// A line on the screen that the pointer can't cross, for [crate::Session::create_pointer_barrier].
+///
+/// Barriers are always horizontal or vertical, the ends are in root window coordinates and both belong to the line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BarrierLine {
+	/// A vertical line at `x`, from `y1` to `y2`.
+	Vertical {
+		/// The column of the line.
+		x: c_int,
+		/// One end of the line.
+		y1: c_int,
+		/// The other end of the line.
+		y2: c_int,
+	},
+	/// A horizontal line at `y`, from `x1` to `x2`.
+	Horizontal {
+		/// The row of the line.
+		y: c_int,
+		/// One end of the line.
+		x1: c_int,
+		/// The other end of the line.
+		x2: c_int,
+	},
+}
+
+impl BarrierLine {
+	fn ends(&self) -> (c_int, c_int, c_int, c_int) {
+		match *self {
+			BarrierLine::Vertical { x, y1, y2 } => (x, y1, x, y2),
+			BarrierLine::Horizontal { y, x1, x2 } => (x1, y, x2, y),
+		}
+	}
+}
+
+/// The directions in which the pointer may still cross a barrier, combined with `|`.
+///
+/// [BarrierDirections::NONE] blocks the pointer both ways, directions along the line are ignored,
+/// so [BarrierDirections::POSITIVE_X] lets the pointer move right through a vertical barrier, but not left.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BarrierDirections(c_int);
+
+impl BarrierDirections {
+	/// The pointer can't cross the barrier at all.
+	pub const NONE: Self = BarrierDirections(0);
+	/// Towards larger x, to the right.
+	pub const POSITIVE_X: Self = BarrierDirections(1);
+	/// Towards larger y, downwards.
+	pub const POSITIVE_Y: Self = BarrierDirections(1 << 1);
+	/// Towards smaller x, to the left.
+	pub const NEGATIVE_X: Self = BarrierDirections(1 << 2);
+	/// Towards smaller y, upwards.
+	pub const NEGATIVE_Y: Self = BarrierDirections(1 << 3);
+
+	/// The bits as XFixes takes them.
+	pub fn bits(&self) -> c_int {
+		self.0
+	}
+}
+
+impl BitOr for BarrierDirections {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		BarrierDirections(self.0 | rhs.0)
+	}
+}
+
+/// Keeps the pointer from crossing a line until this is dropped, see [crate::Session::create_pointer_barrier].
+///
+/// Barriers hold back the pointer of every device, also the input of the user and input faked with XTest,
+/// but not warping, see [crate::Window::warp_pointer].
+#[must_use = "the barrier is removed as soon as it is dropped"]
+pub struct PointerBarrier {
+	display: Arc<Display>,
+	id: XPointerBarrier,
+	line: BarrierLine,
+}
+
+impl PointerBarrier {
+	pub(crate) fn new(display: Arc<Display>, root: XWindow, line: BarrierLine, allowed: BarrierDirections) -> Result<Self, Error> {
+		if !is_supported(&display) {
+			return Err(Error::ExtensionMissing("XFIXES"));
+		}
+		let (x1, y1, x2, y2) = line.ends();
+		let id = unsafe { XFixesCreatePointerBarrier(display.as_raw(), root, x1, y1, x2, y2, allowed.bits(), 0, null_mut()) };
+		unsafe { XFlush(display.as_raw()) };
+		Ok(PointerBarrier { display, id, line })
+	}
+
+	/// The line the pointer can't cross.
+	pub fn line(&self) -> BarrierLine {
+		self.line
+	}
+}
+
+impl Drop for PointerBarrier {
+	fn drop(&mut self) {
+		unsafe {
+			XFixesDestroyPointerBarrier(self.display.as_raw(), self.id);
+			XFlush(self.display.as_raw());
+		}
+	}
+}
+
+/// Whether the server has XFixes 5, which added pointer barriers.
+fn is_supported(display: &Display) -> bool {
+	let (mut event_base, mut error_base) = (0, 0);
+	if unsafe { XFixesQueryExtension(display.as_raw(), &mut event_base, &mut error_base) } == 0 {
+		return false;
+	}
+	let (mut major, minor) = (5, 0);
+	unsafe { XFixesQueryVersion(display.as_raw(), &mut major, &minor) != 0 && major >= 5 }
+}
@@ -0,0 +1,31 @@
+use std::os::raw::c_int;
+
+use x11::xlib::{GrabFrozen, GrabInvalidTime, GrabNotViewable, GrabSuccess};
+
+/// The reason the X server refused a grab.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GrabError {
+	/// Another client has the device grabbed already.
+	AlreadyGrabbed,
+	/// The grab was requested with a time before the last grab.
+	InvalidTime,
+	/// The window the grab is for is not viewable.
+	NotViewable,
+	/// The device is frozen by a grab of another client.
+	Frozen,
+	/// The connection that holds the grab for [crate::Window::block_input] could not be opened.
+	DisplayOpenFailed,
+}
+
+impl GrabError {
+	/// Turns the status returned by a grab request into a result.
+	pub(crate) fn check(status: c_int) -> Result<(), GrabError> {
+		Err(match status {
+			_ if status == GrabSuccess => return Ok(()),
+			_ if status == GrabInvalidTime => GrabError::InvalidTime,
+			_ if status == GrabNotViewable => GrabError::NotViewable,
+			_ if status == GrabFrozen => GrabError::Frozen,
+			_ => GrabError::AlreadyGrabbed,
+		})
+	}
+}
@@ -0,0 +1,97 @@
+use std::{
+	mem::MaybeUninit,
+	sync::mpsc::{channel, sync_channel, RecvTimeoutError, Sender},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use x11::xlib::{Window as XWindow, XEvent, XNextEvent, XPending};
+
+use crate::Display;
+
+use super::{GrabError, KeyboardGrab, PointerGrab, PointerGrabOptions};
+
+/// How often the connection that holds the grabs throws away the input it eats.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Keeps the user from typing and clicking while automation runs, see [crate::Window::block_input].
+///
+/// The grabs live on a connection of their own, on a worker thread, so they end when the
+/// duration is over even if this is never dropped, and input sent with
+/// [crate::event::backend::InputBackend::SendEvent] still reaches the window.
+/// Input faked with XTest is eaten like real input while blocked.
+#[must_use = "input is unblocked as soon as the block is dropped"]
+pub struct InputBlock {
+	stop: Option<Sender<()>>,
+	worker: Option<JoinHandle<()>>,
+}
+
+impl InputBlock {
+	pub(crate) fn new(window: XWindow, duration: Duration) -> Result<Self, GrabError> {
+		let (ready, grabbed) = sync_channel(1);
+		let (stop, stopped) = channel::<()>();
+		let worker = thread::Builder::new()
+			.name("x11-input-block".to_owned())
+			.spawn(move || {
+				let display = match Display::open() {
+					Some(display) => display.shared(),
+					None => {
+						let _ = ready.send(Err(GrabError::DisplayOpenFailed));
+						return;
+					}
+				};
+				let options = PointerGrabOptions { confine_to: Some(window), ..Default::default() };
+				let grabs = PointerGrab::new(display.clone(), window, options)
+					.and_then(|pointer| Ok((pointer, KeyboardGrab::new(display.clone(), window)?)));
+				let _grabs = match grabs {
+					Ok(grabs) => {
+						let _ = ready.send(Ok(()));
+						grabs
+					}
+					Err(err) => {
+						let _ = ready.send(Err(err));
+						return;
+					}
+				};
+				let deadline = Instant::now() + duration;
+				loop {
+					let left = deadline.saturating_duration_since(Instant::now());
+					match stopped.recv_timeout(left.min(DRAIN_INTERVAL)) {
+						Err(RecvTimeoutError::Timeout) if !left.is_zero() => drain(&display),
+						_ => break,
+					}
+				}
+			})
+			.map_err(|_| GrabError::DisplayOpenFailed)?;
+		match grabbed.recv() {
+			Ok(Ok(())) => Ok(InputBlock { stop: Some(stop), worker: Some(worker) }),
+			Ok(Err(err)) => Err(err),
+			Err(_) => Err(GrabError::DisplayOpenFailed),
+		}
+	}
+
+	/// Unblocks input now, which is the same as dropping this.
+	pub fn release(self) {}
+
+	/// Whether the duration is over and input is no longer blocked.
+	pub fn is_expired(&self) -> bool {
+		self.worker.as_ref().is_none_or(JoinHandle::is_finished)
+	}
+}
+
+impl Drop for InputBlock {
+	fn drop(&mut self) {
+		drop(self.stop.take());
+		if let Some(worker) = self.worker.take() {
+			let _ = worker.join();
+		}
+	}
+}
+
+/// Reads and throws away the events the grabs got, so they don't pile up in the connection.
+fn drain(display: &Display) {
+	let mut event = MaybeUninit::<XEvent>::uninit();
+	while unsafe { XPending(display.as_raw()) } > 0 {
+		unsafe { XNextEvent(display.as_raw(), event.as_mut_ptr()) };
+	}
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use x11::xlib::{CurrentTime, GrabModeAsync, True, Window as XWindow, XFlush, XGrabKeyboard, XUngrabKeyboard};
+
+use crate::Display;
+
+use super::GrabError;
+
+/// Keeps the keyboard grabbed until this is dropped, see [crate::Session::grab_keyboard].
+///
+/// While grabbed, every key event goes to this client instead of the focused window.
+#[must_use = "the keyboard is released as soon as the grab is dropped"]
+pub struct KeyboardGrab {
+	display: Arc<Display>,
+}
+
+impl KeyboardGrab {
+	pub(crate) fn new(display: Arc<Display>, window: XWindow) -> Result<Self, GrabError> {
+		let status = unsafe { XGrabKeyboard(display.as_raw(), window, True, GrabModeAsync, GrabModeAsync, CurrentTime) };
+		GrabError::check(status)?;
+		Ok(KeyboardGrab { display })
+	}
+
+	/// Releases the grab now, which is the same as dropping this.
+	pub fn release(self) {}
+}
+
+impl Drop for KeyboardGrab {
+	fn drop(&mut self) {
+		unsafe {
+			XUngrabKeyboard(self.display.as_raw(), CurrentTime);
+			XFlush(self.display.as_raw());
+		}
+	}
+}
@@ -0,0 +1,17 @@
+mod auto_repeat;
+#[cfg(feature = "xfixes")]
+mod barrier;
+mod error;
+mod input_block;
+mod keyboard;
+mod pointer;
+
+pub use self::{
+	auto_repeat::AutoRepeatGuard,
+	error::GrabError,
+	input_block::InputBlock,
+	keyboard::KeyboardGrab,
+	pointer::*,
+};
+#[cfg(feature = "xfixes")]
+pub use self::barrier::{BarrierDirections, BarrierLine, PointerBarrier};
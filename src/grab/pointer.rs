@@ -0,0 +1,103 @@
+use std::os::raw::{c_long, c_uint};
+use std::sync::Arc;
+
+use x11::xlib::{
+	ButtonPressMask, ButtonReleaseMask, CurrentTime, Cursor, False, GrabModeAsync, PointerMotionMask, True,
+	Window as XWindow, XCreateFontCursor, XFlush, XFreeCursor, XGrabPointer, XUngrabPointer,
+};
+
+use crate::Display;
+
+use super::GrabError;
+
+/// The crosshair of the standard cursor font, for [PointerGrabOptions::cursor].
+pub const CURSOR_CROSSHAIR: c_uint = 34;
+
+/// The four way arrow of the standard cursor font, for moving things.
+pub const CURSOR_FLEUR: c_uint = 52;
+
+/// The pointing hand of the standard cursor font, for clickable things.
+pub const CURSOR_HAND: c_uint = 60;
+
+/// The usual arrow of the standard cursor font.
+pub const CURSOR_LEFT_PTR: c_uint = 68;
+
+/// The question mark arrow of the standard cursor font, e.g. for "click on a window" pickers.
+pub const CURSOR_QUESTION_ARROW: c_uint = 92;
+
+/// The watch of the standard cursor font, for waiting.
+pub const CURSOR_WATCH: c_uint = 150;
+
+/// Options for [crate::Session::grab_pointer].
+#[derive(Copy, Clone, Debug)]
+pub struct PointerGrabOptions {
+	/// The pointer events reported while grabbed, e.g. [x11::xlib::ButtonPressMask].
+	pub event_mask: c_long,
+	/// Whether pointer events over windows of this client are reported to those windows as usual.
+	///
+	/// With `false` every event is reported to the grab window, relative to it.
+	pub owner_events: bool,
+	/// Keep the pointer inside this window while grabbed.
+	pub confine_to: Option<XWindow>,
+	/// Show this shape of the standard cursor font while grabbed, e.g. [CURSOR_CROSSHAIR].
+	///
+	/// The shapes are listed in `X11/cursorfont.h`.
+	pub cursor: Option<c_uint>,
+}
+
+impl Default for PointerGrabOptions {
+	fn default() -> Self {
+		Self {
+			event_mask: ButtonPressMask | ButtonReleaseMask | PointerMotionMask,
+			owner_events: true,
+			confine_to: None,
+			cursor: None,
+		}
+	}
+}
+
+/// Keeps the pointer grabbed until this is dropped, see [crate::Session::grab_pointer].
+///
+/// While grabbed, every pointer event goes to this client instead of the window under the pointer.
+#[must_use = "the pointer is released as soon as the grab is dropped"]
+pub struct PointerGrab {
+	display: Arc<Display>,
+	cursor: Cursor,
+}
+
+impl PointerGrab {
+	pub(crate) fn new(display: Arc<Display>, window: XWindow, options: PointerGrabOptions) -> Result<Self, GrabError> {
+		let cursor = options.cursor.map_or(0, |shape| unsafe { XCreateFontCursor(display.as_raw(), shape) });
+		let status = unsafe {
+			XGrabPointer(
+				display.as_raw(),
+				window,
+				if options.owner_events { True } else { False },
+				options.event_mask as c_uint,
+				GrabModeAsync,
+				GrabModeAsync,
+				options.confine_to.unwrap_or(0),
+				cursor,
+				CurrentTime,
+			)
+		};
+		// Dropping the guard frees the cursor.
+		let grab = PointerGrab { display, cursor };
+		GrabError::check(status).map(|_| grab)
+	}
+
+	/// Releases the grab now, which is the same as dropping this.
+	pub fn release(self) {}
+}
+
+impl Drop for PointerGrab {
+	fn drop(&mut self) {
+		unsafe {
+			XUngrabPointer(self.display.as_raw(), CurrentTime);
+			if self.cursor != 0 {
+				XFreeCursor(self.display.as_raw(), self.cursor);
+			}
+			XFlush(self.display.as_raw());
+		}
+	}
+}
@@ -0,0 +1,90 @@
+use std::{
+	os::raw::{c_int, c_uint},
+	sync::{Arc, Mutex, Weak},
+};
+
+use x11::xlib::{
+	BadAccess, False, GrabModeAsync, KeyCode, LockMask, Window as XWindow,
+	XGrabKey, XSync, XUngrabKey,
+};
+
+use crate::Display;
+use crate::keys::{KeyCombo, UnknownKey};
+use crate::keys::num_lock_mask;
+
+use super::{Callback, Registry};
+
+/// The reason a hotkey could not be registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HotkeyError {
+	/// The combo could not be parsed, see [KeyCombo::parse].
+	UnknownKey(UnknownKey),
+	/// The key is not on the current keyboard layout.
+	NotOnKeyboard,
+	/// Another client has registered the same combo already.
+	AlreadyGrabbed,
+}
+
+impl From<UnknownKey> for HotkeyError {
+	fn from(err: UnknownKey) -> Self {
+		HotkeyError::UnknownKey(err)
+	}
+}
+
+/// A global hotkey that stays registered until this is dropped, see [crate::Session::register_hotkey].
+#[must_use = "the hotkey is unregistered as soon as it is dropped"]
+pub struct Hotkey {
+	display: Arc<Display>,
+	root: XWindow,
+	code: KeyCode,
+	modifiers: c_uint,
+	locks: [c_uint; 4],
+	registry: Weak<Mutex<Registry>>,
+	id: u64,
+}
+
+impl Hotkey {
+	pub(crate) fn register(
+		display: Arc<Display>,
+		root: XWindow,
+		registry: &Arc<Mutex<Registry>>,
+		combo: &KeyCombo,
+		callback: Callback,
+	) -> Result<Self, HotkeyError> {
+		let (code, level) = display.keymap().lookup(combo.key).ok_or(HotkeyError::NotOnKeyboard)?;
+		let modifiers = combo.mask() | level;
+		let num_lock = num_lock_mask(&display);
+		// The grab only matches the exact state, so it is repeated for every combination of the locks.
+		let locks = [0, LockMask, num_lock, LockMask | num_lock];
+
+		let grabbed = catch_bad_access(&display, || {
+			for lock in &locks {
+				unsafe { XGrabKey(display.as_raw(), code as c_int, modifiers | lock, root, False, GrabModeAsync, GrabModeAsync) };
+			}
+		});
+		let id = registry.lock().unwrap_or_else(|it| it.into_inner()).insert(code, modifiers, callback);
+		let hotkey = Hotkey { display, root, code, modifiers, locks, registry: Arc::downgrade(registry), id };
+		if grabbed { Ok(hotkey) } else { Err(HotkeyError::AlreadyGrabbed) }
+	}
+
+	/// Unregisters the hotkey now, which is the same as dropping this.
+	pub fn unregister(self) {}
+}
+
+impl Drop for Hotkey {
+	fn drop(&mut self) {
+		for lock in &self.locks {
+			unsafe { XUngrabKey(self.display.as_raw(), self.code as c_int, self.modifiers | lock, self.root) };
+		}
+		unsafe { XSync(self.display.as_raw(), False) };
+		if let Some(registry) = self.registry.upgrade() {
+			registry.lock().unwrap_or_else(|it| it.into_inner()).remove(self.id);
+		}
+	}
+}
+
+/// Runs `requests` and waits for the server to answer them, returns false if any was refused with BadAccess.
+fn catch_bad_access(display: &Display, requests: impl FnOnce()) -> bool {
+	let ((), errors) = display.with_error_trap(requests);
+	!errors.iter().any(|it| it.error_code == BadAccess)
+}
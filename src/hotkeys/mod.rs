@@ -0,0 +1,5 @@
+mod hotkey;
+mod registry;
+
+pub use self::hotkey::{Hotkey, HotkeyError};
+pub(crate) use self::registry::{Callback, Registry};
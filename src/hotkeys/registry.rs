@@ -0,0 +1,46 @@
+use std::os::raw::c_uint;
+
+use x11::xlib::KeyCode;
+
+/// What a hotkey runs, it can be run on any thread that dispatches the hotkeys of the session.
+pub(crate) type Callback = Box<dyn FnMut() + Send>;
+
+/// The callbacks of the hotkeys of a session, see [crate::Session::register_hotkey].
+#[derive(Default)]
+pub(crate) struct Registry {
+	next_id: u64,
+	entries: Vec<Entry>,
+}
+
+struct Entry {
+	id: u64,
+	code: KeyCode,
+	modifiers: c_uint,
+	// Taken out while the callback runs, so that it can register and drop hotkeys itself.
+	callback: Option<Callback>,
+}
+
+impl Registry {
+	pub(crate) fn insert(&mut self, code: KeyCode, modifiers: c_uint, callback: Callback) -> u64 {
+		self.next_id += 1;
+		self.entries.push(Entry { id: self.next_id, code, modifiers, callback: Some(callback) });
+		self.next_id
+	}
+
+	pub(crate) fn remove(&mut self, id: u64) {
+		self.entries.retain(|it| it.id != id);
+	}
+
+	/// Takes the callback of the hotkey for the key, `modifiers` has to be without the locks.
+	pub(crate) fn take(&mut self, code: KeyCode, modifiers: c_uint) -> Option<(u64, Callback)> {
+		let entry = self.entries.iter_mut().find(|it| it.code == code && it.modifiers == modifiers)?;
+		Some((entry.id, entry.callback.take()?))
+	}
+
+	/// Puts a callback back after [Self::take], unless the hotkey was dropped meanwhile.
+	pub(crate) fn put_back(&mut self, id: u64, callback: Callback) {
+		if let Some(entry) = self.entries.iter_mut().find(|it| it.id == id) {
+			entry.callback = Some(callback);
+		}
+	}
+}
@@ -0,0 +1,189 @@
+use std::{
+	fmt,
+	os::raw::c_uint,
+	str::FromStr,
+};
+
+use x11::keysym::{XK_Alt_L, XK_Control_L, XK_ISO_Level3_Shift, XK_Shift_L, XK_Super_L};
+use x11::xlib::{ControlMask, KeySym, Mod1Mask, Mod4Mask, Mod5Mask, ShiftMask};
+
+use super::{keysym_from_name, name_from_keysym};
+
+/// A modifier key that can be part of a [KeyCombo].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Modifier {
+	/// Shift
+	Shift,
+	/// Control, parsed from `Ctrl` or `Control`.
+	Control,
+	/// Alt, parsed from `Alt` or `Meta`.
+	Alt,
+	/// The Windows or Command key, parsed from `Super`, `Win` or `Logo`.
+	Super,
+	/// The right Alt key on many European layouts, parsed from `AltGr`.
+	AltGr,
+}
+
+impl Modifier {
+	/// The bit of this modifier in the state of an event, assuming the common modifier mapping.
+	pub fn mask(&self) -> c_uint {
+		match self {
+			Modifier::Shift => ShiftMask,
+			Modifier::Control => ControlMask,
+			Modifier::Alt => Mod1Mask,
+			Modifier::Super => Mod4Mask,
+			Modifier::AltGr => Mod5Mask,
+		}
+	}
+
+	/// The keysym of the key that is pressed for this modifier.
+	pub fn keysym(&self) -> KeySym {
+		(match self {
+			Modifier::Shift => XK_Shift_L,
+			Modifier::Control => XK_Control_L,
+			Modifier::Alt => XK_Alt_L,
+			Modifier::Super => XK_Super_L,
+			Modifier::AltGr => XK_ISO_Level3_Shift,
+		}) as KeySym
+	}
+
+	/// The name [KeyCombo::parse] knows this modifier by.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Modifier::Shift => "Shift",
+			Modifier::Control => "Ctrl",
+			Modifier::Alt => "Alt",
+			Modifier::Super => "Super",
+			Modifier::AltGr => "AltGr",
+		}
+	}
+
+	fn from_name(name: &str) -> Option<Self> {
+		Some(match name.to_ascii_lowercase().as_str() {
+			"shift" => Modifier::Shift,
+			"ctrl" | "control" => Modifier::Control,
+			"alt" | "meta" => Modifier::Alt,
+			"super" | "win" | "logo" => Modifier::Super,
+			"altgr" => Modifier::AltGr,
+			_ => return None,
+		})
+	}
+}
+
+/// A keyboard shortcut like `Ctrl+Alt+F2`, see [crate::Window::send_combo].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+	/// The modifiers in the order they are pressed.
+	pub modifiers: Vec<Modifier>,
+	/// The keysym of the key that is tapped while the modifiers are held.
+	pub key: KeySym,
+}
+
+/// The error returned by [KeyCombo::parse] with the part that is not a known key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownKey(pub String);
+
+impl KeyCombo {
+	/// Parses a shortcut made of modifiers and a key joined by `+`, e.g. `Ctrl+Shift+T`.
+	///
+	/// Modifier names are case insensitive, see [Modifier] for the accepted names.
+	/// The key is any name [keysym_from_name] knows like `F2`, `Return`, `Enter` or `space`,
+	/// single letters always mean the key, so `Ctrl+T` is the same as `Ctrl+t`.
+	/// Keysyms without a name can be given in hex, like `0x1000e9`.
+	pub fn parse(combo: &str) -> Result<Self, UnknownKey> {
+		let mut parts = combo.split('+').map(str::trim).collect::<Vec<_>>();
+		// A trailing `+` means the plus key itself, e.g. `Ctrl++`.
+		if combo.ends_with("++") {
+			parts.truncate(parts.len() - 2);
+			parts.push("plus");
+		}
+		let (key, modifiers) = parts.split_last().ok_or_else(|| UnknownKey(String::new()))?;
+		let modifiers = modifiers
+			.iter()
+			.map(|it| Modifier::from_name(it).ok_or_else(|| UnknownKey(it.to_string())))
+			.collect::<Result<Vec<_>, _>>()?;
+		let key = key_from_name(key).ok_or_else(|| UnknownKey(key.to_string()))?;
+		Ok(KeyCombo { modifiers, key })
+	}
+
+	/// The state of an event while all the modifiers are held.
+	pub fn mask(&self) -> c_uint {
+		self.modifiers.iter().fold(0, |mask, it| mask | it.mask())
+	}
+}
+
+/// Writes the combo in the syntax of [KeyCombo::parse], e.g. `Ctrl+Shift+t`.
+impl fmt::Display for KeyCombo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for modifier in &self.modifiers {
+			write!(f, "{}+", modifier.name())?;
+		}
+		match name_from_keysym(self.key) {
+			Some(name) => f.write_str(&name),
+			None => write!(f, "{:#x}", self.key),
+		}
+	}
+}
+
+impl FromStr for KeyCombo {
+	type Err = UnknownKey;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		KeyCombo::parse(s)
+	}
+}
+
+/// Looks up the keysym of a key by its name, single letters always mean the key and not its shifted keysym.
+fn key_from_name(name: &str) -> Option<KeySym> {
+	let mut chars = name.chars();
+	let single = chars.next().filter(|_| chars.next().is_none());
+	if let Some(c) = single.filter(char::is_ascii_alphabetic) {
+		return keysym_from_name(&c.to_ascii_lowercase().to_string());
+	}
+	if let Some(hex) = name.strip_prefix("0x") {
+		return KeySym::from_str_radix(hex, 16).ok();
+	}
+	keysym_from_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+	use x11::keysym::{XK_F2, XK_Return, XK_plus, XK_t};
+
+	use super::*;
+
+	#[test]
+	fn parse_reads_modifiers_in_order() {
+		let combo = KeyCombo::parse("Ctrl+Shift+T").unwrap();
+		assert_eq!(combo.modifiers, vec![Modifier::Control, Modifier::Shift]);
+		assert_eq!(combo.key, XK_t as KeySym);
+		assert_eq!(combo.mask(), ControlMask | ShiftMask);
+	}
+
+	#[test]
+	fn parse_ignores_the_case_of_modifiers() {
+		let combo = KeyCombo::parse("control + META + win + altgr + F2").unwrap();
+		assert_eq!(combo.modifiers, vec![Modifier::Control, Modifier::Alt, Modifier::Super, Modifier::AltGr]);
+		assert_eq!(combo.key, XK_F2 as KeySym);
+	}
+
+	#[test]
+	fn parse_reads_the_plus_key_and_hex_keysyms() {
+		assert_eq!(KeyCombo::parse("Ctrl++").unwrap().key, XK_plus as KeySym);
+		assert_eq!(KeyCombo::parse("Alt+0x1000e9").unwrap().key, 0x1000e9);
+		assert_eq!(KeyCombo::parse("Return").unwrap(), KeyCombo { modifiers: vec![], key: XK_Return as KeySym });
+	}
+
+	#[test]
+	fn parse_rejects_unknown_parts() {
+		assert_eq!(KeyCombo::parse("Hyper+t"), Err(UnknownKey("Hyper".to_owned())));
+		assert_eq!(KeyCombo::parse("Ctrl+NoSuchKey"), Err(UnknownKey("NoSuchKey".to_owned())));
+	}
+
+	#[test]
+	fn display_is_read_back_by_parse() {
+		let combo = KeyCombo::parse("shift+ctrl+t").unwrap();
+		assert_eq!(combo.to_string(), "Shift+Ctrl+t");
+		assert_eq!(combo.to_string().parse(), Ok(combo));
+	}
+}
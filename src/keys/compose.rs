@@ -0,0 +1,95 @@
+use std::os::raw::c_uint;
+
+use x11::keysym::{
+	XK_Multi_key, XK_dead_abovering, XK_dead_acute, XK_dead_caron, XK_dead_cedilla, XK_dead_circumflex,
+	XK_dead_diaeresis, XK_dead_grave, XK_dead_tilde,
+};
+use x11::xlib::{KeyCode, KeySym};
+
+use super::{keysym_from_char, Keymap};
+
+/// An accent that dead keys and the compose key put on top of a letter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Accent {
+	/// `à`
+	Grave,
+	/// `á`
+	Acute,
+	/// `â`
+	Circumflex,
+	/// `ã`
+	Tilde,
+	/// `ä`
+	Diaeresis,
+	/// `å`
+	Ring,
+	/// `ç`
+	Cedilla,
+	/// `č`
+	Caron,
+}
+
+/// The letters every accent can be put on, and the accented letters they turn into.
+const LETTERS: [(Accent, &str, &str); 8] = [
+	(Accent::Grave, "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+	(Accent::Acute, "aeiouycnszlrAEIOUYCNSZLR", "áéíóúýćńśźĺŕÁÉÍÓÚÝĆŃŚŹĹŔ"),
+	(Accent::Circumflex, "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+	(Accent::Tilde, "anoANO", "ãñõÃÑÕ"),
+	(Accent::Diaeresis, "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+	(Accent::Ring, "auAU", "åůÅŮ"),
+	(Accent::Cedilla, "csCS", "çşÇŞ"),
+	(Accent::Caron, "cszrnedCSZRNED", "čšžřňěďČŠŽŘŇĚĎ"),
+];
+
+impl Accent {
+	/// Splits an accented letter into the accent and the plain letter.
+	pub fn decompose(c: char) -> Option<(Accent, char)> {
+		LETTERS.iter().find_map(|(accent, plain, accented)| {
+			let i = accented.chars().position(|it| it == c)?;
+			Some((*accent, plain.chars().nth(i)?))
+		})
+	}
+
+	/// The keysym of the dead key for this accent.
+	pub fn dead_keysym(&self) -> KeySym {
+		(match self {
+			Accent::Grave => XK_dead_grave,
+			Accent::Acute => XK_dead_acute,
+			Accent::Circumflex => XK_dead_circumflex,
+			Accent::Tilde => XK_dead_tilde,
+			Accent::Diaeresis => XK_dead_diaeresis,
+			Accent::Ring => XK_dead_abovering,
+			Accent::Cedilla => XK_dead_cedilla,
+			Accent::Caron => XK_dead_caron,
+		}) as KeySym
+	}
+
+	/// The character typed after the compose key for this accent, as in the default compose table.
+	pub fn compose_char(&self) -> char {
+		match self {
+			Accent::Grave => '`',
+			Accent::Acute => '\'',
+			Accent::Circumflex => '^',
+			Accent::Tilde => '~',
+			Accent::Diaeresis => '"',
+			Accent::Ring => 'o',
+			Accent::Cedilla => ',',
+			Accent::Caron => 'c',
+		}
+	}
+}
+
+/// Finds the keys that type an accented character on a layout that lacks it.
+///
+/// A dead key for the accent followed by the plain letter is preferred, otherwise the
+/// compose key, the accent and the letter are used. Every key is pressed and released in turn.
+pub fn compose_sequence(keymap: &Keymap, c: char) -> Option<Vec<(KeyCode, c_uint)>> {
+	let (accent, plain) = Accent::decompose(c)?;
+	let letter = keymap.resolve_char(plain)?;
+	if let Some(dead) = keymap.lookup(accent.dead_keysym()) {
+		return Some(vec![dead, letter]);
+	}
+	let compose = keymap.lookup(XK_Multi_key as KeySym)?;
+	let accent = keymap.lookup(keysym_from_char(accent.compose_char()))?;
+	Some(vec![compose, accent, letter])
+}
@@ -0,0 +1,277 @@
+use std::{
+	mem::MaybeUninit,
+	os::raw::{c_char, c_int, c_uint},
+	sync::Arc,
+	slice,
+	thread,
+	time::Duration,
+};
+
+use x11::keysym::{XK_BackSpace, XK_Escape, XK_Return, XK_Tab};
+use x11::xlib::{
+	Display as XDisplay, False, KeyCode, KeySym, MappingNotify, MappingPointer, NoSymbol, ShiftMask, XChangeKeyboardMapping, XCheckIfEvent,
+	XConvertCase, XDisplayKeycodes, XEvent, XFree, XGetKeyboardMapping, XSync, XkbAnyEvent, XkbMapNotify, XkbGroupStateMask, XkbMapNotifyMask,
+	XkbQueryExtension, XkbSelectEventDetails, XkbSelectEvents, XkbStateNotify,
+};
+
+use crate::Display;
+
+use super::{xkb::XKB_USE_CORE_KBD, XkbKeymap};
+
+/// Gets the keysym that types the character.
+///
+/// Latin-1 characters have a keysym of the same value, everything else uses a Unicode keysym.
+/// Newlines, tabs, backspaces and escapes map to the keysyms of their keys.
+pub fn keysym_from_char(c: char) -> KeySym {
+	match c {
+		'\n' | '\r' => XK_Return as KeySym,
+		'\t' => XK_Tab as KeySym,
+		'\u{8}' => XK_BackSpace as KeySym,
+		'\u{1b}' => XK_Escape as KeySym,
+		' '..='~' | '\u{a0}'..='\u{ff}' => c as KeySym,
+		_ => 0x0100_0000 + c as KeySym,
+	}
+}
+
+/// Gets the character a keysym types, the reverse of [keysym_from_char].
+///
+/// Only Latin-1 and Unicode keysyms have a character, and the keys for newlines, tabs, backspaces and escapes;
+/// the legacy keysyms of other scripts, e.g. Cyrillic, give [None].
+pub fn char_from_keysym(keysym: KeySym) -> Option<char> {
+	match keysym {
+		_ if keysym == XK_Return as KeySym => Some('\n'),
+		_ if keysym == XK_Tab as KeySym => Some('\t'),
+		_ if keysym == XK_BackSpace as KeySym => Some('\u{8}'),
+		_ if keysym == XK_Escape as KeySym => Some('\u{1b}'),
+		0x20..=0x7e | 0xa0..=0xff => std::char::from_u32(keysym as u32),
+		0x0100_0100..=0x0110_ffff => std::char::from_u32((keysym - 0x0100_0000) as u32),
+		_ => None,
+	}
+}
+
+/// A copy of the keyboard mapping of the X server, see [XGetKeyboardMapping].
+///
+/// [x11::xlib::XKeysymToKeycode], which [crate::Window::send_key] uses, only finds the key
+/// and ignores that the keysym may need Shift, so on non-US layouts it often types the wrong symbol.
+/// This finds the modifiers as well. When the server supports XKB, the levels of the active group
+/// are searched first, see [XkbKeymap], which also finds keysyms that need AltGr.
+///
+/// The mapping is not updated when the layout changes, load a new one in that case,
+/// or use the copy of [Display::keymap], which is loaded again after every change.
+///
+/// # Example
+/// ```ignore
+/// let keymap = Keymap::load(&session.display);
+/// let (code, modifiers) = keymap.resolve_char('@').expect("'@' is not on the keyboard.");
+/// window.send_keycode(KeyType::Press, code, modifiers);
+/// window.send_keycode(KeyType::Release, code, modifiers);
+/// ```
+pub struct Keymap {
+	min_keycode: KeyCode,
+	per_keycode: usize,
+	keysyms: Vec<KeySym>,
+	xkb: Option<XkbKeymap>,
+}
+
+impl Keymap {
+	/// Fetches the current keyboard mapping.
+	pub fn load(display: &Display) -> Self {
+		let (mut min, mut max) = (0, 0);
+		unsafe { XDisplayKeycodes(display.as_raw(), &mut min, &mut max) };
+		let count = max - min + 1;
+		let mut per_keycode = 0;
+		let syms = unsafe { XGetKeyboardMapping(display.as_raw(), min as _, count, &mut per_keycode) };
+		let keysyms = if syms.is_null() {
+			Vec::new()
+		} else {
+			let keysyms = unsafe { slice::from_raw_parts(syms, (count * per_keycode) as usize) }.to_vec();
+			unsafe { XFree(syms as _) };
+			keysyms
+		};
+		Keymap {
+			min_keycode: min as KeyCode,
+			per_keycode: per_keycode.max(1) as usize,
+			keysyms,
+			xkb: XkbKeymap::load(display),
+		}
+	}
+
+	/// The keysyms of every key code, the plain one first and the shifted one second.
+	fn keys(&self) -> impl Iterator<Item=(KeyCode, [KeySym; 2])> + '_ {
+		self.keysyms.chunks(self.per_keycode).enumerate().map(move |(i, syms)| {
+			let plain = syms[0];
+			let shifted = match syms.get(1) {
+				Some(sym) if *sym != NoSymbol as KeySym => *sym,
+				// A single keysym means both cases are on the same level, e.g. `a` and `A`.
+				_ => {
+					let (mut lower, mut upper) = (0, 0);
+					unsafe { XConvertCase(plain, &mut lower, &mut upper) };
+					upper
+				}
+			};
+			(self.min_keycode.wrapping_add(i as KeyCode), [plain, shifted])
+		})
+	}
+
+	/// Finds a key code that types the keysym and the modifiers that have to be held for it.
+	///
+	/// Keys that need fewer modifiers are preferred. Without XKB, or if the keysym is not
+	/// in the active group, only the plain and the Shift level of the core mapping are considered.
+	pub fn lookup(&self, keysym: KeySym) -> Option<(KeyCode, c_uint)> {
+		if keysym == NoSymbol as KeySym {
+			return None;
+		}
+		if let Some(key) = self.xkb.as_ref().and_then(|it| it.lookup(keysym)) {
+			return Some(key);
+		}
+		self.keys()
+			.find(|(_, syms)| syms[0] == keysym)
+			.map(|(code, _)| (code, 0))
+			.or_else(|| self.keys().find(|(_, syms)| syms[1] == keysym).map(|(code, _)| (code, ShiftMask)))
+	}
+
+	/// Finds a key code that types the character and the modifiers that have to be held for it.
+	///
+	/// See [keysym_from_char] and [Keymap::lookup].
+	pub fn resolve_char(&self, c: char) -> Option<(KeyCode, c_uint)> {
+		self.lookup(keysym_from_char(c))
+	}
+
+	/// Every keysym bound to the key code in the core mapping, on all its levels and groups.
+	pub(crate) fn keysyms_of(&self, code: KeyCode) -> &[KeySym] {
+		let start = code.wrapping_sub(self.min_keycode) as usize * self.per_keycode;
+		self.keysyms.get(start..start + self.per_keycode).unwrap_or_default()
+	}
+
+	/// Key codes that don't have any keysym bound to them.
+	pub fn spare_keycodes(&self) -> Vec<KeyCode> {
+		self.keysyms
+			.chunks(self.per_keycode)
+			.enumerate()
+			.filter(|(_, syms)| syms.iter().all(|it| *it == NoSymbol as KeySym))
+			.map(|(i, _)| self.min_keycode.wrapping_add(i as KeyCode))
+			.collect()
+	}
+}
+
+/// Selects the XKB events that change the mapping of the core keyboard or its active group.
+///
+/// Returns the event base of XKB, [None] if the server doesn't support it.
+pub(crate) fn select_mapping_events(display: &Display) -> Option<c_int> {
+	let (mut opcode, mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 1, 0);
+	let supported = unsafe { XkbQueryExtension(display.as_raw(), &mut opcode, &mut event_base, &mut error_base, &mut major, &mut minor) };
+	if supported == False {
+		return None;
+	}
+	unsafe {
+		XkbSelectEvents(display.as_raw(), XKB_USE_CORE_KBD, XkbMapNotifyMask, XkbMapNotifyMask);
+		XkbSelectEventDetails(display.as_raw(), XKB_USE_CORE_KBD, XkbStateNotify as _, XkbGroupStateMask, XkbGroupStateMask);
+	}
+	Some(event_base)
+}
+
+/// Whether the event tells that a [Keymap] loaded before it is outdated.
+///
+/// Only group changes are selected of the XKB state events, see [select_mapping_events].
+pub(crate) fn changes_keymap(event: &XEvent, xkb_event_base: Option<c_int>) -> bool {
+	match event.get_type() {
+		typ if typ == MappingNotify => unsafe { event.mapping }.request != MappingPointer,
+		typ if Some(typ) == xkb_event_base => {
+			let xkb_type = unsafe { (*(event as *const XEvent as *const XkbAnyEvent)).xkb_type };
+			xkb_type == XkbMapNotify || xkb_type == XkbStateNotify
+		}
+		_ => false,
+	}
+}
+
+/// Whether an event that [changes_keymap] is waiting in the queue, without taking any event off it.
+pub(crate) fn keymap_changed_in_queue(display: &Display, xkb_event_base: Option<c_int>) -> bool {
+	unsafe extern "C" fn check(_: *mut XDisplay, event: *mut XEvent, arg: *mut c_char) -> c_int {
+		let (found, xkb_event_base) = &mut *(arg as *mut (bool, Option<c_int>));
+		*found |= changes_keymap(&*event, *xkb_event_base);
+		False
+	}
+	let mut arg = (false, xkb_event_base);
+	let mut event = MaybeUninit::<XEvent>::uninit();
+	unsafe { XCheckIfEvent(display.as_raw(), event.as_mut_ptr(), Some(check), &mut arg as *mut _ as *mut c_char) };
+	arg.0
+}
+
+/// Keysyms that are temporarily bound to spare key codes.
+///
+/// This is how keysyms that are not on the current layout are typed, e.g. emoji or
+/// letters of other scripts. The key codes are unbound again when this is dropped,
+/// so keep it alive until the key has been released.
+///
+/// # Example
+/// ```ignore
+/// let keymap = Keymap::load(&session.display);
+/// let mut remap = Remap::new(Arc::clone(&session.display), &keymap);
+/// let (code, modifiers) = remap.resolve(&keymap, keysym_from_char('€')).expect("No spare key codes.");
+/// window.send_keycode(KeyType::Press, code, modifiers);
+/// window.send_keycode(KeyType::Release, code, modifiers);
+/// ```
+#[must_use = "the key codes are unbound as soon as this is dropped"]
+pub struct Remap {
+	display: Arc<Display>,
+	spare: Vec<KeyCode>,
+	bound: Vec<(KeyCode, KeySym)>,
+}
+
+impl Remap {
+	/// Prepares to bind keysyms to the key codes that are unused in `keymap`.
+	pub fn new(display: Arc<Display>, keymap: &Keymap) -> Self {
+		Remap {
+			display,
+			spare: keymap.spare_keycodes(),
+			bound: Vec::new(),
+		}
+	}
+
+	/// Binds the keysym to a spare key code, returns [None] if there are no spare key codes.
+	///
+	/// Once every spare key code is in use, the old bindings are removed to make space.
+	pub fn bind(&mut self, keysym: KeySym) -> Option<KeyCode> {
+		if let Some((code, _)) = self.bound.iter().find(|(_, sym)| *sym == keysym) {
+			return Some(*code);
+		}
+		if self.spare.is_empty() {
+			return None;
+		}
+		if self.bound.len() == self.spare.len() {
+			self.restore();
+		}
+		let code = self.spare[self.bound.len()];
+		let mut syms = [keysym, keysym];
+		unsafe {
+			XChangeKeyboardMapping(self.display.as_raw(), code as c_int, 2, syms.as_mut_ptr(), 1);
+			XSync(self.display.as_raw(), False);
+		}
+		self.bound.push((code, keysym));
+		Some(code)
+	}
+
+	/// Finds the keysym in `keymap` like [Keymap::lookup], and binds it if it is not there.
+	pub fn resolve(&mut self, keymap: &Keymap, keysym: KeySym) -> Option<(KeyCode, c_uint)> {
+		keymap.lookup(keysym).or_else(|| Some((self.bind(keysym)?, 0)))
+	}
+
+	fn restore(&mut self) {
+		unsafe { XSync(self.display.as_raw(), False) };
+		// Clients look up the keysym when they handle the event, so give them a moment to do that.
+		thread::sleep(Duration::from_millis(50));
+		for (code, _) in self.bound.drain(..) {
+			let mut syms = [NoSymbol as KeySym; 2];
+			unsafe { XChangeKeyboardMapping(self.display.as_raw(), code as c_int, 2, syms.as_mut_ptr(), 1) };
+		}
+		unsafe { XSync(self.display.as_raw(), False) };
+	}
+}
+
+impl Drop for Remap {
+	fn drop(&mut self) {
+		if !self.bound.is_empty() {
+			self.restore();
+		}
+	}
+}
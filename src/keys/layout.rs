@@ -0,0 +1,110 @@
+use std::{
+	ffi::CStr,
+	mem::MaybeUninit,
+	os::raw::{c_uint, c_void},
+	sync::Arc,
+	slice,
+};
+
+use x11::xlib::{True, XA_STRING, XFlush, XFree, XGetAtomName, XkbAllocKeyboard, XkbFreeKeyboard, XkbGetNames, XkbGetState, XkbLockGroup};
+
+use crate::{Atom, Display, Window, util::get_window_property};
+
+use super::xkb::XKB_USE_CORE_KBD;
+
+// Not exported by the x11 crate, the value is from XKB.h.
+const XKB_GROUP_NAMES_MASK: c_uint = 1 << 12;
+
+/// A keyboard layout the server switches between, one XKB group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyboardLayout {
+	/// The number of the group, pass it to [crate::Session::set_active_layout].
+	pub index: usize,
+	/// The short name of the layout from the XKB rules, e.g. `us` or `de`, empty if it is not known.
+	pub name: String,
+	/// The name the keymap gives the group, e.g. `English (US)`.
+	pub description: String,
+}
+
+/// Reads the layouts of the core keyboard.
+pub(crate) fn keyboard_layouts(display: &Arc<Display>) -> Vec<KeyboardLayout> {
+	let names = rules_layouts(display);
+	let descriptions = group_names(display);
+	let count = names.len().max(descriptions.len());
+	(0..count)
+		.map(|index| KeyboardLayout {
+			index,
+			name: names.get(index).cloned().unwrap_or_default(),
+			description: descriptions.get(index).cloned().unwrap_or_default(),
+		})
+		.collect()
+}
+
+/// The group that is currently active on the core keyboard.
+pub(crate) fn active_group(display: &Display) -> Option<usize> {
+	let mut state = MaybeUninit::zeroed();
+	if unsafe { XkbGetState(display.as_raw(), XKB_USE_CORE_KBD, state.as_mut_ptr()) } != 0 {
+		return None;
+	}
+	Some(unsafe { state.assume_init() }.group as usize)
+}
+
+/// Asks the server to lock the core keyboard to the group.
+pub(crate) fn lock_group(display: &Display, group: usize) -> bool {
+	let ok = unsafe { XkbLockGroup(display.as_raw(), XKB_USE_CORE_KBD, group as c_uint) } != 0;
+	unsafe { XFlush(display.as_raw()) };
+	ok
+}
+
+/// The layouts in the `_XKB_RULES_NAMES` property of the root window, which holds
+/// the rules, model, layouts, variants and options separated by NUL bytes.
+fn rules_layouts(display: &Arc<Display>) -> Vec<String> {
+	let atom = match Atom::new(display, "_XKB_RULES_NAMES") {
+		Ok(atom) if atom.0 != 0 => atom,
+		_ => return Vec::new(),
+	};
+	let root = Window::default_root_window(Arc::clone(display));
+	let response = match unsafe { get_window_property(display, root, atom, XA_STRING) } {
+		Ok(response) => response,
+		Err(_) => return Vec::new(),
+	};
+	let mut layouts = Vec::new();
+	if response.actual_format_return == 8 && !response.proper_return.is_null() {
+		let bytes = unsafe { slice::from_raw_parts(response.proper_return, response.nitems_return as usize) };
+		if let Some(field) = bytes.split(|it| *it == 0).nth(2) {
+			layouts = String::from_utf8_lossy(field).split(',').map(|it| it.trim().to_string()).collect();
+		}
+	}
+	if !response.proper_return.is_null() {
+		unsafe { XFree(response.proper_return as *mut c_void) };
+	}
+	layouts.retain(|it| !it.is_empty());
+	layouts
+}
+
+/// The names of the groups from the keymap, up to the last group that has one.
+fn group_names(display: &Display) -> Vec<String> {
+	let desc = unsafe { XkbAllocKeyboard() };
+	if desc.is_null() {
+		return Vec::new();
+	}
+	let mut names = Vec::new();
+	unsafe {
+		(*desc).device_spec = XKB_USE_CORE_KBD as _;
+		if XkbGetNames(display.as_raw(), XKB_GROUP_NAMES_MASK, desc) == 0 && !(*desc).names.is_null() {
+			for atom in (*(*desc).names).groups {
+				if atom == 0 {
+					break;
+				}
+				let name = XGetAtomName(display.as_raw(), atom);
+				if name.is_null() {
+					break;
+				}
+				names.push(CStr::from_ptr(name).to_string_lossy().into_owned());
+				XFree(name as *mut c_void);
+			}
+		}
+		XkbFreeKeyboard(desc, 0, True);
+	}
+	names
+}
@@ -0,0 +1,19 @@
+mod combo;
+mod compose;
+mod keymap;
+mod layout;
+mod names;
+mod state;
+mod xkb;
+
+pub use self::{
+	combo::*,
+	compose::*,
+	keymap::*,
+	names::{keysym_from_name, name_from_keysym},
+	state::*,
+	xkb::*,
+};
+pub use self::layout::KeyboardLayout;
+pub(crate) use self::layout::{active_group, keyboard_layouts, lock_group};
+pub(crate) use self::keymap::{changes_keymap, keymap_changed_in_queue, select_mapping_events};
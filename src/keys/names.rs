@@ -0,0 +1,93 @@
+use std::{
+	collections::HashMap,
+	ffi::{CStr, CString},
+	sync::{Mutex, OnceLock},
+};
+
+use x11::xlib::{KeySym, NoSymbol, XKeysymToString, XStringToKeysym};
+
+/// Common names of keys that are not the names of their keysyms, lowercase.
+const ALIASES: &[(&str, &str)] = &[
+	("enter", "Return"),
+	("esc", "Escape"),
+	("del", "Delete"),
+	("ins", "Insert"),
+	("pgup", "Prior"),
+	("pageup", "Prior"),
+	("pgdown", "Next"),
+	("pagedown", "Next"),
+	("backspace", "BackSpace"),
+	("capslock", "Caps_Lock"),
+];
+
+/// The keysyms and names that were looked up, Xlib searches a large table for every lookup.
+#[derive(Default)]
+struct Names {
+	keysyms: HashMap<String, Option<KeySym>>,
+	names: HashMap<KeySym, Option<String>>,
+}
+
+fn names() -> &'static Mutex<Names> {
+	static NAMES: OnceLock<Mutex<Names>> = OnceLock::new();
+	NAMES.get_or_init(Mutex::default)
+}
+
+/// Gets the keysym of a key by its name, like `Return`, `F2`, `a` or `space`.
+///
+/// Names of keysyms are looked up with [XStringToKeysym], and a few common spellings are accepted as well:
+/// the case of names longer than a character is ignored if there is no keysym with exactly that name,
+/// and aliases like `Enter`, `Esc`, `Del`, `PgUp` and `Backspace` work. Single characters keep their case,
+/// so `a` and `A` are different keysyms. Lookups are cached, so this is cheap to call for every line of a configuration.
+/// For the keysym of a character, e.g. `é`, use [super::keysym_from_char] instead, its reverse is [super::char_from_keysym].
+/// # Example
+/// ```ignore
+/// assert_eq!(keysym_from_name("enter"), Some(XK_Return as KeySym));
+/// ```
+pub fn keysym_from_name(name: &str) -> Option<KeySym> {
+	let mut names = names().lock().unwrap_or_else(|it| it.into_inner());
+	if let Some(keysym) = names.keysyms.get(name) {
+		return *keysym;
+	}
+	let keysym = lookup_spellings(name);
+	names.keysyms.insert(name.to_owned(), keysym);
+	keysym
+}
+
+/// Gets the name of a keysym that [keysym_from_name] reads back, see [XKeysymToString].
+///
+/// Returns [None] for keysyms without a name, write those in hex like `0x1000e9` instead.
+pub fn name_from_keysym(keysym: KeySym) -> Option<String> {
+	let mut names = names().lock().unwrap_or_else(|it| it.into_inner());
+	names.names.entry(keysym).or_insert_with(|| keysym_name(keysym)).clone()
+}
+
+fn lookup_spellings(name: &str) -> Option<KeySym> {
+	if let Some(keysym) = lookup(name) {
+		return Some(keysym);
+	}
+	// Single characters keep their case.
+	name.chars().nth(1)?;
+	let lowercase = name.to_ascii_lowercase();
+	let alias = ALIASES.iter().find(|(it, _)| *it == lowercase).map_or("", |(_, it)| it);
+	let mut capitalized = lowercase.clone();
+	if let Some(first) = capitalized.get_mut(..1) {
+		first.make_ascii_uppercase();
+	}
+	[alias, &lowercase, &capitalized].iter().filter(|it| !it.is_empty()).find_map(|it| lookup(it))
+}
+
+/// Looks up the keysym with exactly this name, see [XStringToKeysym].
+fn lookup(name: &str) -> Option<KeySym> {
+	let name = CString::new(name).ok()?;
+	let keysym = unsafe { XStringToKeysym(name.as_ptr()) };
+	if keysym == NoSymbol as KeySym { None } else { Some(keysym) }
+}
+
+/// The name of the keysym, see [XKeysymToString].
+fn keysym_name(keysym: KeySym) -> Option<String> {
+	let name = unsafe { XKeysymToString(keysym) };
+	if name.is_null() {
+		return None;
+	}
+	Some(unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned())
+}
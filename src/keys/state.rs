@@ -0,0 +1,139 @@
+use std::{
+	mem::MaybeUninit,
+	os::raw::{c_char, c_uint},
+	slice,
+};
+
+use x11::keysym::XK_Num_Lock;
+use x11::xlib::{KeyCode, KeySym, NoSymbol, XQueryKeymap, LockMask, Mod2Mask, XFreeModifiermap, XGetModifierMapping, XKeysymToKeycode, XkbGetState, XQueryPointer, XDefaultRootWindow};
+
+use crate::Display;
+
+use super::{Modifier, xkb::XKB_USE_CORE_KBD};
+
+/// Which modifiers and locks are active on the keyboard, see [crate::Session::modifier_state].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModifierState {
+	/// Every modifier that is in effect, whether it is held, latched or locked.
+	pub mask: c_uint,
+	/// The modifiers that are locked, like Caps Lock and Num Lock.
+	pub locked: c_uint,
+	/// Whether Caps Lock is on.
+	pub caps_lock: bool,
+	/// Whether Num Lock is on.
+	pub num_lock: bool,
+}
+
+impl ModifierState {
+	/// Whether the modifier is in effect.
+	pub fn has(&self, modifier: Modifier) -> bool {
+		self.mask & modifier.mask() != 0
+	}
+
+	/// The modifiers of [Modifier] that are in effect, in the order Shift, Ctrl, Alt, Super and AltGr.
+	///
+	/// Caps Lock and Num Lock are not modifiers of shortcuts, see [Self::caps_lock] and [Self::num_lock] for those.
+	pub fn modifiers(&self) -> Vec<Modifier> {
+		[Modifier::Shift, Modifier::Control, Modifier::Alt, Modifier::Super, Modifier::AltGr]
+			.iter()
+			.copied()
+			.filter(|it| self.has(*it))
+			.collect()
+	}
+
+	/// Queries the state of the core keyboard.
+	///
+	/// XKB is asked first, without it the state of the pointer is used,
+	/// which has the same modifiers but can't tell held and locked ones apart.
+	pub(crate) fn query(display: &Display) -> Self {
+		let num_lock = num_lock_mask(display);
+		let mut state = MaybeUninit::zeroed();
+		let (mask, locked) = if unsafe { XkbGetState(display.as_raw(), XKB_USE_CORE_KBD, state.as_mut_ptr()) } == 0 {
+			let state = unsafe { state.assume_init() };
+			(state.mods as c_uint, state.locked_mods as c_uint)
+		} else {
+			let (mut root, mut child, mut root_x, mut root_y, mut x, mut y, mut mask) = (0, 0, 0, 0, 0, 0, 0);
+			unsafe {
+				let window = XDefaultRootWindow(display.as_raw());
+				XQueryPointer(display.as_raw(), window, &mut root, &mut child, &mut root_x, &mut root_y, &mut x, &mut y, &mut mask);
+			}
+			(mask, mask & (LockMask | num_lock))
+		};
+		ModifierState {
+			mask,
+			locked,
+			caps_lock: mask & LockMask != 0,
+			num_lock: mask & num_lock != 0,
+		}
+	}
+}
+
+/// Which keys are held down on the keyboard, see [crate::Session::query_keymap].
+///
+/// This is a snapshot of the moment it was queried, with the keysyms the mapping had then.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyboardState {
+	/// A bit for every key code, from [XQueryKeymap].
+	keys: [u8; 32],
+	/// The keysyms of the held keys, on all their levels.
+	keysyms: Vec<KeySym>,
+}
+
+impl KeyboardState {
+	/// Queries the keys that are held down on the core keyboard.
+	pub(crate) fn query(display: &Display) -> Self {
+		let mut raw = [0 as c_char; 32];
+		unsafe { XQueryKeymap(display.as_raw(), raw.as_mut_ptr()) };
+		let keys = raw.map(|it| it as u8);
+		let mut state = KeyboardState { keys, keysyms: Vec::new() };
+		if state.any_pressed() {
+			let keymap = display.keymap();
+			state.keysyms = state
+				.pressed_keycodes()
+				.flat_map(|code| keymap.keysyms_of(code).to_vec())
+				.filter(|it| *it != NoSymbol as KeySym)
+				.collect();
+		}
+		state
+	}
+
+	/// Whether a key with the keysym on any of its levels is held, e.g. [x11::keysym::XK_Control_L].
+	///
+	/// Levels are not told apart, so `a` and `A` are both pressed while the A key is held.
+	pub fn is_pressed(&self, keysym: KeySym) -> bool {
+		self.keysyms.contains(&keysym)
+	}
+
+	/// Whether the key with the key code is held.
+	pub fn is_code_pressed(&self, code: KeyCode) -> bool {
+		self.keys[code as usize / 8] & (1 << (code % 8)) != 0
+	}
+
+	/// Whether any key is held.
+	pub fn any_pressed(&self) -> bool {
+		self.keys.iter().any(|it| *it != 0)
+	}
+
+	/// The key codes of the held keys, in ascending order.
+	pub fn pressed_keycodes(&self) -> impl Iterator<Item = KeyCode> + '_ {
+		(0..=KeyCode::MAX).filter(move |it| self.is_code_pressed(*it))
+	}
+}
+
+/// Finds the modifier Num Lock is mapped to, which is almost always [Mod2Mask].
+pub(crate) fn num_lock_mask(display: &Display) -> c_uint {
+	let code = unsafe { XKeysymToKeycode(display.as_raw(), XK_Num_Lock as _) };
+	let map = unsafe { XGetModifierMapping(display.as_raw()) };
+	if map.is_null() {
+		return Mod2Mask;
+	}
+	if code == 0 {
+		unsafe { XFreeModifiermap(map) };
+		return Mod2Mask;
+	}
+	let per_modifier = unsafe { (*map).max_keypermod } as usize;
+	let codes = unsafe { slice::from_raw_parts((*map).modifiermap, per_modifier * 8) };
+	let bit = (0..8).find(|bit| codes[bit * per_modifier..(bit + 1) * per_modifier].contains(&code));
+	unsafe { XFreeModifiermap(map) };
+	bit.map_or(Mod2Mask, |bit| 1 << bit)
+}
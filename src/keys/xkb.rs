@@ -0,0 +1,153 @@
+use std::{
+	mem::MaybeUninit,
+	os::raw::{c_int, c_uchar, c_uint, c_ushort},
+	slice,
+};
+
+use x11::xlib::{Atom, KeyCode, KeySym, NoSymbol, True, XkbDescPtr, XkbFreeKeyboard, XkbGetMap, XkbGetState, XkbModsRec};
+
+use crate::Display;
+
+// Not exported by the x11 crate, the values are from XKB.h.
+pub(super) const XKB_USE_CORE_KBD: c_uint = 0x0100;
+const XKB_KEY_TYPES_MASK: c_uint = 1 << 0;
+const XKB_KEY_SYMS_MASK: c_uint = 1 << 1;
+const XKB_NUM_KBD_GROUPS: usize = 4;
+
+/// The keysym of every level of a key and the modifiers that select it, if any do.
+type Levels = Vec<(KeySym, Option<c_uint>)>;
+
+// The x11 crate leaves the client map opaque, these mirror XKBstr.h.
+#[repr(C)]
+#[allow(dead_code)]
+struct KTMapEntry {
+	active: c_int,
+	level: c_uchar,
+	mods: XkbModsRec,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct KeyType {
+	mods: XkbModsRec,
+	num_levels: c_uchar,
+	map_count: c_uchar,
+	map: *mut KTMapEntry,
+	preserve: *mut XkbModsRec,
+	name: Atom,
+	level_names: *mut Atom,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct SymMap {
+	kt_index: [c_uchar; XKB_NUM_KBD_GROUPS],
+	group_info: c_uchar,
+	width: c_uchar,
+	offset: c_ushort,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct ClientMap {
+	size_types: c_uchar,
+	num_types: c_uchar,
+	types: *mut KeyType,
+	size_syms: c_ushort,
+	num_syms: c_ushort,
+	syms: *mut KeySym,
+	key_sym_map: *mut SymMap,
+	modmap: *mut c_uchar,
+}
+
+/// The keyboard mapping of the active XKB group, with the modifiers of every shift level.
+///
+/// Unlike the core mapping used by [super::Keymap] on its own, this knows about levels
+/// above Shift, like AltGr on European layouts, and about layouts other than the first one.
+/// The mapping is not updated when the layout or group changes, load a new one in that case.
+pub struct XkbKeymap {
+	/// The keysym and the modifiers of every level, for every key code that has any.
+	levels: Vec<(KeyCode, Levels)>,
+}
+
+impl XkbKeymap {
+	/// Fetches the mapping of the group that is currently active on the core keyboard.
+	///
+	/// Returns `None` if the server does not support XKB.
+	pub fn load(display: &Display) -> Option<Self> {
+		let mut state = MaybeUninit::zeroed();
+		if unsafe { XkbGetState(display.as_raw(), XKB_USE_CORE_KBD, state.as_mut_ptr()) } != 0 {
+			return None;
+		}
+		let group = unsafe { state.assume_init() }.group as usize;
+		let desc = unsafe { XkbGetMap(display.as_raw(), XKB_KEY_TYPES_MASK | XKB_KEY_SYMS_MASK, XKB_USE_CORE_KBD) };
+		if desc.is_null() {
+			return None;
+		}
+		let levels = unsafe { read_levels(desc, group) };
+		unsafe { XkbFreeKeyboard(desc, 0, True) };
+		Some(XkbKeymap { levels })
+	}
+
+	/// Finds a key code that types the keysym in the active group and the modifiers that have to be held for it.
+	///
+	/// Keys that need fewer modifiers are preferred, so a keysym on both a plain and an AltGr level
+	/// is typed without AltGr.
+	pub fn lookup(&self, keysym: KeySym) -> Option<(KeyCode, c_uint)> {
+		if keysym == NoSymbol as KeySym {
+			return None;
+		}
+		self.levels
+			.iter()
+			.flat_map(|(code, levels)| levels.iter().filter(|(sym, _)| *sym == keysym).filter_map(move |(_, mods)| Some((*code, (*mods)?))))
+			.min_by_key(|(_, mods)| mods.count_ones())
+	}
+}
+
+/// Copies the levels of every key in `group` out of the XKB description.
+///
+/// # Safety
+/// `desc` has to be returned by [XkbGetMap] with the key types and key syms.
+unsafe fn read_levels(desc: XkbDescPtr, group: usize) -> Vec<(KeyCode, Levels)> {
+	let desc = &*desc;
+	let map = desc.map as *const ClientMap;
+	if map.is_null() {
+		return Vec::new();
+	}
+	let map = &*map;
+	if map.types.is_null() || map.syms.is_null() || map.key_sym_map.is_null() {
+		return Vec::new();
+	}
+	let types = slice::from_raw_parts(map.types, map.num_types as usize);
+	(desc.min_key_code..=desc.max_key_code)
+		.filter_map(|code| {
+			let sym_map = &*map.key_sym_map.add(code as usize);
+			let groups = (sym_map.group_info & 0x0f) as usize;
+			if groups == 0 {
+				return None;
+			}
+			// Groups past the ones the key has wrap around, which is what most keys ask for.
+			let group = group % groups;
+			let key_type = types.get(sym_map.kt_index[group] as usize)?;
+			let width = sym_map.width as usize;
+			let syms = map.syms.add(sym_map.offset as usize + group * width);
+			let entries = if key_type.map.is_null() { &[][..] } else { slice::from_raw_parts(key_type.map, key_type.map_count as usize) };
+			let levels = (0..(key_type.num_levels as usize).min(width))
+				.map(|level| {
+					let mods = if level == 0 {
+						Some(0)
+					} else {
+						entries
+							.iter()
+							.filter(|it| it.active != 0 && it.level as usize == level)
+							.map(|it| it.mods.mask as c_uint)
+							.min_by_key(|it| it.count_ones())
+					};
+					(*syms.add(level), mods)
+				})
+				.filter(|(sym, _)| *sym != NoSymbol as KeySym)
+				.collect::<Vec<_>>();
+			Some((code, levels))
+		})
+		.collect()
+}
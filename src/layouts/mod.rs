@@ -0,0 +1,8 @@
+mod saved;
+mod tiling;
+
+pub use self::{
+	saved::WindowLayout,
+	tiling::{apply, Layout},
+};
+pub(crate) use self::saved::{restore, save};
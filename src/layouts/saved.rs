@@ -0,0 +1,153 @@
+use std::{
+	fmt,
+	fs,
+	io,
+	path::Path,
+	str::FromStr,
+};
+
+use crate::{Rect, Session, Window};
+use crate::util::unescape;
+
+/// Where a window was and how it looked, one line of a file written by [Session::save_layout].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowLayout {
+	/// The instance name from `WM_CLASS`.
+	pub instance: String,
+	/// The class name from `WM_CLASS`.
+	pub class: String,
+	/// The title of the window.
+	pub title: String,
+	/// The geometry of the window, see [Window::geometry].
+	pub rect: Rect,
+	/// The virtual desktop, see [Window::desktop].
+	pub desktop: Option<u32>,
+	/// The names of the states, see [Window::states].
+	pub states: Vec<String>,
+}
+
+impl WindowLayout {
+	/// Reads the layout of a window.
+	pub fn of(window: &Window) -> Self {
+		let (instance, class) = window.class().ok().flatten().unwrap_or_default();
+		WindowLayout {
+			instance,
+			class,
+			title: window.title().unwrap_or_default(),
+			rect: window.geometry(),
+			desktop: window.desktop(),
+			states: window.states().unwrap_or_default(),
+		}
+	}
+
+	/// Moves the window back to where this layout was saved.
+	pub fn apply(&self, window: &Window) {
+		if let Some(desktop) = self.desktop {
+			if window.desktop() != Some(desktop) {
+				window.set_desktop(desktop);
+			}
+		}
+		window.move_resize(self.rect);
+	}
+
+	fn same_class(&self, other: &WindowLayout) -> bool {
+		self.class == other.class && self.instance == other.instance
+	}
+}
+
+/// Writes the layout as a line of tab separated fields, the title comes last.
+impl fmt::Display for WindowLayout {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let Rect { x, y, width, height } = self.rect;
+		match self.desktop {
+			Some(desktop) => write!(f, "{}", desktop)?,
+			None => write!(f, "-")?,
+		}
+		write!(
+			f,
+			"\t{} {} {} {}\t{}\t{}\t{}\t{}",
+			x, y, width, height,
+			self.states.join(","),
+			self.instance.escape_default(),
+			self.class.escape_default(),
+			self.title.escape_default(),
+		)
+	}
+}
+
+/// Reads a line written by the [fmt::Display] of [WindowLayout].
+impl FromStr for WindowLayout {
+	type Err = ();
+
+	fn from_str(line: &str) -> Result<Self, Self::Err> {
+		let fields = line.split('\t').collect::<Vec<_>>();
+		if fields.len() != 6 {
+			return Err(());
+		}
+		let desktop = match fields[0] {
+			"-" => None,
+			desktop => Some(desktop.parse().map_err(|_| ())?),
+		};
+		let rect = fields[1].split(' ').collect::<Vec<_>>();
+		if rect.len() != 4 {
+			return Err(());
+		}
+		let rect = Rect::new(
+			rect[0].parse().map_err(|_| ())?,
+			rect[1].parse().map_err(|_| ())?,
+			rect[2].parse().map_err(|_| ())?,
+			rect[3].parse().map_err(|_| ())?,
+		);
+		let states = fields[2].split(',').filter(|it| !it.is_empty()).map(String::from).collect();
+		Ok(WindowLayout {
+			instance: unescape(fields[3])?,
+			class: unescape(fields[4])?,
+			title: unescape(fields[5])?,
+			rect,
+			desktop,
+			states,
+		})
+	}
+}
+
+pub(crate) fn save(session: &Session, path: &Path) -> io::Result<usize> {
+	let windows = session.get_windows().map_err(|err| io::Error::new(io::ErrorKind::Unsupported, err))?;
+	let text = windows.iter().map(|it| format!("{}\n", WindowLayout::of(it))).collect::<String>();
+	fs::write(path, text)?;
+	Ok(windows.as_vec().len())
+}
+
+pub(crate) fn restore(session: &Session, path: &Path) -> io::Result<usize> {
+	let saved = fs::read_to_string(path)?
+		.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.is_empty())
+		.map(|(i, line)| {
+			line.parse::<WindowLayout>()
+				.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid layout on line {}", i + 1)))
+		})
+		.collect::<io::Result<Vec<_>>>()?;
+	let windows = session.get_windows().map_err(|err| io::Error::new(io::ErrorKind::Unsupported, err))?;
+	let mut open = windows.inner().into_iter().map(|it| (WindowLayout::of(&it), it)).collect::<Vec<_>>();
+
+	// Windows with the same class and title are matched first, so a window that only shares
+	// its class doesn't take the place of the one that was saved.
+	let mut pending = Vec::new();
+	let mut restored = 0;
+	for layout in &saved {
+		match open.iter().position(|(it, _)| it.same_class(layout) && it.title == layout.title) {
+			Some(i) => {
+				layout.apply(&open.remove(i).1);
+				restored += 1;
+			}
+			None => pending.push(layout),
+		}
+	}
+	for layout in pending {
+		if let Some(i) = open.iter().position(|(it, _)| it.same_class(layout)) {
+			layout.apply(&open.remove(i).1);
+			restored += 1;
+		}
+	}
+	Ok(restored)
+}
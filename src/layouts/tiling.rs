@@ -0,0 +1,161 @@
+use std::{
+	os::raw::{c_long, c_void},
+	sync::Arc,
+	slice,
+};
+
+use x11::xlib::{XA_CARDINAL, XFlush, XFree, XMoveResizeWindow};
+
+use crate::{Atom, Rect, Window, util::get_window_property};
+
+/// How [apply] arranges the windows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layout {
+	/// Rows of equal cells, the windows of the last row share its whole width.
+	Grid,
+	/// Side by side columns of equal width.
+	Columns,
+	/// The first window takes the left half, the others are stacked on top of each other in the right half.
+	MainAndStack,
+}
+
+impl Layout {
+	/// Splits `area` into one rect for each of `count` windows, the rects don't overlap.
+	pub fn tiles(&self, area: Rect, count: usize) -> Vec<Rect> {
+		if count == 0 {
+			return Vec::new();
+		}
+		match self {
+			Layout::Columns => columns(area, count),
+			Layout::Grid => {
+				let cols = (1..=count).find(|it| it * it >= count).unwrap_or(count);
+				let rows = count.div_ceil(cols);
+				rows_of(area, rows)
+					.into_iter()
+					.enumerate()
+					.flat_map(|(i, row)| columns(row, cols.min(count - i * cols)))
+					.collect()
+			}
+			Layout::MainAndStack if count == 1 => vec![area],
+			Layout::MainAndStack => {
+				let halves = columns(area, 2);
+				let mut tiles = vec![halves[0]];
+				tiles.extend(rows_of(halves[1], count - 1));
+				tiles
+			}
+		}
+	}
+}
+
+/// Arranges the windows inside the work area of the monitor, in the order they are given.
+///
+/// The work area is the part of `monitor` that is not covered by panels, from `_NET_WORKAREA`.
+/// Every window gets a tile that fits it including its decorations, then all of them are moved at once.
+///
+/// # Example
+/// ```ignore
+/// let windows = session.get_windows().unwrap().inner();
+/// layouts::apply(&windows, Layout::MainAndStack, session.monitors()[0]);
+/// ```
+pub fn apply(windows: &[Window], layout: Layout, monitor: Rect) {
+	let display = match windows.first() {
+		Some(window) => Arc::clone(&window.display),
+		None => return,
+	};
+	let root = Window::default_root_window(Arc::clone(&display));
+	let area = work_area(&root).and_then(|it| it.intersect(&monitor)).unwrap_or(monitor);
+	for (window, tile) in windows.iter().zip(layout.tiles(area, windows.len())) {
+		let extents = window.frame_extents().unwrap_or_default();
+		let width = tile.width.saturating_sub(extents.left + extents.right).max(1);
+		let height = tile.height.saturating_sub(extents.top + extents.bottom).max(1);
+		unsafe { XMoveResizeWindow(display.as_raw(), window.window, tile.x, tile.y, width, height) };
+	}
+	unsafe { XFlush(display.as_raw()) };
+}
+
+/// Reads the work area of the current desktop from `_NET_WORKAREA`.
+fn work_area(root: &Window) -> Option<Rect> {
+	let current = cardinals(root, "_NET_CURRENT_DESKTOP")
+		.and_then(|it| it.first().copied())
+		.unwrap_or(0) as usize;
+	let areas = cardinals(root, "_NET_WORKAREA")?;
+	let area = areas.get(current * 4..current * 4 + 4).or_else(|| areas.get(..4))?;
+	Some(Rect::new(area[0] as i32, area[1] as i32, area[2] as u32, area[3] as u32))
+}
+
+fn cardinals(window: &Window, name: &str) -> Option<Vec<c_long>> {
+	let atom = Atom::new(&window.display, name).ok()?;
+	if atom.0 == 0 {
+		return None;
+	}
+	let response = unsafe { get_window_property(&window.display, window.clone(), atom, XA_CARDINAL).ok()? };
+	let values = if response.actual_format_return == 32 && !response.proper_return.is_null() {
+		Some(unsafe { slice::from_raw_parts(response.proper_return as *const c_long, response.nitems_return as usize) }.to_vec())
+	} else { None };
+	if !response.proper_return.is_null() {
+		unsafe { XFree(response.proper_return as *mut c_void) };
+	}
+	values
+}
+
+/// Splits `length` into `count` parts, the first parts get the pixels left over.
+fn split(start: i32, length: u32, count: usize) -> impl Iterator<Item = (i32, u32)> {
+	let (part, rest) = (length / count as u32, length % count as u32);
+	(0..count as u32).map(move |i| {
+		let offset = i * part + i.min(rest);
+		(start + offset as i32, part + u32::from(i < rest))
+	})
+}
+
+fn columns(area: Rect, count: usize) -> Vec<Rect> {
+	split(area.x, area.width, count).map(|(x, width)| Rect::new(x, area.y, width, area.height)).collect()
+}
+
+fn rows_of(area: Rect, count: usize) -> Vec<Rect> {
+	split(area.y, area.height, count).map(|(y, height)| Rect::new(area.x, y, area.width, height)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn columns_give_the_leftover_pixels_to_the_first_tiles() {
+		assert_eq!(Layout::Columns.tiles(Rect::new(10, 20, 101, 50), 3), vec![
+			Rect::new(10, 20, 34, 50),
+			Rect::new(44, 20, 34, 50),
+			Rect::new(78, 20, 33, 50),
+		]);
+	}
+
+	#[test]
+	fn grid_shares_the_last_row() {
+		assert_eq!(Layout::Grid.tiles(Rect::new(0, 0, 300, 200), 5), vec![
+			Rect::new(0, 0, 100, 100),
+			Rect::new(100, 0, 100, 100),
+			Rect::new(200, 0, 100, 100),
+			Rect::new(0, 100, 150, 100),
+			Rect::new(150, 100, 150, 100),
+		]);
+		assert_eq!(Layout::Grid.tiles(Rect::new(0, 0, 300, 200), 4).len(), 4);
+	}
+
+	#[test]
+	fn main_and_stack_stacks_the_others_on_the_right() {
+		let area = Rect::new(0, 0, 200, 90);
+		assert_eq!(Layout::MainAndStack.tiles(area, 1), vec![area]);
+		assert_eq!(Layout::MainAndStack.tiles(area, 4), vec![
+			Rect::new(0, 0, 100, 90),
+			Rect::new(100, 0, 100, 30),
+			Rect::new(100, 30, 100, 30),
+			Rect::new(100, 60, 100, 30),
+		]);
+	}
+
+	#[test]
+	fn no_windows_get_no_tiles() {
+		for layout in [Layout::Grid, Layout::Columns, Layout::MainAndStack] {
+			assert!(layout.tiles(Rect::new(0, 0, 100, 100), 0).is_empty());
+		}
+	}
+}
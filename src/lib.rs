@@ -61,49 +61,80 @@
 //! * [RustDocs: x11::xlib](https://docs.rs/x11/2.18.1/x11/xlib/index.html)
 //! 
 
-#![feature(dbg_macro)]
 #![warn(clippy::all)]
 #![warn(missing_docs)]
 #![allow(clippy::cast_lossless, clippy::cast_ptr_alignment)]
 
 mod atom;
+mod automation;
+mod cancel;
+mod clipboard;
 mod display;
+mod error;
+mod picker;
+mod rect;
 mod session;
+mod spawn;
+mod trap;
+mod tree;
 mod window;
 mod windows;
 /// Just some helpful functions if you require more functionality than this wrapper.
 pub mod util;
+/// Types used when sending events to windows and receiving them.
 pub mod event;
+/// Translating characters and keysyms into key codes.
+pub mod keys;
+/// Capturing the contents of windows into buffers.
+pub mod capture;
+/// Recording windows on a worker thread.
+pub mod recorder;
+/// Sequences of input actions that run one after the other.
+pub mod actions;
+/// Grabs and other changes to the server that are undone when their guard is dropped, also when unwinding from a panic.
+pub mod grab;
+/// Global hotkeys that run callbacks.
+pub mod hotkeys;
+/// Saving, restoring and tiling where windows are on the screen.
+pub mod layouts;
+/// The requests the crate makes to the X server, through Xlib or XCB.
+pub mod backend;
+/// Windows above all others that the crate draws selection rectangles and annotations on.
+pub mod overlay;
+/// Running tasks only while the user is away from the keyboard and mouse.
+#[cfg(feature = "idle")]
+pub mod scheduler;
 
 pub use self::{
     atom::Atom,
+    automation::{Automation, AutomationError},
+    cancel::CancelToken,
+    clipboard::PasteError,
     display::Display,
+    error::Error,
+    rect::Rect,
     session::Session,
-    window::{Window, XImg, XColor},
-    windows::Windows,
+    spawn::SpawnError,
+    trap::XError,
+    tree::{MapState, WindowTree},
+    window::{FrameExtents, Window, XImg, XColor},
+    windows::{BroadcastOptions, Windows},
 };
 
-/// A struct which is used to represent that an error occured due to a Null pointer.
-#[derive(Copy, Clone, Debug)]
-pub struct Null;
-
-/// A struct that represents an error where the ``_NET_ClIENT_LIST`` property
-/// was not found in the root window.
-/// 
-/// This error can be caused by using Desktop Environments that does not support
-/// the above convention.
-/// The WMCTRL tool's source code that I used as a reference to make this crate
-/// checked for another property, if the first one didn't work,
-/// but as I had no need for it I didn't implement it.
-/// But if there is a need for it I should have no problem implementing that as well.
-/// 
-/// Another possible source of this error was that the size of the item was not expected.
-/// 
-/// If this error happens please make an issue on the GitHub repo,
-/// giving the OS; architecture; and/or desktop environment; of your computer.
-#[derive(Copy, Clone, Debug)]
-pub struct NotSupported;
-
 const NET_CLIENT_LIST: &str = "_NET_CLIENT_LIST";
 
+const NET_CLIENT_LIST_STACKING: &str = "_NET_CLIENT_LIST_STACKING";
+
 const NET_ACTIVE_WINDOW: &str = "_NET_ACTIVE_WINDOW";
+
+const NET_FRAME_EXTENTS: &str = "_NET_FRAME_EXTENTS";
+
+const NET_WM_NAME: &str = "_NET_WM_NAME";
+
+const NET_WM_DESKTOP: &str = "_NET_WM_DESKTOP";
+
+const NET_WM_STATE: &str = "_NET_WM_STATE";
+
+const NET_WM_PID: &str = "_NET_WM_PID";
+
+const NET_STARTUP_ID: &str = "_NET_STARTUP_ID";
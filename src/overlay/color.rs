@@ -0,0 +1,76 @@
+use std::os::raw::{c_int, c_ulong};
+
+use x11::xlib::Visual;
+
+/// A color with an alpha channel, used when drawing on an [super::Overlay].
+///
+/// The alpha channel only has an effect when the overlay got a 32 bit visual
+/// and a compositor is running, otherwise colors are drawn opaque.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+	/// Red channel.
+	pub red: u8,
+	/// Green channel.
+	pub green: u8,
+	/// Blue channel.
+	pub blue: u8,
+	/// Opacity, 0 is fully transparent.
+	pub alpha: u8,
+}
+
+impl Color {
+	/// Fully transparent, the background overlays start out with.
+	pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+	/// Opaque black.
+	pub const BLACK: Color = Color::rgb(0, 0, 0);
+	/// Opaque white.
+	pub const WHITE: Color = Color::rgb(255, 255, 255);
+	/// Opaque red.
+	pub const RED: Color = Color::rgb(255, 0, 0);
+
+	/// An opaque color.
+	pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+		Color { red, green, blue, alpha: 255 }
+	}
+
+	/// A color that may be transparent.
+	pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+		Color { red, green, blue, alpha }
+	}
+
+	/// The channels multiplied by the alpha channel, the way compositors expect ARGB pixels.
+	pub(crate) fn premultiplied(&self) -> (u8, u8, u8) {
+		let scale = |channel: u8| ((channel as u16 * self.alpha as u16 + 127) / 255) as u8;
+		(scale(self.red), scale(self.green), scale(self.blue))
+	}
+
+	/// The pixel value of this color in a TrueColor `visual` of `depth` bits.
+	///
+	/// The bits of the depth that are in none of the color masks hold the alpha channel.
+	pub(crate) fn pixel(&self, visual: &Visual, depth: c_int) -> c_ulong {
+		let depth_mask = if depth >= 32 { u32::MAX as c_ulong } else { (1 << depth) - 1 };
+		let alpha_mask = depth_mask & !(visual.red_mask | visual.green_mask | visual.blue_mask);
+		let (red, green, blue) = if alpha_mask != 0 {
+			self.premultiplied()
+		} else {
+			(self.red, self.green, self.blue)
+		};
+		let mut pixel = scale_into(red, visual.red_mask)
+			| scale_into(green, visual.green_mask)
+			| scale_into(blue, visual.blue_mask);
+		if alpha_mask != 0 {
+			pixel |= scale_into(self.alpha, alpha_mask);
+		}
+		pixel
+	}
+}
+
+/// Places an 8 bit channel in the bits of `mask`, scaling it to the width of the mask.
+fn scale_into(value: u8, mask: c_ulong) -> c_ulong {
+	if mask == 0 {
+		return 0;
+	}
+	let shift = mask.trailing_zeros();
+	let max = mask >> shift;
+	((value as c_ulong * max + 127) / 255) << shift
+}
@@ -0,0 +1,182 @@
+use std::{ffi::CString, os::raw::c_int};
+
+#[cfg(not(feature = "xft"))]
+use x11::xlib::{XDrawString, XFontStruct, XFreeFont, XLoadQueryFont, XSetFont, XTextWidth};
+#[cfg(feature = "xft")]
+use x11::{
+	xft::{
+		XftColor, XftColorAllocValue, XftColorFree, XftDraw, XftDrawCreate, XftDrawDestroy,
+		XftDrawStringUtf8, XftFont, XftFontClose, XftFontOpenName, XftTextExtentsUtf8,
+	},
+	xlib::XDefaultScreen,
+	xrender::{XGlyphInfo, XRenderColor},
+};
+use x11::xlib::{Colormap, Visual, Window as XWindow, GC};
+
+use crate::Display;
+
+use super::Color;
+
+/// The font used when none was set, a core font name or, with `xft`, a fontconfig pattern.
+#[cfg(not(feature = "xft"))]
+pub(crate) const DEFAULT_FONT: &str = "fixed";
+#[cfg(feature = "xft")]
+pub(crate) const DEFAULT_FONT: &str = "monospace-10";
+
+/// Draws text on an overlay with a core font, or with Xft when the `xft` feature is on.
+pub(crate) struct Text {
+	#[cfg(not(feature = "xft"))]
+	font: *mut XFontStruct,
+	#[cfg(feature = "xft")]
+	font: *mut XftFont,
+	#[cfg(feature = "xft")]
+	draw: *mut XftDraw,
+	#[cfg(feature = "xft")]
+	visual: *mut Visual,
+	#[cfg(feature = "xft")]
+	colormap: Colormap,
+}
+
+#[cfg(not(feature = "xft"))]
+impl Text {
+	pub(crate) fn new(_display: &Display, _window: XWindow, _visual: *mut Visual, _colormap: Colormap) -> Self {
+		Text { font: std::ptr::null_mut() }
+	}
+
+	/// Loads the font called `name`, keeping the current one if it doesn't exist.
+	pub(crate) fn set_font(&mut self, display: &Display, name: &str) -> bool {
+		let name = match CString::new(name) {
+			Ok(name) => name,
+			Err(_) => return false,
+		};
+		let font = unsafe { XLoadQueryFont(display.as_raw(), name.as_ptr()) };
+		if font.is_null() {
+			return false;
+		}
+		self.close(display);
+		self.font = font;
+		true
+	}
+
+	/// Draws the Latin-1 text with its baseline at `y`, characters outside of it are skipped.
+	pub(crate) fn draw(&self, display: &Display, window: XWindow, gc: GC, (x, y): (c_int, c_int), text: &str, _color: Color) {
+		if self.font.is_null() {
+			return;
+		}
+		let bytes = latin1(text);
+		unsafe {
+			XSetFont(display.as_raw(), gc, (*self.font).fid);
+			XDrawString(display.as_raw(), window, gc, x, y, bytes.as_ptr() as _, bytes.len() as _);
+		}
+	}
+
+	pub(crate) fn width(&self, _display: &Display, text: &str) -> u32 {
+		if self.font.is_null() {
+			return 0;
+		}
+		let bytes = latin1(text);
+		unsafe { XTextWidth(self.font, bytes.as_ptr() as _, bytes.len() as _).max(0) as u32 }
+	}
+
+	/// Height of the font above and below the baseline.
+	pub(crate) fn extents(&self) -> (c_int, c_int) {
+		if self.font.is_null() {
+			return (0, 0);
+		}
+		unsafe { ((*self.font).ascent, (*self.font).descent) }
+	}
+
+	pub(crate) fn close(&mut self, display: &Display) {
+		if !self.font.is_null() {
+			unsafe { XFreeFont(display.as_raw(), self.font) };
+			self.font = std::ptr::null_mut();
+		}
+	}
+}
+
+#[cfg(feature = "xft")]
+impl Text {
+	pub(crate) fn new(display: &Display, window: XWindow, visual: *mut Visual, colormap: Colormap) -> Self {
+		let draw = unsafe { XftDrawCreate(display.as_raw(), window, visual, colormap) };
+		Text { font: std::ptr::null_mut(), draw, visual, colormap }
+	}
+
+	/// Opens the font matching the fontconfig pattern `name`, keeping the current one if it can't.
+	pub(crate) fn set_font(&mut self, display: &Display, name: &str) -> bool {
+		let name = match CString::new(name) {
+			Ok(name) => name,
+			Err(_) => return false,
+		};
+		let font = unsafe { XftFontOpenName(display.as_raw(), XDefaultScreen(display.as_raw()), name.as_ptr()) };
+		if font.is_null() {
+			return false;
+		}
+		if !self.font.is_null() {
+			unsafe { XftFontClose(display.as_raw(), self.font) };
+		}
+		self.font = font;
+		true
+	}
+
+	/// Draws the UTF-8 text with its baseline at `y`.
+	pub(crate) fn draw(&self, display: &Display, _window: XWindow, _gc: GC, (x, y): (c_int, c_int), text: &str, color: Color) {
+		if self.font.is_null() || self.draw.is_null() {
+			return;
+		}
+		let (red, green, blue) = color.premultiplied();
+		let render = XRenderColor {
+			red: red as u16 * 257,
+			green: green as u16 * 257,
+			blue: blue as u16 * 257,
+			alpha: color.alpha as u16 * 257,
+		};
+		let mut xft_color = std::mem::MaybeUninit::<XftColor>::uninit();
+		unsafe {
+			if XftColorAllocValue(display.as_raw(), self.visual, self.colormap, &render, xft_color.as_mut_ptr()) == 0 {
+				return;
+			}
+			let mut xft_color = xft_color.assume_init();
+			XftDrawStringUtf8(self.draw, &xft_color, self.font, x, y, text.as_ptr(), text.len() as _);
+			XftColorFree(display.as_raw(), self.visual, self.colormap, &mut xft_color);
+		}
+	}
+
+	pub(crate) fn width(&self, display: &Display, text: &str) -> u32 {
+		if self.font.is_null() {
+			return 0;
+		}
+		let mut info = std::mem::MaybeUninit::<XGlyphInfo>::zeroed();
+		unsafe {
+			XftTextExtentsUtf8(display.as_raw(), self.font, text.as_ptr(), text.len() as _, info.as_mut_ptr());
+			info.assume_init().xOff.max(0) as u32
+		}
+	}
+
+	/// Height of the font above and below the baseline.
+	pub(crate) fn extents(&self) -> (c_int, c_int) {
+		if self.font.is_null() {
+			return (0, 0);
+		}
+		unsafe { ((*self.font).ascent, (*self.font).descent) }
+	}
+
+	pub(crate) fn close(&mut self, display: &Display) {
+		unsafe {
+			if !self.font.is_null() {
+				XftFontClose(display.as_raw(), self.font);
+				self.font = std::ptr::null_mut();
+			}
+			if !self.draw.is_null() {
+				XftDrawDestroy(self.draw);
+				self.draw = std::ptr::null_mut();
+			}
+		}
+	}
+}
+
+/// Core fonts take Latin-1, which are the first 256 code points of Unicode.
+#[cfg(not(feature = "xft"))]
+fn latin1(text: &str) -> Vec<u8> {
+	use std::convert::TryFrom;
+	text.chars().filter_map(|c| u8::try_from(c as u32).ok()).collect()
+}
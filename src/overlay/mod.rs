@@ -0,0 +1,5 @@
+mod color;
+mod font;
+mod window;
+
+pub use self::{color::Color, window::Overlay};
@@ -0,0 +1,238 @@
+use std::{mem::MaybeUninit, os::raw::c_int, sync::Arc};
+
+use x11::xlib::{
+	AllocNone, CWBackPixel, CWBorderPixel, CWColormap, CWOverrideRedirect, CapButt, Colormap, InputOutput,
+	JoinMiter, LineSolid, TrueColor, Visual, XClearWindow, XCreateColormap, XCreateGC, XCreateWindow,
+	XDefaultDepth, XDefaultRootWindow, XDefaultScreen, XDefaultVisual, XDestroyWindow, XDrawLine,
+	XDrawRectangle, XFillRectangle, XFlush, XFreeColormap, XFreeGC, XMapRaised, XMatchVisualInfo,
+	XMoveResizeWindow, XSetForeground, XSetLineAttributes, XSetWindowAttributes, XSetWindowBackground, XUnmapWindow, XVisualInfo,
+	Window as XWindow, GC,
+};
+
+use crate::{Display, Rect, Session, Window};
+
+use super::{
+	font::{Text, DEFAULT_FONT},
+	Color,
+};
+
+/// A borderless window above all others that the crate draws on,
+/// e.g. to show a selection rectangle or annotate parts of the screen.
+///
+/// The window is override-redirect, so window managers don't decorate, move or focus it.
+/// When a 32 bit visual is available the overlay starts out transparent and [Color::alpha] is respected,
+/// which needs a running compositor to be visible; otherwise it starts out black.
+/// With the `xfixes` feature clicks go through the overlay to the windows below it.
+///
+/// Drawing is done with a GC and core fonts, or with Xft for text when the `xft` feature is on.
+/// Nothing is shown until [Self::flush] is called.
+/// The window is destroyed when the overlay is dropped.
+///
+/// # Example
+/// ```ignore
+/// let overlay = Overlay::new(&session, Rect::new(100, 100, 400, 300));
+/// overlay.stroke_rect(Rect::new(0, 0, 400, 300), Color::RED, 2);
+/// overlay.text(8, 20, "Selected", Color::WHITE);
+/// overlay.flush();
+/// ```
+pub struct Overlay {
+	display: Arc<Display>,
+	window: XWindow,
+	gc: GC,
+	visual: *mut Visual,
+	depth: c_int,
+	colormap: Colormap,
+	text: Text,
+}
+
+impl Overlay {
+	/// Creates and shows an overlay covering `rect` of the screen.
+	pub fn new(session: &Session, rect: Rect) -> Self {
+		Self::with_display(session.display.clone(), rect)
+	}
+
+	/// Like [Self::new], on any opened display.
+	pub fn with_display(display: Arc<Display>, rect: Rect) -> Self {
+		unsafe {
+			let screen = XDefaultScreen(display.as_raw());
+			let root = XDefaultRootWindow(display.as_raw());
+			let mut info = MaybeUninit::<XVisualInfo>::zeroed();
+			let (visual, depth) = if XMatchVisualInfo(display.as_raw(), screen, 32, TrueColor, info.as_mut_ptr()) != 0 {
+				let info = info.assume_init();
+				(info.visual, info.depth)
+			} else {
+				(XDefaultVisual(display.as_raw(), screen), XDefaultDepth(display.as_raw(), screen))
+			};
+			let colormap = XCreateColormap(display.as_raw(), root, visual, AllocNone);
+			let mut attributes = MaybeUninit::<XSetWindowAttributes>::zeroed().assume_init();
+			attributes.override_redirect = 1;
+			attributes.background_pixel = Color::TRANSPARENT.pixel(&*visual, depth);
+			attributes.border_pixel = 0;
+			attributes.colormap = colormap;
+			let window = XCreateWindow(
+				display.as_raw(), root, rect.x, rect.y, rect.width.max(1), rect.height.max(1), 0,
+				depth, InputOutput as _, visual,
+				CWOverrideRedirect | CWBackPixel | CWBorderPixel | CWColormap, &mut attributes,
+			);
+			#[cfg(feature = "xfixes")]
+			pass_input_through(&display, window);
+			let gc = XCreateGC(display.as_raw(), window, 0, std::ptr::null_mut());
+			let mut text = Text::new(&display, window, visual, colormap);
+			text.set_font(&display, DEFAULT_FONT);
+			XMapRaised(display.as_raw(), window);
+			XFlush(display.as_raw());
+			Overlay { display, window, gc, visual, depth, colormap, text }
+		}
+	}
+
+	/// The window of the overlay, e.g. to raise it again or to capture it.
+	pub fn window(&self) -> Window {
+		Window { window: self.window, display: self.display.clone() }
+	}
+
+	/// Moves the overlay and changes its size, which clears what was drawn.
+	pub fn move_resize(&self, rect: Rect) {
+		unsafe {
+			XMoveResizeWindow(self.display.as_raw(), self.window, rect.x, rect.y, rect.width.max(1), rect.height.max(1));
+			XClearWindow(self.display.as_raw(), self.window);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Shows the overlay again after [Self::hide].
+	///
+	/// What was drawn before isn't kept while hidden, so draw it again afterwards.
+	pub fn show(&self) {
+		unsafe {
+			XMapRaised(self.display.as_raw(), self.window);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Hides the overlay without destroying it.
+	pub fn hide(&self) {
+		unsafe {
+			XUnmapWindow(self.display.as_raw(), self.window);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Uses the font called `name` for [Self::text], returns false and keeps the current font if it isn't found.
+	///
+	/// This is a core font name like `fixed` or `-misc-fixed-medium-r-*-*-13-*-*-*-*-*-iso8859-1`,
+	/// or a fontconfig pattern like `DejaVu Sans-12` with the `xft` feature.
+	pub fn set_font(&mut self, name: &str) -> bool {
+		self.text.set_font(&self.display, name)
+	}
+
+	/// Fills the overlay with `color` whenever the server repaints it, e.g. after it was covered,
+	/// instead of leaving it transparent.
+	///
+	/// This is enough for overlays of one color, like the edges of a selection rectangle.
+	pub fn set_background(&self, color: Color) {
+		unsafe {
+			XSetWindowBackground(self.display.as_raw(), self.window, color.pixel(&*self.visual, self.depth));
+			XClearWindow(self.display.as_raw(), self.window);
+		}
+	}
+
+	/// Erases everything that was drawn, which fills the overlay with its background again.
+	pub fn clear(&self) {
+		unsafe { XClearWindow(self.display.as_raw(), self.window) };
+	}
+
+	/// Fills `rect`, relative to the top-left corner of the overlay, with `color`.
+	pub fn fill_rect(&self, rect: Rect, color: Color) {
+		self.set_color(color);
+		unsafe { XFillRectangle(self.display.as_raw(), self.window, self.gc, rect.x, rect.y, rect.width, rect.height) };
+	}
+
+	/// Draws the outline of `rect` with lines that are `width` pixels wide.
+	///
+	/// The outline is drawn inside of `rect`, so it can cover the whole overlay.
+	pub fn stroke_rect(&self, rect: Rect, color: Color, width: u32) {
+		let width = width.max(1);
+		if rect.width <= width * 2 || rect.height <= width * 2 {
+			self.fill_rect(rect, color);
+			return;
+		}
+		self.set_color(color);
+		self.set_line_width(width);
+		let inset = (width / 2) as c_int;
+		unsafe {
+			XDrawRectangle(
+				self.display.as_raw(), self.window, self.gc, rect.x + inset, rect.y + inset,
+				rect.width - width, rect.height - width,
+			)
+		};
+	}
+
+	/// Draws a line from `from` to `to` that is `width` pixels wide.
+	pub fn line(&self, from: (c_int, c_int), to: (c_int, c_int), color: Color, width: u32) {
+		self.set_color(color);
+		self.set_line_width(width);
+		unsafe { XDrawLine(self.display.as_raw(), self.window, self.gc, from.0, from.1, to.0, to.1) };
+	}
+
+	/// Draws `text` with its baseline at `y` and its left edge at `x`.
+	///
+	/// Core fonts only cover Latin-1, other characters are left out unless the `xft` feature is on.
+	pub fn text(&self, x: c_int, y: c_int, text: &str, color: Color) {
+		self.set_color(color);
+		self.text.draw(&self.display, self.window, self.gc, (x, y), text, color);
+	}
+
+	/// The size `text` would take up when drawn with the current font.
+	pub fn text_size(&self, text: &str) -> (u32, u32) {
+		let (ascent, descent) = self.text.extents();
+		(self.text.width(&self.display, text), (ascent + descent).max(0) as u32)
+	}
+
+	/// Draws `text` on a filled box with `padding` pixels around it, the top-left corner at `x`, `y`.
+	///
+	/// Returns the box, which is handy for placing labels next to each other.
+	pub fn label(&self, x: c_int, y: c_int, text: &str, color: Color, background: Color, padding: u32) -> Rect {
+		let (width, height) = self.text_size(text);
+		let rect = Rect::new(x, y, width + padding * 2, height + padding * 2);
+		self.fill_rect(rect, background);
+		let (ascent, _) = self.text.extents();
+		self.text(x + padding as c_int, y + padding as c_int + ascent, text, color);
+		rect
+	}
+
+	/// Sends everything that was drawn to the server, so that it shows up.
+	pub fn flush(&self) {
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+
+	fn set_color(&self, color: Color) {
+		unsafe { XSetForeground(self.display.as_raw(), self.gc, color.pixel(&*self.visual, self.depth)) };
+	}
+
+	fn set_line_width(&self, width: u32) {
+		unsafe { XSetLineAttributes(self.display.as_raw(), self.gc, width, LineSolid, CapButt, JoinMiter) };
+	}
+}
+
+impl Drop for Overlay {
+	fn drop(&mut self) {
+		self.text.close(&self.display);
+		unsafe {
+			XFreeGC(self.display.as_raw(), self.gc);
+			XDestroyWindow(self.display.as_raw(), self.window);
+			XFreeColormap(self.display.as_raw(), self.colormap);
+			XFlush(self.display.as_raw());
+		}
+	}
+}
+
+/// Gives the window an empty input shape, so the pointer ends up on the windows below it.
+#[cfg(feature = "xfixes")]
+unsafe fn pass_input_through(display: &Display, window: XWindow) {
+	use x11::xfixes::{XFixesCreateRegion, XFixesDestroyRegion, XFixesSetWindowShapeRegion};
+	/// `ShapeInput` from the SHAPE extension, which isn't part of the x11 crate.
+	const SHAPE_INPUT: c_int = 2;
+	let region = XFixesCreateRegion(display.as_raw(), std::ptr::null_mut(), 0);
+	XFixesSetWindowShapeRegion(display.as_raw(), window, SHAPE_INPUT, 0, 0, region);
+	XFixesDestroyRegion(display.as_raw(), region);
+}
@@ -0,0 +1,183 @@
+use std::{mem::MaybeUninit, os::raw::c_int, sync::Arc};
+
+use x11::{
+	keysym::XK_Escape,
+	xlib::{
+		Button1, Button3, ButtonPress, ButtonRelease, KeyPress, MotionNotify, XEvent, XLookupKeysym, XNextEvent,
+		XQueryPointer, Window as XWindow,
+	},
+};
+
+use crate::{
+	grab::{GrabError, PointerGrabOptions, CURSOR_CROSSHAIR},
+	overlay::{Color, Overlay},
+	Display, Rect, Session, Window,
+};
+
+/// How thick the outline drawn around the selection is.
+const OUTLINE_WIDTH: u32 = 2;
+
+/// The color of the outline drawn around the selection.
+const OUTLINE_COLOR: Color = Color::RED;
+
+/// Lets the user drag a rectangle on the screen, see [Session::pick_region].
+pub(crate) fn pick_region(session: &Session) -> Result<Option<Rect>, GrabError> {
+	let _pointer = session.grab_pointer(PointerGrabOptions { cursor: Some(CURSOR_CROSSHAIR), ..Default::default() })?;
+	// Escape only cancels when the keyboard isn't grabbed by someone else already.
+	let _keyboard = session.grab_keyboard().ok();
+	let outline = Outline::new(&session.display);
+	let mut start = None;
+	loop {
+		let event = next_event(&session.display);
+		match event.get_type() {
+			typ if typ == ButtonPress => {
+				let button = unsafe { event.button };
+				match button.button {
+					pressed if pressed == Button1 => start = Some((button.x_root, button.y_root)),
+					pressed if pressed == Button3 => return Ok(None),
+					_ => {}
+				}
+			}
+			typ if typ == MotionNotify => {
+				let motion = unsafe { event.motion };
+				if let Some(start) = start {
+					outline.show(span(start, (motion.x_root, motion.y_root)));
+				}
+			}
+			typ if typ == ButtonRelease => {
+				let button = unsafe { event.button };
+				if let Some(start) = start.filter(|_| button.button == Button1) {
+					let rect = span(start, (button.x_root, button.y_root));
+					return Ok(if rect.width == 0 || rect.height == 0 { None } else { Some(rect) });
+				}
+			}
+			typ if typ == KeyPress && is_escape(&event) => return Ok(None),
+			_ => {}
+		}
+	}
+}
+
+/// Lets the user click a window while the one under the pointer is outlined, see [Session::pick_window].
+pub(crate) fn pick_window(session: &Session) -> Result<Option<Window>, GrabError> {
+	let _pointer = session.grab_pointer(PointerGrabOptions { cursor: Some(CURSOR_CROSSHAIR), ..Default::default() })?;
+	let _keyboard = session.grab_keyboard().ok();
+	// The top level window under the pointer is the frame of the window manager, not the window itself.
+	let frames: Vec<(XWindow, Window)> = session
+		.get_windows()
+		.map(|windows| windows.inner().into_iter().filter_map(|it| Some((it.frame().ok()?.window, it))).collect())
+		.unwrap_or_default();
+	let outline = Outline::new(&session.display);
+	let mut hovered = hover(session, &frames, &outline, None);
+	loop {
+		let event = next_event(&session.display);
+		match event.get_type() {
+			typ if typ == MotionNotify => hovered = hover(session, &frames, &outline, hovered),
+			typ if typ == ButtonPress => {
+				match unsafe { event.button }.button {
+					pressed if pressed == Button1 => {
+						if let Some(i) = hover(session, &frames, &outline, hovered) {
+							return Ok(Some(frames[i].1.clone()));
+						}
+					}
+					pressed if pressed == Button3 => return Ok(None),
+					_ => {}
+				}
+			}
+			typ if typ == KeyPress && is_escape(&event) => return Ok(None),
+			_ => {}
+		}
+	}
+}
+
+/// Outlines the window under the pointer and returns its index in `frames`.
+///
+/// While the pointer is over the outline itself, the window that was hovered before stays.
+fn hover(session: &Session, frames: &[(XWindow, Window)], outline: &Outline, hovered: Option<usize>) -> Option<usize> {
+	let child = top_level_under_pointer(session);
+	if outline.contains(child) {
+		return hovered;
+	}
+	let hovered = frames.iter().position(|(frame, _)| *frame == child);
+	match hovered {
+		Some(i) => outline.show(frame_rect(&session.display, frames[i].0)),
+		None => outline.hide(),
+	}
+	hovered
+}
+
+/// A rectangle drawn with four thin overlays, which needs no compositor to leave the inside visible.
+struct Outline {
+	edges: [Overlay; 4],
+}
+
+impl Outline {
+	fn new(display: &Arc<Display>) -> Self {
+		let edge = || {
+			let overlay = Overlay::with_display(Arc::clone(display), Rect::new(0, 0, 1, 1));
+			overlay.hide();
+			overlay.set_background(OUTLINE_COLOR);
+			overlay
+		};
+		Outline { edges: [edge(), edge(), edge(), edge()] }
+	}
+
+	fn show(&self, rect: Rect) {
+		let width = OUTLINE_WIDTH;
+		let rects = [
+			Rect::new(rect.x, rect.y, rect.width, width),
+			Rect::new(rect.x, rect.y + rect.height as c_int - width as c_int, rect.width, width),
+			Rect::new(rect.x, rect.y, width, rect.height),
+			Rect::new(rect.x + rect.width as c_int - width as c_int, rect.y, width, rect.height),
+		];
+		for (edge, rect) in self.edges.iter().zip(rects.iter()) {
+			edge.move_resize(*rect);
+			edge.show();
+		}
+	}
+
+	fn hide(&self) {
+		self.edges.iter().for_each(Overlay::hide);
+	}
+
+	fn contains(&self, window: XWindow) -> bool {
+		self.edges.iter().any(|edge| edge.window().window == window)
+	}
+}
+
+/// The rectangle between two corners, in whatever order they were given.
+fn span(a: (c_int, c_int), b: (c_int, c_int)) -> Rect {
+	let (x, y) = (a.0.min(b.0), a.1.min(b.1));
+	Rect::new(x, y, (a.0 - b.0).unsigned_abs(), (a.1 - b.1).unsigned_abs())
+}
+
+/// The child of the root window under the pointer, 0 if there is none.
+fn top_level_under_pointer(session: &Session) -> XWindow {
+	let (mut root, mut child, mut root_x, mut root_y, mut x, mut y, mut mask) = (0, 0, 0, 0, 0, 0, 0);
+	unsafe {
+		XQueryPointer(
+			session.display.as_raw(), session.root().window, &mut root, &mut child,
+			&mut root_x, &mut root_y, &mut x, &mut y, &mut mask,
+		)
+	};
+	child
+}
+
+/// Where a child of the root window is, borders included.
+fn frame_rect(display: &Arc<Display>, frame: XWindow) -> Rect {
+	let attr = Window { window: frame, display: Arc::clone(display) }.get_attr();
+	let border = attr.border_width as u32 * 2;
+	Rect::new(attr.x, attr.y, attr.width as u32 + border, attr.height as u32 + border)
+}
+
+fn next_event(display: &Display) -> XEvent {
+	let mut event = MaybeUninit::<XEvent>::uninit();
+	unsafe {
+		XNextEvent(display.as_raw(), event.as_mut_ptr());
+		event.assume_init()
+	}
+}
+
+fn is_escape(event: &XEvent) -> bool {
+	let mut key = unsafe { event.key };
+	unsafe { XLookupKeysym(&mut key, 0) == XK_Escape as _ }
+}
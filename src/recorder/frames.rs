@@ -0,0 +1,174 @@
+use std::{
+	collections::hash_map::DefaultHasher,
+	hash::{Hash, Hasher},
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+		mpsc::sync_channel,
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use x11::xlib::Window as XWindow;
+
+use crate::{Display, Rect, Window};
+use crate::capture::{CaptureError, CaptureSession, draw_cursor, Frame, PixelFormat};
+
+/// Options for a [Recorder].
+#[derive(Copy, Clone, Debug)]
+pub struct RecorderOptions {
+	/// How many frames are captured every second.
+	pub fps: u32,
+	/// Draw the mouse cursor on top of the frames.
+	///
+	/// This needs the `xfixes` feature, without it the cursor is never drawn.
+	pub cursor: bool,
+	/// Only record this part of the window, see [CaptureSession::set_region].
+	pub region: Option<Rect>,
+	/// Record the title bar and borders as well, see [CaptureSession::set_decorations].
+	pub decorations: bool,
+	/// The layout of the pixels handed to the callback.
+	pub format: PixelFormat,
+	/// Don't call the callback for frames that are identical to the previous one.
+	pub skip_unchanged: bool,
+}
+
+impl Default for RecorderOptions {
+	fn default() -> Self {
+		Self {
+			fps: 30,
+			cursor: false,
+			region: None,
+			decorations: false,
+			format: PixelFormat::Bgra,
+			skip_unchanged: false,
+		}
+	}
+}
+
+/// Records a window on a worker thread, handing every frame to a callback.
+///
+/// The worker opens its own connection to the X server with [Display::open], so its captures
+/// don't wait for the lock of the connection the window is used on elsewhere.
+/// Resizing of the window is handled by the [CaptureSession] used for grabbing,
+/// frames that are late are dropped instead of being captured in a burst.
+///
+/// The recording stops when the [Recorder] is dropped or [Recorder::stop] is called.
+///
+/// # Example
+/// ```ignore
+/// let recorder = Recorder::start(&window, RecorderOptions { fps: 60, ..Default::default() }, |frame| {
+///     encoder.push(&frame.data);
+/// }).expect("Could not open a display for the recorder.");
+/// thread::sleep(Duration::from_secs(10));
+/// recorder.stop().expect("Error while recording.");
+/// ```
+pub struct Recorder {
+	running: Arc<AtomicBool>,
+	handle: Option<JoinHandle<Result<(), CaptureError>>>,
+}
+
+impl Recorder {
+	/// Starts recording the window.
+	///
+	/// Returns [None] if the worker thread could not open a display.
+	pub fn start<F>(window: &Window, options: RecorderOptions, callback: F) -> Option<Self>
+		where F: FnMut(Frame) + Send + 'static {
+		let id = window.window;
+		let running = Arc::new(AtomicBool::new(true));
+		let (ready, opened) = sync_channel(1);
+		let flag = Arc::clone(&running);
+		let handle = thread::Builder::new()
+			.name("x11-recorder".into())
+			.spawn(move || {
+				let display = match Display::open() {
+					Some(display) => display,
+					None => {
+						let _ = ready.send(false);
+						return Ok(());
+					}
+				};
+				let _ = ready.send(true);
+				record(display, id, options, callback, &flag)
+			})
+			.ok()?;
+
+		if opened.recv().unwrap_or(false) {
+			Some(Self { running, handle: Some(handle) })
+		} else {
+			let _ = handle.join();
+			None
+		}
+	}
+
+	/// Whether the worker is still recording, it stops on its own when a capture fails.
+	pub fn is_running(&self) -> bool {
+		self.running.load(Ordering::Relaxed)
+	}
+
+	/// Stops the recording and waits for the worker to finish.
+	///
+	/// Returns the error that stopped the worker early, if any.
+	pub fn stop(mut self) -> Result<(), CaptureError> {
+		self.join()
+	}
+
+	fn join(&mut self) -> Result<(), CaptureError> {
+		self.running.store(false, Ordering::Relaxed);
+		match self.handle.take() {
+			Some(handle) => handle.join().unwrap_or(Ok(())),
+			None => Ok(()),
+		}
+	}
+}
+
+impl Drop for Recorder {
+	fn drop(&mut self) {
+		let _ = self.join();
+	}
+}
+
+fn record<F: FnMut(Frame)>(display: Display, id: XWindow, options: RecorderOptions, mut callback: F, running: &AtomicBool) -> Result<(), CaptureError> {
+	let window = Window { window: id, display: display.shared() };
+	let mut session = CaptureSession::new(window);
+	session.set_region(options.region);
+	session.set_decorations(options.decorations);
+	let interval = Duration::from_secs(1) / options.fps.max(1);
+	let mut due = Instant::now();
+	let mut previous = None;
+
+	while running.load(Ordering::Relaxed) {
+		let now = Instant::now();
+		if now < due {
+			thread::sleep(due - now);
+			continue;
+		}
+		due = (due + interval).max(now);
+
+		let res = session.capture().and_then(|img| Frame::from_image(img, options.format));
+		let mut frame = match res {
+			Ok(frame) => frame,
+			Err(err) => {
+				running.store(false, Ordering::Relaxed);
+				return Err(err);
+			}
+		};
+		if options.cursor {
+			let (x, y) = session.source().root_position().unwrap_or_default();
+			let area = session.area();
+			draw_cursor(&session.window().display, (x + area.x, y + area.y), &mut frame);
+		}
+		if options.skip_unchanged {
+			let mut hasher = DefaultHasher::new();
+			frame.data.hash(&mut hasher);
+			let hash = hasher.finish();
+			if previous == Some(hash) {
+				continue;
+			}
+			previous = Some(hash);
+		}
+		callback(frame);
+	}
+	Ok(())
+}
@@ -0,0 +1,328 @@
+use std::{
+	collections::HashMap,
+	os::raw::{c_char, c_int, c_uint},
+	slice,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+		mpsc::sync_channel,
+	},
+	thread::{self, JoinHandle},
+	time::Duration,
+};
+
+use x11::xlib::{
+	ButtonPress, ButtonRelease, KeyPress, KeyRelease, MotionNotify, PointerRoot, Time, Window as XWindow,
+	XDefaultRootWindow, XFlush, XFree, XGetInputFocus, XTranslateCoordinates, XkbKeycodeToKeysym,
+};
+use x11::xrecord::{
+	XRecordAllClients, XRecordAllocRange, XRecordContext, XRecordCreateContext, XRecordDisableContext,
+	XRecordEnableContextAsync, XRecordFreeContext, XRecordFreeData, XRecordFromServer, XRecordInterceptData,
+	XRecordProcessReplies, XRecordQueryVersion,
+};
+
+use crate::actions::{Action, Actions, Macro, MacroStep, Target};
+use crate::backend::{Backend, XlibBackend};
+use crate::{Display, Window};
+
+/// How often the worker looks for new events.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Records the real keyboard and mouse input of the whole server on a worker thread.
+///
+/// This uses the XRecord extension, which sees every key and button before it reaches
+/// any window. The recording is turned into [Actions] that can be replayed, or saved with
+/// [Actions::to_script]. Pointer positions are relative to the root window, so replay them
+/// against [crate::Session::root] to hit the same places on the screen.
+///
+/// Keys are recorded as the keysym of their first level, modifier keys are recorded as keys of their own.
+///
+/// # Example
+/// ```ignore
+/// let recorder = InputRecorder::start().expect("XRecord is not supported.");
+/// thread::sleep(Duration::from_secs(10));
+/// let actions = recorder.stop();
+/// fs::write("macro.txt", actions.to_script()).unwrap();
+/// ```
+pub struct InputRecorder {
+	worker: Worker,
+}
+
+impl InputRecorder {
+	/// Starts recording.
+	///
+	/// Returns [None] if the worker thread could not open a display or the server lacks XRecord.
+	pub fn start() -> Option<Self> {
+		Worker::start("x11-input-recorder", false).map(|worker| Self { worker })
+	}
+
+	/// Stops recording and returns what was recorded.
+	pub fn stop(mut self) -> Actions {
+		let mut actions = Vec::new();
+		for step in self.worker.join() {
+			if !step.delay.is_zero() {
+				actions.push(Action::Wait(step.delay));
+			}
+			actions.push(step.action);
+		}
+		Actions::from(actions)
+	}
+}
+
+/// Records the real keyboard and mouse input like [InputRecorder], together with the windows it was aimed at.
+///
+/// Keys are aimed at the window that has the focus and pointer events at the window under the pointer,
+/// the windows are remembered by their title and class, see [Target]. Pointer positions are relative
+/// to those windows, so the [Macro] keeps working when they are moved or opened again somewhere else.
+/// Input that is not aimed at a window of the client list, e.g. at the desktop, has no target.
+///
+/// # Example
+/// ```ignore
+/// let recorder = MacroRecorder::start().expect("XRecord is not supported.");
+/// thread::sleep(Duration::from_secs(10));
+/// let recording = recorder.stop();
+/// fs::write("macro.tsv", recording.to_script()).unwrap();
+/// recording.replay(&session, 2.0)?;
+/// ```
+pub struct MacroRecorder {
+	worker: Worker,
+}
+
+impl MacroRecorder {
+	/// Starts recording.
+	///
+	/// Returns [None] if the worker thread could not open a display or the server lacks XRecord.
+	pub fn start() -> Option<Self> {
+		Worker::start("x11-macro-recorder", true).map(|worker| Self { worker })
+	}
+
+	/// Stops recording and returns what was recorded.
+	pub fn stop(mut self) -> Macro {
+		Macro::from(self.worker.join())
+	}
+}
+
+/// The thread that records, stopped and joined when dropped.
+struct Worker {
+	running: Arc<AtomicBool>,
+	handle: Option<JoinHandle<Vec<MacroStep>>>,
+}
+
+impl Worker {
+	fn start(name: &str, aim: bool) -> Option<Self> {
+		let running = Arc::new(AtomicBool::new(true));
+		let (ready, started) = sync_channel(1);
+		let flag = Arc::clone(&running);
+		let handle = thread::Builder::new()
+			.name(name.into())
+			.spawn(move || {
+				// The context is controlled on one connection and its data arrives on another.
+				let (control, data) = match (Display::open(), Display::open()) {
+					(Some(control), Some(data)) => (control.shared(), data),
+					_ => {
+						let _ = ready.send(false);
+						return Vec::new();
+					}
+				};
+				record(&control, &data, &flag, aim, |ok| { let _ = ready.send(ok); })
+			})
+			.ok()?;
+
+		if started.recv().unwrap_or(false) {
+			Some(Self { running, handle: Some(handle) })
+		} else {
+			let _ = handle.join();
+			None
+		}
+	}
+
+	fn join(&mut self) -> Vec<MacroStep> {
+		self.running.store(false, Ordering::Relaxed);
+		match self.handle.take() {
+			Some(handle) => handle.join().unwrap_or_default(),
+			None => Vec::new(),
+		}
+	}
+}
+
+impl Drop for Worker {
+	fn drop(&mut self) {
+		let _ = self.join();
+	}
+}
+
+/// The state the callback of XRecord writes into.
+struct Recording<'a> {
+	display: &'a Arc<Display>,
+	steps: Vec<MacroStep>,
+	last: Option<Time>,
+	aims: Option<Aims>,
+}
+
+/// What is known about the windows the input of a macro is aimed at, see [MacroRecorder].
+#[derive(Default)]
+struct Aims {
+	/// The client window inside every frame of the window manager that was seen.
+	clients: HashMap<XWindow, XWindow>,
+	/// The target of every client window that was seen, read when it was seen first.
+	targets: HashMap<XWindow, Target>,
+}
+
+fn record(control: &Arc<Display>, data: &Display, running: &AtomicBool, aim: bool, started: impl FnOnce(bool)) -> Vec<MacroStep> {
+	let (mut major, mut minor) = (0, 0);
+	if unsafe { XRecordQueryVersion(control.as_raw(), &mut major, &mut minor) } == 0 {
+		started(false);
+		return Vec::new();
+	}
+	let context = unsafe { create_context(control) };
+	if context == 0 {
+		started(false);
+		return Vec::new();
+	}
+
+	let aims = if aim { Some(Aims::default()) } else { None };
+	let mut recording = Recording { display: control, steps: Vec::new(), last: None, aims };
+	let enabled = unsafe {
+		XRecordEnableContextAsync(data.as_raw(), context, Some(intercept), &mut recording as *mut Recording as *mut c_char)
+	};
+	started(enabled != 0);
+	if enabled != 0 {
+		while running.load(Ordering::Relaxed) {
+			unsafe { XRecordProcessReplies(data.as_raw()) };
+			thread::sleep(POLL_INTERVAL);
+		}
+		unsafe {
+			XRecordDisableContext(control.as_raw(), context);
+			XFlush(control.as_raw());
+			XRecordProcessReplies(data.as_raw());
+		}
+	}
+	unsafe { XRecordFreeContext(control.as_raw(), context) };
+	recording.steps
+}
+
+/// Creates a context that records the device events of every client.
+///
+/// # Safety
+/// `display` has to be a valid connection.
+unsafe fn create_context(display: &Display) -> XRecordContext {
+	let range = XRecordAllocRange();
+	if range.is_null() {
+		return 0;
+	}
+	(*range).device_events.first = KeyPress as _;
+	(*range).device_events.last = MotionNotify as _;
+	let mut clients = XRecordAllClients;
+	let mut ranges = range;
+	let context = XRecordCreateContext(display.as_raw(), 0, &mut clients, 1, &mut ranges, 1);
+	XFree(range as _);
+	context
+}
+
+unsafe extern "C" fn intercept(closure: *mut c_char, data: *mut XRecordInterceptData) {
+	let recording = &mut *(closure as *mut Recording);
+	let intercepted = &*data;
+	// The callback runs on the worker, inside XRecordProcessReplies.
+	if intercepted.category == XRecordFromServer && intercepted.data_len >= 8 {
+		// The length is in units of 4 bytes, a core event is 32 bytes.
+		let event = slice::from_raw_parts(intercepted.data, intercepted.data_len as usize * 4);
+		recording.push(event);
+	}
+	XRecordFreeData(data);
+}
+
+impl Recording<'_> {
+	/// Turns a core event in wire format into a step, delayed by the time since the last one.
+	fn push(&mut self, event: &[u8]) {
+		let time = u32::from_ne_bytes([event[4], event[5], event[6], event[7]]) as Time;
+		let short = |at: usize| i16::from_ne_bytes([event[at], event[at + 1]]) as c_int;
+		let (kind, detail) = ((event[0] & 0x7f) as c_int, event[1]);
+		let (x, y, state) = (short(20), short(22), short(28) as u16 as c_uint);
+		let action = match kind {
+			_ if kind == KeyPress || kind == KeyRelease => {
+				let keysym = unsafe { XkbKeycodeToKeysym(self.display.as_raw(), detail, 0, 0) };
+				Action::Key { press: kind == KeyPress, keysym: keysym as _, modifiers: state }
+			}
+			_ if kind == ButtonPress || kind == ButtonRelease => Action::Button { press: kind == ButtonPress, button: detail as _, x, y },
+			_ if kind == MotionNotify => Action::Move { x, y },
+			_ => return,
+		};
+		let delay = match self.last.replace(time) {
+			Some(last) => Duration::from_millis((time as u32).wrapping_sub(last as u32) as u64),
+			None => Duration::ZERO,
+		};
+		let (target, action) = match self.aims.take() {
+			Some(mut aims) => {
+				let aimed = aims.aim(self.display, action);
+				self.aims = Some(aims);
+				aimed
+			}
+			None => (None, action),
+		};
+		self.steps.push(MacroStep { delay, target, action });
+	}
+}
+
+impl Aims {
+	/// Finds the window the action is aimed at and makes its position relative to that window.
+	fn aim(&mut self, display: &Arc<Display>, action: Action) -> (Option<Target>, Action) {
+		let root = unsafe { XDefaultRootWindow(display.as_raw()) };
+		let translate = |to: XWindow, x: c_int, y: c_int| {
+			let (mut to_x, mut to_y, mut child) = (0, 0, 0);
+			unsafe { XTranslateCoordinates(display.as_raw(), root, to, x, y, &mut to_x, &mut to_y, &mut child) };
+			(to_x, to_y, child)
+		};
+		let top_level = match action {
+			Action::Button { x, y, .. } | Action::Move { x, y } => translate(root, x, y).2,
+			_ => {
+				let (mut focus, mut revert) = (0, 0);
+				unsafe { XGetInputFocus(display.as_raw(), &mut focus, &mut revert) };
+				// No focus and PointerRoot are 0 and 1, there is no window to aim at then.
+				if focus <= PointerRoot as XWindow || focus == root {
+					return (None, action);
+				}
+				match (Window { window: focus, display: Arc::clone(display) }).frame() {
+					Ok(frame) => frame.window,
+					// The focused window is gone already, there is nothing to aim at.
+					Err(_) => return (None, action),
+				}
+			}
+		};
+		let client = match self.client(display, top_level) {
+			Some(it) => it,
+			None => return (None, action),
+		};
+		let target = self
+			.targets
+			.entry(client)
+			.or_insert_with(|| Target::of(&Window { window: client, display: Arc::clone(display) }))
+			.clone();
+		let action = match action {
+			Action::Button { press, button, x, y } => {
+				let (x, y, _) = translate(client, x, y);
+				Action::Button { press, button, x, y }
+			}
+			Action::Move { x, y } => {
+				let (x, y, _) = translate(client, x, y);
+				Action::Move { x, y }
+			}
+			action => action,
+		};
+		(Some(target), action)
+	}
+
+	/// The client window inside a child of the root window, the client list is read again for frames not seen yet.
+	fn client(&mut self, display: &Arc<Display>, top_level: XWindow) -> Option<XWindow> {
+		if top_level == 0 {
+			return None;
+		}
+		if !self.clients.contains_key(&top_level) {
+			for client in XlibBackend::new(Arc::clone(display)).client_list().unwrap_or_default() {
+				if let Ok(frame) = (Window { window: client, display: Arc::clone(display) }).frame() {
+					self.clients.insert(frame.window, client);
+				}
+			}
+		}
+		self.clients.get(&top_level).copied()
+	}
+}
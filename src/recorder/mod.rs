@@ -0,0 +1,11 @@
+mod frames;
+mod snapshots;
+mod thumbnails;
+#[cfg(feature = "xrecord")]
+mod input;
+
+pub use self::frames::*;
+pub use self::snapshots::{SnapshotOptions, Snapshots};
+pub use self::thumbnails::{ThumbnailOptions, Thumbnails};
+#[cfg(feature = "xrecord")]
+pub use self::input::{InputRecorder, MacroRecorder};
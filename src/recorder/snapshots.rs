@@ -0,0 +1,134 @@
+use std::{
+	fs,
+	io,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use crate::Window;
+use crate::capture::{Frame, PixelFormat};
+
+use super::{Recorder, RecorderOptions};
+
+/// How much a colour channel may change before the pixel counts as changed.
+const CHANNEL_TOLERANCE: u8 = 16;
+
+/// Options for [Window::snapshot_on_change].
+#[derive(Copy, Clone, Debug)]
+pub struct SnapshotOptions {
+	/// How often the window is captured to look for changes.
+	pub interval: Duration,
+	/// How long the window has to stay the same after a change before it is saved,
+	/// so an animation is saved once it finished instead of every frame of it.
+	pub debounce: Duration,
+	/// The part of the pixels, from 0 to 1, that have to differ from the last saved image.
+	pub min_changed: f32,
+	/// Save the title bar and borders as well.
+	pub decorations: bool,
+}
+
+impl Default for SnapshotOptions {
+	fn default() -> Self {
+		Self {
+			interval: Duration::from_millis(250),
+			debounce: Duration::from_millis(500),
+			min_changed: 0.001,
+			decorations: false,
+		}
+	}
+}
+
+/// Saves a PNG image of a window every time its contents change, see [Window::snapshot_on_change].
+///
+/// Saving stops when this is dropped or [Snapshots::stop] is called.
+pub struct Snapshots {
+	recorder: Option<Recorder>,
+	state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+	saved: Vec<PathBuf>,
+	error: Option<io::Error>,
+}
+
+impl Snapshots {
+	pub(crate) fn start(window: &Window, dir: &Path, options: SnapshotOptions) -> io::Result<Self> {
+		fs::create_dir_all(dir)?;
+		let state = Arc::new(Mutex::new(State::default()));
+		let shared = Arc::clone(&state);
+		let dir = dir.to_path_buf();
+		let recorder_options = RecorderOptions {
+			fps: (1.0 / options.interval.as_secs_f32().max(0.001)).ceil() as u32,
+			decorations: options.decorations,
+			format: PixelFormat::Rgba,
+			..Default::default()
+		};
+		// The frame waiting for the window to settle, with the time it last changed.
+		let mut pending: Option<(Frame, Instant)> = None;
+		let mut last_saved: Option<Frame> = None;
+		let recorder = Recorder::start(window, recorder_options, move |frame| {
+			let settled = match &pending {
+				Some((previous, _)) if changed_part(previous, &frame) > 0.0 => {
+					pending = Some((frame, Instant::now()));
+					return;
+				}
+				Some((_, since)) => since.elapsed() >= options.debounce,
+				None => {
+					pending = Some((frame, Instant::now()));
+					return;
+				}
+			};
+			if !settled {
+				return;
+			}
+			let material = last_saved.as_ref().is_none_or(|saved| changed_part(saved, &frame) >= options.min_changed);
+			if !material {
+				return;
+			}
+			let mut state = shared.lock().unwrap_or_else(|it| it.into_inner());
+			let path = dir.join(format!("snapshot-{:05}.png", state.saved.len()));
+			match frame.save_png(&path) {
+				Ok(()) => state.saved.push(path),
+				Err(err) => state.error = Some(err),
+			}
+			last_saved = Some(frame);
+		});
+		let recorder = recorder.ok_or_else(|| io::Error::other("could not open a display for the snapshots"))?;
+		Ok(Snapshots { recorder: Some(recorder), state })
+	}
+
+	/// The images that were saved so far.
+	pub fn saved(&self) -> Vec<PathBuf> {
+		self.state.lock().unwrap_or_else(|it| it.into_inner()).saved.clone()
+	}
+
+	/// Stops watching the window and returns the images that were saved.
+	///
+	/// Returns the last error of writing an image instead, if there was any.
+	pub fn stop(mut self) -> io::Result<Vec<PathBuf>> {
+		if let Some(recorder) = self.recorder.take() {
+			let _ = recorder.stop();
+		}
+		let mut state = self.state.lock().unwrap_or_else(|it| it.into_inner());
+		match state.error.take() {
+			Some(err) => Err(err),
+			None => Ok(std::mem::take(&mut state.saved)),
+		}
+	}
+}
+
+/// The part of the pixels that differ between two frames, a change of size counts as everything.
+fn changed_part(a: &Frame, b: &Frame) -> f32 {
+	if a.width != b.width || a.height != b.height || a.format != b.format {
+		return 1.0;
+	}
+	let bpp = a.format.bytes_per_pixel();
+	let changed = a.data
+		.chunks_exact(bpp)
+		.zip(b.data.chunks_exact(bpp))
+		.filter(|(a, b)| a.iter().zip(b.iter()).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+		.count();
+	changed as f32 / (a.width as f32 * a.height as f32).max(1.0)
+}
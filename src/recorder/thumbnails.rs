@@ -0,0 +1,180 @@
+use std::{
+	collections::{hash_map::DefaultHasher, HashMap},
+	hash::{Hash, Hasher},
+	sync::{
+		Arc,
+		Mutex,
+		atomic::{AtomicBool, Ordering},
+		mpsc::{channel, Receiver, sync_channel},
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use x11::xlib::Window as XWindow;
+
+use crate::{Display, Window};
+use crate::capture::{CaptureSession, Frame, PixelFormat};
+
+/// How long the worker sleeps at most before it checks whether it was stopped.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Options for [Thumbnails].
+#[derive(Copy, Clone, Debug)]
+pub struct ThumbnailOptions {
+	/// The width of the thumbnails, windows that are narrower keep their size.
+	pub width: u32,
+	/// How often every window is captured again.
+	pub interval: Duration,
+	/// The layout of the pixels of the thumbnails.
+	pub format: PixelFormat,
+	/// Include the title bar and borders, see [CaptureSession::set_decorations].
+	pub decorations: bool,
+}
+
+impl Default for ThumbnailOptions {
+	fn default() -> Self {
+		Self {
+			width: 256,
+			interval: Duration::from_secs(2),
+			format: PixelFormat::Bgra,
+			decorations: false,
+		}
+	}
+}
+
+/// Keeps scaled down captures of a set of windows up to date on a worker thread, e.g. for previews in a switcher.
+///
+/// Every [ThumbnailOptions::interval] all the windows are captured, and a new thumbnail is handed
+/// to the callback only for the windows whose contents changed since the last one.
+/// Windows that can't be captured, e.g. because they are unmapped, are skipped until they can be again.
+///
+/// The worker opens its own connection to the X server, like the [super::Recorder].
+///
+/// # Example
+/// ```ignore
+/// let windows = session.get_windows().unwrap().inner();
+/// let (thumbnails, updates) = Thumbnails::channel(&windows, ThumbnailOptions::default())
+///     .expect("Could not open a display for the thumbnails.");
+/// for (window, frame) in updates {
+///     switcher.set_preview(window, frame);
+/// }
+/// ```
+pub struct Thumbnails {
+	running: Arc<AtomicBool>,
+	windows: Arc<Mutex<Vec<XWindow>>>,
+	handle: Option<JoinHandle<()>>,
+}
+
+impl Thumbnails {
+	/// Starts making thumbnails of the windows, `callback` gets the ID of the window and its new thumbnail.
+	///
+	/// Returns [None] if the worker thread could not open a display.
+	pub fn start<F>(windows: &[Window], options: ThumbnailOptions, callback: F) -> Option<Self>
+		where F: FnMut(XWindow, Frame) + Send + 'static {
+		let running = Arc::new(AtomicBool::new(true));
+		let ids = Arc::new(Mutex::new(windows.iter().map(|it| it.window).collect::<Vec<_>>()));
+		let (ready, opened) = sync_channel(1);
+		let (flag, shared) = (Arc::clone(&running), Arc::clone(&ids));
+		let handle = thread::Builder::new()
+			.name("x11-thumbnails".into())
+			.spawn(move || {
+				let display = match Display::open() {
+					Some(display) => display,
+					None => {
+						let _ = ready.send(false);
+						return;
+					}
+				};
+				let _ = ready.send(true);
+				refresh(display, &shared, options, callback, &flag)
+			})
+			.ok()?;
+
+		if opened.recv().unwrap_or(false) {
+			Some(Self { running, windows: ids, handle: Some(handle) })
+		} else {
+			let _ = handle.join();
+			None
+		}
+	}
+
+	/// Like [Self::start], but the thumbnails are sent to the returned channel.
+	pub fn channel(windows: &[Window], options: ThumbnailOptions) -> Option<(Self, Receiver<(XWindow, Frame)>)> {
+		let (sender, receiver) = channel();
+		let thumbnails = Self::start(windows, options, move |window, frame| {
+			let _ = sender.send((window, frame));
+		})?;
+		Some((thumbnails, receiver))
+	}
+
+	/// Starts making thumbnails of one more window as well.
+	pub fn add(&self, window: &Window) {
+		let mut windows = self.windows.lock().unwrap_or_else(|it| it.into_inner());
+		if !windows.contains(&window.window) {
+			windows.push(window.window);
+		}
+	}
+
+	/// Stops making thumbnails of the window.
+	pub fn remove(&self, window: &Window) {
+		self.windows.lock().unwrap_or_else(|it| it.into_inner()).retain(|it| *it != window.window);
+	}
+
+	/// Stops the worker and waits for it to finish.
+	pub fn stop(mut self) {
+		self.join()
+	}
+
+	fn join(&mut self) {
+		self.running.store(false, Ordering::Relaxed);
+		if let Some(handle) = self.handle.take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+impl Drop for Thumbnails {
+	fn drop(&mut self) {
+		self.join();
+	}
+}
+
+fn refresh<F: FnMut(XWindow, Frame)>(display: Display, windows: &Mutex<Vec<XWindow>>, options: ThumbnailOptions, mut callback: F, running: &AtomicBool) {
+	let display = display.shared();
+	// The capture session and the hash of the last capture of every window.
+	let mut sessions = HashMap::<XWindow, (CaptureSession, Option<u64>)>::new();
+	let mut due = Instant::now();
+
+	while running.load(Ordering::Relaxed) {
+		let now = Instant::now();
+		if now < due {
+			thread::sleep((due - now).min(STOP_CHECK_INTERVAL));
+			continue;
+		}
+		due = (due + options.interval).max(now);
+
+		let ids = windows.lock().unwrap_or_else(|it| it.into_inner()).clone();
+		sessions.retain(|id, _| ids.contains(id));
+		for id in ids {
+			let (session, previous) = sessions.entry(id).or_insert_with(|| {
+				let mut session = CaptureSession::new(Window { window: id, display: display.clone() });
+				session.set_decorations(options.decorations);
+				(session, None)
+			});
+			let frame = match session.capture().and_then(|img| Frame::from_image(img, options.format)) {
+				Ok(frame) => frame,
+				Err(_) => continue,
+			};
+			// Without damage tracking from the server, unchanged windows are found by their pixels.
+			let mut hasher = DefaultHasher::new();
+			frame.data.hash(&mut hasher);
+			let hash = hasher.finish();
+			if *previous == Some(hash) {
+				continue;
+			}
+			*previous = Some(hash);
+			callback(id, frame.scale_to_width(options.width));
+		}
+	}
+}
@@ -0,0 +1,45 @@
+/// A rectangle, used for regions of windows and of the screen.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+	/// Horizontal position of the left edge.
+	pub x: i32,
+	/// Vertical position of the top edge.
+	pub y: i32,
+	/// Width in pixels.
+	pub width: u32,
+	/// Height in pixels.
+	pub height: u32,
+}
+
+impl Rect {
+	/// Creates a rectangle from its position and size.
+	pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+		Rect { x, y, width, height }
+	}
+
+	/// The part of this rectangle that is also inside `other`, [None] if they don't overlap.
+	pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+		let left = self.x.max(other.x);
+		let top = self.y.max(other.y);
+		let right = (self.x + self.width as i32).min(other.x + other.width as i32);
+		let bottom = (self.y + self.height as i32).min(other.y + other.height as i32);
+		if right <= left || bottom <= top {
+			return None;
+		}
+		Some(Rect::new(left, top, (right - left) as u32, (bottom - top) as u32))
+	}
+
+	/// The smallest rectangle that covers both.
+	pub fn union(&self, other: &Rect) -> Rect {
+		let left = self.x.min(other.x);
+		let top = self.y.min(other.y);
+		let right = (self.x + self.width as i32).max(other.x + other.width as i32);
+		let bottom = (self.y + self.height as i32).max(other.y + other.height as i32);
+		Rect::new(left, top, (right - left) as u32, (bottom - top) as u32)
+	}
+
+	/// Whether the point is inside this rectangle.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		x >= self.x && y >= self.y && x < self.x + self.width as i32 && y < self.y + self.height as i32
+	}
+}
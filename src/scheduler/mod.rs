@@ -0,0 +1,112 @@
+mod watcher;
+
+use std::{
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use crate::{Error, Session};
+
+use self::watcher::{Activity, Watcher};
+
+pub(crate) use self::watcher::idle_time;
+
+/// A task of an [IdleScheduler], with when it is due next.
+struct Task {
+	every: Duration,
+	next: Instant,
+	run: Box<dyn FnMut(&Session)>,
+}
+
+/// Runs tasks only while the user is away from the keyboard and mouse, see [Session::idle_scheduler].
+///
+/// A task runs once the user has been idle for the threshold and it is due, then again every interval
+/// while the user stays away. Real input, seen as raw XInput 2 events, pauses the scheduler right away:
+/// the [crate::CancelToken] of the session is cancelled, so a running task that waits on it,
+/// like [crate::actions::Actions::replay] or [crate::Window::type_text], stops early.
+/// The token is reset before the next task runs, and an interrupted task is run again from the start
+/// once the user is idle again, so tasks should be safe to repeat.
+///
+/// Input faked with XTest, by this crate or other automation, doesn't count as real input.
+/// Events sent with XSendEvent are not input at all to the server.
+///
+/// # Example
+/// ```ignore
+/// let mut scheduler = session.idle_scheduler(Duration::from_secs(300));
+/// scheduler.every(Duration::from_secs(3600), |session| {
+///     if let Some(editor) = Target::class("gedit").find(session) {
+///         let _ = editor.focus();
+///         editor.send_combo(&KeyCombo::parse("Ctrl+S").unwrap());
+///     }
+/// });
+/// // Runs until the token of the session is cancelled, e.g. from a Ctrl+C handler.
+/// scheduler.run()?;
+/// ```
+pub struct IdleScheduler<'a> {
+	session: &'a Session,
+	threshold: Duration,
+	tasks: Vec<Task>,
+}
+
+impl<'a> IdleScheduler<'a> {
+	pub(crate) fn new(session: &'a Session, threshold: Duration) -> Self {
+		IdleScheduler { session, threshold, tasks: Vec::new() }
+	}
+
+	/// Adds a task that runs the first time the user is idle, and after that every `interval` while they are.
+	pub fn every(&mut self, interval: Duration, task: impl FnMut(&Session) + 'static) -> &mut Self {
+		self.tasks.push(Task { every: interval, next: Instant::now(), run: Box::new(task) });
+		self
+	}
+
+	/// Runs the tasks on this thread until the [crate::CancelToken] of the session is cancelled.
+	///
+	/// Returns [Error::ExtensionMissing] if the server lacks XInput 2.1, which is needed to notice real input.
+	/// The idle time is taken from the MIT-SCREEN-SAVER extension when this starts, if the server has it,
+	/// otherwise the user is taken to have just been active.
+	pub fn run(&mut self) -> Result<(), Error> {
+		let token = self.session.cancel_token();
+		let idle = idle_time(&self.session.display).unwrap_or_default();
+		let now = Instant::now();
+		let activity = Arc::new(Mutex::new(Activity {
+			last_input: now.checked_sub(idle).unwrap_or(now),
+			running: false,
+			interrupted: false,
+		}));
+		let _watcher = Watcher::start(Arc::clone(&activity), token.clone())?;
+		loop {
+			if token.is_cancelled() {
+				let mut activity = activity.lock().unwrap_or_else(|it| it.into_inner());
+				if !activity.interrupted {
+					return Ok(());
+				}
+				activity.interrupted = false;
+				token.reset();
+			}
+			let now = Instant::now();
+			let wait = {
+				let mut activity = activity.lock().unwrap_or_else(|it| it.into_inner());
+				let idle_at = activity.last_input + self.threshold;
+				let due = self.tasks.iter().map(|it| it.next).min().unwrap_or(now + self.threshold);
+				let start = idle_at.max(due);
+				if start <= now {
+					activity.running = true;
+				}
+				start.saturating_duration_since(now)
+			};
+			if !wait.is_zero() {
+				token.sleep(wait);
+				continue;
+			}
+			let task = self.tasks.iter_mut().filter(|it| it.next <= now).min_by_key(|it| it.next);
+			if let Some(task) = task {
+				(task.run)(self.session);
+				let mut activity = activity.lock().unwrap_or_else(|it| it.into_inner());
+				activity.running = false;
+				if !activity.interrupted {
+					task.next = Instant::now() + task.every;
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,178 @@
+use std::{
+	ffi::CStr,
+	mem::MaybeUninit,
+	os::raw::c_int,
+	slice,
+	sync::{
+		Arc, Mutex,
+		atomic::{AtomicBool, Ordering},
+		mpsc::sync_channel,
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use x11::xinput2::{
+	XIAllDevices, XIAllMasterDevices, XIEventMask, XIFreeDeviceInfo, XIQueryDevice, XIQueryVersion, XIRawEvent,
+	XISelectEvents, XISetMask, XI_LASTEVENT, XI_RawButtonPress, XI_RawKeyPress, XI_RawMotion,
+};
+use x11::xlib::{GenericEvent, XDefaultRootWindow, XEvent, XFlush, XFree, XFreeEventData, XGetEventData, XNextEvent, XPending, XQueryExtension};
+use x11::xss::{XScreenSaverAllocInfo, XScreenSaverQueryInfo};
+
+use crate::{CancelToken, Display, Error};
+
+/// How often the worker looks for new events.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How long the server has not seen any input, from the MIT-SCREEN-SAVER extension.
+pub(crate) fn idle_time(display: &Display) -> Result<Duration, Error> {
+	let info = unsafe { XScreenSaverAllocInfo() };
+	if info.is_null() {
+		return Err(Error::ExtensionMissing("MIT-SCREEN-SAVER"));
+	}
+	let ok = unsafe { XScreenSaverQueryInfo(display.as_raw(), XDefaultRootWindow(display.as_raw()), info) } != 0;
+	let idle = unsafe { (*info).idle };
+	unsafe { XFree(info as _) };
+	if ok { Ok(Duration::from_millis(idle as u64)) } else { Err(Error::ExtensionMissing("MIT-SCREEN-SAVER")) }
+}
+
+/// What the watcher and the scheduler share, behind one lock so that a task never starts right after real input.
+#[derive(Debug)]
+pub(super) struct Activity {
+	/// When the last real input was seen.
+	pub(super) last_input: Instant,
+	/// Whether a task is running, real input interrupts it then.
+	pub(super) running: bool,
+	/// Whether the cancel token was cancelled by real input, not by the user of the token.
+	pub(super) interrupted: bool,
+}
+
+/// The thread that watches the raw input of the server, stopped and joined when dropped.
+pub(super) struct Watcher {
+	running: Arc<AtomicBool>,
+	handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+	/// Starts watching, real input updates `activity` and cancels `token` while a task runs.
+	pub(super) fn start(activity: Arc<Mutex<Activity>>, token: CancelToken) -> Result<Self, Error> {
+		let running = Arc::new(AtomicBool::new(true));
+		let (ready, started) = sync_channel(1);
+		let flag = Arc::clone(&running);
+		let handle = thread::Builder::new()
+			.name("x11-idle-watcher".into())
+			.spawn(move || {
+				let display = match Display::open() {
+					Some(display) => display,
+					None => {
+						let _ = ready.send(Err(Error::DisplayOpenFailed));
+						return;
+					}
+				};
+				let opcode = match select_raw_events(&display) {
+					Ok(opcode) => opcode,
+					Err(err) => {
+						let _ = ready.send(Err(err));
+						return;
+					}
+				};
+				let _ = ready.send(Ok(()));
+				let fake = fake_devices(&display);
+				watch(&display, opcode, &fake, &flag, |activity_at| {
+					let mut activity = activity.lock().unwrap_or_else(|it| it.into_inner());
+					activity.last_input = activity_at;
+					if activity.running {
+						activity.interrupted = true;
+						token.cancel();
+					}
+				});
+			})
+			.map_err(|_| Error::DisplayOpenFailed)?;
+
+		match started.recv().unwrap_or(Err(Error::DisplayOpenFailed)) {
+			Ok(()) => Ok(Self { running, handle: Some(handle) }),
+			Err(err) => {
+				let _ = handle.join();
+				Err(err)
+			}
+		}
+	}
+}
+
+impl Drop for Watcher {
+	fn drop(&mut self) {
+		self.running.store(false, Ordering::Relaxed);
+		if let Some(handle) = self.handle.take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+/// Selects the raw key, button and motion events of all devices on the root window.
+///
+/// Returns the major opcode of XInput, which the events carry.
+fn select_raw_events(display: &Display) -> Result<c_int, Error> {
+	let (mut opcode, mut event, mut error) = (0, 0, 0);
+	let name = CStr::from_bytes_with_nul(b"XInputExtension\0").unwrap();
+	if unsafe { XQueryExtension(display.as_raw(), name.as_ptr(), &mut opcode, &mut event, &mut error) } == 0 {
+		return Err(Error::ExtensionMissing("XInputExtension"));
+	}
+	// Raw events reach the root window during grabs of other clients since XInput 2.1.
+	let (mut major, mut minor) = (2, 1);
+	if unsafe { XIQueryVersion(display.as_raw(), &mut major, &mut minor) } != 0 {
+		return Err(Error::ExtensionMissing("XInputExtension"));
+	}
+	let mut bits = [0u8; (XI_LASTEVENT as usize + 8) / 8];
+	for typ in [XI_RawKeyPress, XI_RawButtonPress, XI_RawMotion] {
+		XISetMask(&mut bits, typ);
+	}
+	let mut mask = XIEventMask { deviceid: XIAllMasterDevices, mask_len: bits.len() as c_int, mask: bits.as_mut_ptr() };
+	unsafe {
+		XISelectEvents(display.as_raw(), XDefaultRootWindow(display.as_raw()), &mut mask, 1);
+		XFlush(display.as_raw());
+	}
+	Ok(opcode)
+}
+
+/// The devices XTest fakes input with, which is the crate and other automation rather than the user.
+fn fake_devices(display: &Display) -> Vec<c_int> {
+	let mut count = 0;
+	let devices = unsafe { XIQueryDevice(display.as_raw(), XIAllDevices, &mut count) };
+	if devices.is_null() {
+		return Vec::new();
+	}
+	let fake = unsafe { slice::from_raw_parts(devices, count.max(0) as usize) }
+		.iter()
+		.filter(|it| !it.name.is_null() && unsafe { CStr::from_ptr(it.name) }.to_string_lossy().contains("XTEST"))
+		.map(|it| it.deviceid)
+		.collect();
+	unsafe { XIFreeDeviceInfo(devices) };
+	fake
+}
+
+/// Calls `input` with the time of every raw event that doesn't come from a `fake` device, until `running` is cleared.
+fn watch(display: &Display, opcode: c_int, fake: &[c_int], running: &AtomicBool, mut input: impl FnMut(Instant)) {
+	let mut event = MaybeUninit::<XEvent>::uninit();
+	while running.load(Ordering::Relaxed) {
+		if unsafe { XPending(display.as_raw()) } == 0 {
+			thread::sleep(POLL_INTERVAL);
+			continue;
+		}
+		let mut event = unsafe {
+			XNextEvent(display.as_raw(), event.as_mut_ptr());
+			event.assume_init()
+		};
+		if event.get_type() != GenericEvent {
+			continue;
+		}
+		let cookie = unsafe { &mut event.generic_event_cookie };
+		if cookie.extension != opcode || unsafe { XGetEventData(display.as_raw(), cookie) } == 0 {
+			continue;
+		}
+		let source = unsafe { (*(cookie.data as *const XIRawEvent)).sourceid };
+		unsafe { XFreeEventData(display.as_raw(), cookie) };
+		if !fake.contains(&source) {
+			input(Instant::now());
+		}
+	}
+}
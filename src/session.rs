@@ -1,21 +1,63 @@
-use std::{
-	os::raw::c_void,
-	slice,
-};
-use std::rc::Rc;
-use std::sync::RwLock;
+use std::os::raw::c_uint;
+#[cfg(feature = "xrandr")]
+use std::slice;
+use std::collections::HashMap;
+use std::io;
+use std::mem::MaybeUninit;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::sync::{Mutex, RwLock};
 
 use x11::xlib::{
 	Window as XWindow,
-	XA_WINDOW,
-	XFree,
+	IsViewable,
+	KeyCode,
+	KeyPress,
+	LockMask,
+	XEvent,
+	XFlush,
+	XKeyEvent,
+	XPutBackEvent,
+	XNextEvent,
+	XPending,
+	XUngrabPointer,
+	CurrentTime,
+	XWarpPointer,
+	XGetInputFocus,
+	XSetInputFocus,
+	PointerRoot,
+	RevertToParent,
 };
 
-use crate::{Atom, Display, NET_ACTIVE_WINDOW, NET_CLIENT_LIST, NotSupported, util::{
-	get_window_property,
-	GetWindowPropertyResponse,
-}, Window, Windows};
+#[cfg(feature = "xrandr")]
+use x11::xrandr::{XRRFreeMonitors, XRRGetMonitors};
+
+use crate::{Atom, backend::{Backend, DefaultBackend}, CancelToken, Display, NET_ACTIVE_WINDOW, NET_CLIENT_LIST, Error, Rect, Window, WindowTree, Windows};
+use crate::picker;
+use crate::capture::{capture_composited, capture_parallel, CaptureError, CaptureOptions, Frame};
+use crate::event::{ActiveWindowWatcher, App, AppWindowWatcher, EventLoop, Events, StackingWatcher, WindowWatcher};
+use crate::event::btn_event::{self, MouseButton};
+#[cfg(feature = "async")]
+use crate::event::EventStream;
+use crate::grab::{GrabError, KeyboardGrab, PointerGrab, PointerGrabOptions};
+#[cfg(feature = "xfixes")]
+use crate::grab::{BarrierDirections, BarrierLine, PointerBarrier};
+use crate::hotkeys::{Hotkey, HotkeyError, Registry};
+use crate::keys::{active_group, KeyboardLayout, KeyCombo, KeyboardState, keyboard_layouts, lock_group, ModifierState, num_lock_mask};
+use crate::layouts;
+use crate::spawn::{self, SpawnError};
 use crate::util::RwLockCell;
+#[cfg(feature = "idle")]
+use crate::scheduler::{self, IdleScheduler};
+#[cfg(feature = "xtest")]
+use crate::event::{backend::InputBackend, xtest};
+#[cfg(feature = "xtest")]
+use crate::event::{btn_event::ButtonType, key_event::KeyType};
+
+/// How often [Session::wait_keys_released] looks at the keyboard.
+const KEY_RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// This is meant to be a struct that makes it easy to use this crate.
 ///
@@ -35,40 +77,90 @@ use crate::util::RwLockCell;
 /// ```
 pub struct Session {
 	/// A display that has been opened.
-	pub display: Rc<Display>,
+	pub display: Arc<Display>,
 	/// The root window of the display.
 	root_window: RwLock<Option<Window>>,
 	/// The atom that represents the client_list property.
 	client_list_atom: RwLock<Option<Atom>>,
 	/// The atom that represents the active_window property.
 	pub active_window_atom: RwLock<Option<Atom>>,
+	/// The callbacks of the registered hotkeys.
+	hotkeys: Arc<Mutex<Registry>>,
+	/// Makes the requests of the window lists, see [Self::set_backend].
+	backend: Box<dyn Backend>,
 }
 
 impl Session {
 	/// Opens a display.
 	pub fn open() -> Option<Self> {
-		Some(Self {
-			display: Rc::new(Display::open()?),
-			root_window: RwLock::new(None),
-			client_list_atom: RwLock::new(None),
-			active_window_atom: RwLock::new(None),
-		})
+		Some(Self::from_display(Display::open()?))
 	}
 	/// Creates a session from an already opened Display connection.
 	///
 	/// See [Display::open] for more information.
 	pub fn from_display(display: Display) -> Self {
+		let display = display.shared();
+		let backend = Box::new(DefaultBackend::new(Arc::clone(&display)));
+		Self::new(display, backend)
+	}
+
+	fn new(display: Arc<Display>, backend: Box<dyn Backend>) -> Self {
 		Self {
-			display: display.shared(),
+			backend,
+			display,
 			root_window: RwLock::new(None),
 			client_list_atom: RwLock::new(None),
 			active_window_atom: RwLock::new(None),
+			hotkeys: Arc::default(),
 		}
 	}
 
+	/// Creates a session without an X server, whose window lists come from `backend`.
+	///
+	/// This is meant for unit tests with a [crate::backend::MockBackend]: [Self::backend], [Self::root],
+	/// [Self::get_windows], [Self::get_window_by_name] and [Self::find_window] work like on a live display,
+	/// the windows they return can be compared and handed to [Backend] methods through their id.
+	/// Nothing else may be called on the session or its windows, as there is no connection to make the requests on.
+	///
+	/// # Example
+	/// ```ignore
+	/// let mock = MockBackend::new();
+	/// let editor = mock.add_window("notes.txt - Editor", Rect::new(0, 0, 800, 600));
+	/// let session = Session::with_backend(mock);
+	/// assert_eq!(session.get_window_by_name("notes.txt - Editor").map(|it| it.window), Some(editor));
+	/// ```
+	#[cfg(any(test, feature = "testing"))]
+	pub fn with_backend(backend: impl Backend + 'static) -> Self {
+		// The default backend would make its requests on the null pointer of the display.
+		Self::new(Display::disconnected().shared(), Box::new(backend))
+	}
+
+	/// A handle that aborts the blocking operations of this session from another thread, see [CancelToken].
+	pub fn cancel_token(&self) -> CancelToken {
+		self.display.cancel_token().clone()
+	}
+
+	/// The backend of the window lists, the one picked with the `backend-xcb` feature on the connection of this session
+	/// unless replaced with [Self::set_backend].
+	///
+	/// Use it to read the properties of many windows in one round trip with [Backend::get_properties].
+	pub fn backend(&self) -> &dyn Backend {
+		&*self.backend
+	}
+
+	/// Replaces the backend that [Self::get_windows], [Self::get_window_by_name] and [Self::find_window] make their requests with.
+	///
+	/// With a [crate::backend::MockBackend] the window lists come from the mock, the windows
+	/// still use the connection of the session for everything else, see [Backend#scope].
+	pub fn set_backend(&mut self, backend: impl Backend + 'static) {
+		self.backend = Box::new(backend);
+	}
+
 	/// Get root windows of this session
+	///
+	/// The root window is the one of the [Self::backend].
 	pub fn root(&self) -> &Window {
-		self.root_window.get_or_insert_with(|| Window::default_root_window(Rc::clone(&self.display)))
+		self.root_window.get_or_insert_with(|| Window { window: self.backend.root(), display: Arc::clone(&self.display) })
 	}
 
 	/// Get client list window atom of this session
@@ -85,64 +177,11 @@ impl Session {
 	///
 	/// This will update any values that are set to [None] if it needs to use them.
 	///
-	/// This can possible produce a [NotSupported] error.
-	/// In that case, please read the documentation for that struct.
-	pub fn get_windows(&self) -> Result<Windows, NotSupported> {
-		let Session { display, .. } = self;
-		let root = self.root();
-		let atom = self.client_list();
-
-		let GetWindowPropertyResponse {
-			actual_type_return: return_type,
-			actual_format_return: return_format,
-			nitems_return: return_nitems,
-			proper_return: return_proper,
-			..
-		} = unsafe { get_window_property(display, root.clone(), *atom, XA_WINDOW)? };
-		if return_type == XA_WINDOW {
-			let windows = match return_format {
-				8 => {
-					let array = unsafe { slice::from_raw_parts(return_proper as *mut u8, return_nitems as usize) }
-						.iter()
-						.map(|x| Window {
-							window: *x as XWindow,
-							display: Rc::clone(display),
-						})
-						.collect();
-					unsafe { XFree(return_proper as *mut c_void) };
-					Windows(array)
-				}
-				16 => {
-					let array = unsafe { slice::from_raw_parts(return_proper as *mut u16, return_nitems as usize) }
-						.iter()
-						.map(|x| Window {
-							window: *x as XWindow,
-							display: Rc::clone(display),
-						})
-						.collect();
-					unsafe { XFree(return_proper as *mut c_void) };
-					Windows(array)
-				}
-				32 => {
-					let array = unsafe { slice::from_raw_parts(return_proper as *mut usize, return_nitems as usize) }
-						.iter()
-						.map(|x| Window {
-							window: *x as XWindow,
-							display: Rc::clone(display),
-						})
-						.collect();
-					unsafe { XFree(return_proper as *mut c_void) };
-					Windows(array)
-				}
-				_ => {
-					unsafe { XFree(return_proper as *mut c_void) };
-					return Err(NotSupported);
-				}
-			};
-			return Ok(windows);
-		} else { unsafe { XFree(return_proper as *mut c_void) }; }
-
-		Err(NotSupported)
+	/// Returns [Error::AtomMissing] or [Error::PropertyMissing] if the window manager
+	/// doesn't keep a `_NET_CLIENT_LIST`, see [Error].
+	pub fn get_windows(&self) -> Result<Windows, Error> {
+		let windows = self.backend.client_list()?;
+		Ok(Windows(windows.into_iter().map(|window| Window { window, display: Arc::clone(&self.display) }).collect()))
 	}
 
 	/// Get windows where name match provided argument
@@ -155,82 +194,602 @@ impl Session {
 	/// + limit = 0 for unlimited
 	///
 	/// return [Vec\<Window>] on success or empty [Vec] if not found or error
-	pub fn find_window<F>(&self, filter: F, mut limit: usize) -> Vec<Window>
+	pub fn find_window<F>(&self, filter: F, limit: usize) -> Vec<Window>
 		where F: for<'a> Fn(&'a [u8]) -> bool {
-		let Session { display, .. } = self;
+		self.backend
+			.find_windows(&filter, limit)
+			.unwrap_or_default()
+			.into_iter()
+			.map(|window| Window { window, display: Arc::clone(&self.display) })
+			.collect()
+	}
+	/// Gets the mapped windows that bypass the window manager, like menus, tooltips and notification popups.
+	///
+	/// These override-redirect windows are children of the root window that never show up in
+	/// `_NET_CLIENT_LIST`. They are returned from the bottom of the stack to the top,
+	/// so the last one is the popup that is shown above everything else.
+	pub fn get_unmanaged_windows(&self) -> Vec<Window> {
+		self.root()
+			.children()
+			.unwrap_or_default()
+			.into_iter()
+			.filter(|it| {
+				let attr = it.get_attr();
+				attr.override_redirect != 0 && attr.map_state == IsViewable
+			})
+			.collect()
+	}
+
+	/// Gets the currently active window in the display.
+	pub fn active_window(&mut self) -> Result<Window, Error> {
+		Window::active_window(self)
+	}
+
+	/// Captures all the windows at the same time, returning the frames by the ID of their window.
+	///
+	/// Threads that share a connection take turns on its lock, so every thread opens a new connection to the
+	/// display from the `DISPLAY` environment variable, see [CaptureOptions::threads].
+	pub fn capture_all(&self, windows: &[Window], options: CaptureOptions) -> HashMap<XWindow, Result<Frame, CaptureError>> {
+		let ids = windows.iter().map(|it| it.window).collect::<Vec<_>>();
+		capture_parallel(&ids, options)
+	}
+
+	/// Whether a compositor runs, see [Display::compositor_active].
+	///
+	/// [Window::focus] and [Self::capture_windows_composited] already take it into account,
+	/// check it for fallbacks of your own, e.g. to capture from the screen when there is none.
+	pub fn compositor_active(&self) -> bool {
+		self.display.compositor_active()
+	}
+
+	/// Captures the windows into a single image, at their positions on the screen and in their stacking order.
+	///
+	/// Useful for an application together with its floating tool palettes. The image covers all the windows,
+	/// [crate::capture::PixelFormat::Bgra] pixels that no window covers are transparent. Windows that are not viewable are left out.
+	///
+	/// Without a compositor the parts of a window that other windows cover are not kept,
+	/// so then every window is taken from what the screen shows at its area, including other windows in front of it.
+	pub fn capture_windows_composited(&self, windows: &[Window]) -> Result<Frame, CaptureError> {
+		capture_composited(self.root(), windows)
+	}
+
+	/// Gets the area of every monitor, relative to the root window.
+	///
+	/// With the `xrandr` feature the monitors are queried with [XRRGetMonitors],
+	/// otherwise, or if that fails, the whole root window is returned as a single monitor.
+	pub fn monitors(&self) -> Vec<Rect> {
+		#[cfg(feature = "xrandr")]
+		{
+			let mut count = 0;
+			let monitors = unsafe { XRRGetMonitors(self.display.as_raw(), self.root().window, 1, &mut count) };
+			if !monitors.is_null() {
+				let rects = unsafe { slice::from_raw_parts(monitors, count as usize) }
+					.iter()
+					.map(|it| Rect::new(it.x, it.y, it.width as u32, it.height as u32))
+					.collect::<Vec<_>>();
+				unsafe { XRRFreeMonitors(monitors) };
+				if !rects.is_empty() {
+					return rects;
+				}
+			}
+		}
+		let attr = self.root().get_attr();
+		vec![Rect::new(0, 0, attr.width as u32, attr.height as u32)]
+	}
+
+	/// Focuses `window`, runs `automation` and gives the focus and the pointer back where the user had them.
+	///
+	/// The window that was active is activated again, so it is raised back above `window`,
+	/// and the pointer is warped back to where it was. This also happens when `automation` panics.
+	/// # Example
+	/// ```ignore
+	/// let saved = session.with_focus(&editor, || {
+	///     editor.send_combo(&KeyCombo::parse("Ctrl+S")?);
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn with_focus<R>(&self, window: &Window, automation: impl FnOnce() -> R) -> R {
+		let (mut focus, mut revert) = (0, 0);
+		unsafe { XGetInputFocus(self.display.as_raw(), &mut focus, &mut revert) };
+		let _restore = FocusRestore {
+			session: self,
+			active: Window::active_window(self).ok(),
+			focus,
+			pointer: self.root().pointer_position().ok().flatten(),
+		};
+		// A window that is gone can't take the focus, the automation then runs without it.
+		let _ = window.focus();
+		unsafe { XFlush(self.display.as_raw()) };
+		automation()
+	}
+
+	/// Fakes a key press or release with XTest, as if it came from the keyboard, whatever the [Display::input_backend] is.
+	///
+	/// The key goes to the window with the input focus. Keysyms that are not on the current layout are not sent.
+	/// Returns [Error::ExtensionMissing] if the server lacks XTest.
+	#[cfg(feature = "xtest")]
+	pub fn fake_key(&self, typ: KeyType, keysym: c_uint, modifiers: c_uint) -> Result<(), Error> {
+		let (code, modifiers) = self.root().resolve_keysym(keysym, modifiers);
+		xtest::fake(&self.display, || {
+			if code != 0 {
+				xtest::fake_key(&self.display, code, matches!(typ, KeyType::Press), modifiers);
+			}
+		})
+	}
+
+	/// Fakes a button press or release with XTest at `x` and `y` on the screen, moving the pointer there first.
+	///
+	/// The button goes to the window under the pointer, as for the real mouse.
+	/// Returns [Error::ExtensionMissing] if the server lacks XTest.
+	#[cfg(feature = "xtest")]
+	pub fn fake_button(&self, typ: ButtonType, button: c_uint, x: i32, y: i32, modifiers: c_uint) -> Result<(), Error> {
+		xtest::fake(&self.display, || {
+			xtest::fake_motion(&self.display, x, y);
+			xtest::fake_button(&self.display, button, matches!(typ, ButtonType::Press), modifiers);
+		})
+	}
+
+	/// Gets how long the server has not seen any input from the keyboard or mouse.
+	///
+	/// Input faked with XTest counts as input here, events sent with XSendEvent don't.
+	/// Returns [Error::ExtensionMissing] if the server lacks the MIT-SCREEN-SAVER extension.
+	#[cfg(feature = "idle")]
+	pub fn idle_time(&self) -> Result<Duration, Error> {
+		scheduler::idle_time(&self.display)
+	}
+
+	/// Creates a scheduler that runs tasks only after the user was idle for `threshold`,
+	/// and pauses them as soon as the user touches the keyboard or mouse again, see [IdleScheduler].
+	#[cfg(feature = "idle")]
+	pub fn idle_scheduler(&self, threshold: Duration) -> IdleScheduler<'_> {
+		IdleScheduler::new(self, threshold)
+	}
+
+	/// Moves the pointer to `x` and `y` on the screen.
+	///
+	/// With the XTest backend this fakes motion, like the real mouse, otherwise the pointer is warped.
+	/// Either way the windows under the pointer get the crossing and motion events of the server,
+	/// which some applications need before they take a synthesized click, see [Window::send_motion]
+	/// to send only a motion event to one window instead.
+	pub fn move_pointer(&self, x: i32, y: i32) {
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			xtest::fake_motion(&self.display, x, y);
+			return unsafe { XFlush(self.display.as_raw()); };
+		}
+		unsafe {
+			XWarpPointer(self.display.as_raw(), 0, self.root().window, 0, 0, 0, 0, x, y);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Moves the pointer by `dx` and `dy` pixels from where it currently is.
+	///
+	/// With the XTest backend this fakes relative motion, which is also seen by applications
+	/// that read pointer deltas, like games with mouse look. Otherwise the pointer is warped.
+	pub fn move_pointer_relative(&self, dx: i32, dy: i32) {
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			xtest::fake_relative_motion(&self.display, dx, dy);
+			return unsafe { XFlush(self.display.as_raw()); };
+		}
+		unsafe {
+			XWarpPointer(self.display.as_raw(), 0, 0, 0, 0, 0, 0, dx, dy);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Reads the whole window hierarchy, from the root window down, see [WindowTree].
+	///
+	/// Only fails if the root window can't be read, windows that are destroyed meanwhile are left out.
+	/// # Example
+	/// ```ignore
+	/// let tree = session.dump_tree()?;
+	/// println!("{}", tree);
+	/// assert!(tree.iter().any(|it| it.class.as_deref() == Some("firefox") && it.map_state == MapState::Viewable));
+	/// ```
+	pub fn dump_tree(&self) -> Result<WindowTree, Error> {
 		let root = self.root();
-		let atom = self.client_list();
-		if limit == 0 {
-			limit = usize::MAX;
+		WindowTree::of(root).ok_or(Error::BadWindow(root.window))
+	}
+
+	/// Gets which modifiers and locks are currently active on the keyboard.
+	///
+	/// Synthesized keys are combined with these by applications, e.g. with Caps Lock on
+	/// a plain `a` types `A`, so check this before typing and compensate if needed.
+	pub fn modifier_state(&self) -> ModifierState {
+		ModifierState::query(&self.display)
+	}
+
+	/// Gets which keys are held down on the keyboard right now, see [KeyboardState].
+	/// # Example
+	/// ```ignore
+	/// use x11::keysym::XK_Control_L;
+	/// if session.query_keymap().is_pressed(XK_Control_L as KeySym) {
+	///     println!("Ctrl is held.");
+	/// }
+	/// ```
+	pub fn query_keymap(&self) -> KeyboardState {
+		KeyboardState::query(&self.display)
+	}
+
+	/// Waits until no key is held down anymore, e.g. the hotkey that started a script, before sending keys.
+	///
+	/// Returns `false` if keys were still held after `timeout`, or the [CancelToken] of the session was cancelled.
+	pub fn wait_keys_released(&self, timeout: Duration) -> bool {
+		let deadline = Instant::now() + timeout;
+		while self.query_keymap().any_pressed() {
+			if Instant::now() >= deadline || self.cancel_token().sleep(KEY_RELEASE_POLL_INTERVAL) {
+				return false;
+			}
 		}
-		let GetWindowPropertyResponse {
-			actual_type_return: return_type,
-			actual_format_return: return_format,
-			nitems_return: return_nitems,
-			proper_return: return_proper,
-			..
-		} = unsafe {
-			let prop = get_window_property(display, root.clone(), *atom, XA_WINDOW);
-			if let Ok(it) = prop {
-				it
+		true
+	}
+
+	/// Grabs the keyboard, so that every key event goes to this client until the guard is dropped.
+	///
+	/// The events are reported to the root window, read them from the connection of this session.
+	/// Returns a [GrabError] if another client has the keyboard grabbed already.
+	pub fn grab_keyboard(&self) -> Result<KeyboardGrab, GrabError> {
+		KeyboardGrab::new(Arc::clone(&self.display), self.root().window)
+	}
+
+	/// Grabs the pointer, so that every pointer event goes to this client until the guard is dropped.
+	///
+	/// This is what selection rectangles of screenshot tools need, e.g. with
+	/// [crate::grab::CURSOR_CROSSHAIR] as the cursor and confined to the root window.
+	/// For "click on a window to select it" like `xwininfo`, [Self::pick_window] does all of it.
+	/// Returns a [GrabError] if another client has the pointer grabbed already.
+	pub fn grab_pointer(&self, options: PointerGrabOptions) -> Result<PointerGrab, GrabError> {
+		PointerGrab::new(Arc::clone(&self.display), self.root().window, options)
+	}
+
+	/// Keeps the pointer from crossing `line`, except in the `allowed` directions, until the returned barrier is dropped.
+	///
+	/// This needs XFixes 5, returns [Error::ExtensionMissing] if the server lacks it.
+	/// Several barriers can fence in the pointer, e.g. to keep it on one monitor while a kiosk runs.
+	/// # Example
+	/// ```ignore
+	/// use x11_get_windows::grab::{BarrierDirections, BarrierLine};
+	/// let monitor = session.monitors()[0];
+	/// // The pointer can't leave the first monitor to the right.
+	/// let right = monitor.x + monitor.width as i32;
+	/// let line = BarrierLine::Vertical { x: right, y1: monitor.y, y2: monitor.y + monitor.height as i32 };
+	/// let barrier = session.create_pointer_barrier(line, BarrierDirections::NONE)?;
+	/// ```
+	#[cfg(feature = "xfixes")]
+	pub fn create_pointer_barrier(&self, line: BarrierLine, allowed: BarrierDirections) -> Result<PointerBarrier, Error> {
+		PointerBarrier::new(Arc::clone(&self.display), self.root().window, line, allowed)
+	}
+
+	/// Releases a pointer grab of this connection right away, also one made without a [PointerGrab].
+	///
+	/// Dropping the [PointerGrab] afterwards does nothing more than freeing its cursor.
+	pub fn ungrab_pointer(&self) {
+		unsafe {
+			XUngrabPointer(self.display.as_raw(), CurrentTime);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Lets the user drag a rectangle on the screen with the left button, the way screenshot tools do.
+	///
+	/// The pointer is grabbed and the rectangle is outlined while dragging.
+	/// Returns [None] when the user cancels with Escape or the right button, or clicks without dragging.
+	pub fn pick_region(&self) -> Result<Option<Rect>, GrabError> {
+		picker::pick_region(self)
+	}
+
+	/// Lets the user click a window, the window under the pointer is outlined until then.
+	///
+	/// Only windows in the list of [Self::get_windows] can be picked, clicks on the desktop are ignored.
+	/// Returns [None] when the user cancels with Escape or the right button.
+	pub fn pick_window(&self) -> Result<Option<Window>, GrabError> {
+		picker::pick_window(self)
+	}
+
+	/// Registers a global hotkey like `Super+Shift+S`, see [KeyCombo::parse] for the syntax.
+	///
+	/// The hotkey works whatever window has the focus, and whether Caps Lock or Num Lock are on.
+	/// The callback is run by [Self::dispatch_hotkeys], the hotkey is unregistered when the
+	/// returned guard is dropped.
+	/// # Example
+	/// ```ignore
+	/// let _hotkey = session.register_hotkey("Super+Shift+S", || println!("Screenshot!"))
+	///     .expect("Could not register the hotkey.");
+	/// loop {
+	///     session.dispatch_hotkeys();
+	///     thread::sleep(Duration::from_millis(10));
+	/// }
+	/// ```
+	pub fn register_hotkey(&self, combo: &str, callback: impl FnMut() + Send + 'static) -> Result<Hotkey, HotkeyError> {
+		let combo = KeyCombo::parse(combo)?;
+		Hotkey::register(Arc::clone(&self.display), self.root().window, &self.hotkeys, &combo, Box::new(callback))
+	}
+
+	/// Runs the callbacks of the hotkeys that were pressed, returns how many were run.
+	///
+	/// This reads every pending event of the connection, the events that are not hotkeys are put back
+	/// on the queue in their order, so an [EventLoop] or other readers still get them afterwards.
+	pub fn dispatch_hotkeys(&self) -> usize {
+		let mut count = 0;
+		let mut others = Vec::new();
+		while unsafe { XPending(self.display.as_raw()) } > 0 {
+			let mut event = MaybeUninit::<XEvent>::uninit();
+			let event = unsafe {
+				XNextEvent(self.display.as_raw(), event.as_mut_ptr());
+				event.assume_init()
+			};
+			if event.get_type() == KeyPress && self.run_hotkey(unsafe { &event.key }) {
+				count += 1;
 			} else {
-				return Vec::new();
+				others.push(event);
 			}
-		};
-		if return_type == XA_WINDOW {
-			return match return_format {
-				8 => {
-					let res = unsafe { slice::from_raw_parts(return_proper as *mut u8, return_nitems as usize) }
-						.iter()
-						.map(|x| Window {
-							window: *x as XWindow,
-							display: Rc::clone(display),
-						})
-						.filter(|it| filter(it.get_title().unwrap().as_ref().to_bytes()))
-						.take(limit)
-						.collect::<Vec<_>>();
-					unsafe { XFree(return_proper as *mut c_void) };
-					res
-				}
-				16 => {
-					let res = unsafe { slice::from_raw_parts(return_proper as *mut u16, return_nitems as usize) }
-						.iter()
-						.map(|x| Window {
-							window: *x as XWindow,
-							display: Rc::clone(display),
-						})
-						.filter(|it| filter(it.get_title().unwrap().as_ref().to_bytes()))
-						.take(limit)
-						.collect::<Vec<_>>();
-					unsafe { XFree(return_proper as *mut c_void) };
-					res
-				}
-				32 => {
-					let res = unsafe { slice::from_raw_parts(return_proper as *mut usize, return_nitems as usize) }
-						.iter()
-						.map(|x| Window {
-							window: *x as XWindow,
-							display: Rc::clone(display),
-						})
-						.filter(|it| filter(it.get_title().unwrap().as_ref().to_bytes()))
-						.take(limit)
-						.collect::<Vec<_>>();
-					unsafe { XFree(return_proper as *mut c_void) };
-					res
-				}
-				_ => {
-					unsafe { XFree(return_proper as *mut c_void) };
-					Vec::new()
-				}
-			};
-		} else {
-			unsafe { XFree(return_proper as *mut c_void) };
 		}
+		// Put back events go to the front of the queue, the last one first keeps the order.
+		for event in others.iter_mut().rev() {
+			unsafe { XPutBackEvent(self.display.as_raw(), event) };
+		}
+		count
+	}
 
-		Vec::new()
+	/// Runs the callback of the hotkey for the key event, returns whether there is one.
+	pub(crate) fn run_hotkey(&self, key: &XKeyEvent) -> bool {
+		let locks = LockMask | num_lock_mask(&self.display);
+		// The state also has the buttons held down, only the modifiers belong to the combo.
+		let found = self.hotkeys.lock().unwrap_or_else(|it| it.into_inner()).take(key.keycode as KeyCode, key.state & 0xff & !locks);
+		match found {
+			Some((id, mut callback)) => {
+				callback();
+				self.hotkeys.lock().unwrap_or_else(|it| it.into_inner()).put_back(id, callback);
+				true
+			}
+			None => false,
+		}
 	}
-	/// Gets the currently active window in the display.
-	pub fn active_window(&mut self) -> Result<Window, NotSupported> {
-		Window::active_window(self)
+
+	/// Gets a loop that receives the events of the windows it selects.
+	pub fn event_loop(&self) -> EventLoop<'_> {
+		EventLoop::new(self)
+	}
+
+	/// Iterates over the events of the windows selected with [EventLoop::select], waiting for each.
+	///
+	/// ```ignore
+	/// use x11::xlib::KeyPressMask;
+	/// session.event_loop().select(&window, KeyPressMask);
+	/// for event in session.events() {
+	///     if let Event::KeyPress { character: Some(c), .. } = event {
+	///         print!("{}", c);
+	///     }
+	/// }
+	/// ```
+	pub fn events(&self) -> Events<'_> {
+		EventLoop::new(self).iter()
+	}
+
+	/// Watches for windows that are created and destroyed, instead of polling [Self::get_windows].
+	///
+	/// This selects [x11::xlib::SubstructureNotifyMask] and [x11::xlib::PropertyChangeMask] on the root window,
+	/// see [WindowWatcher] for which windows are reported.
+	/// ```ignore
+	/// for change in session.watch_windows() {
+	///     match change {
+	///         WindowChange::Created(window) => taskbar.add(window),
+	///         WindowChange::Destroyed(id) => taskbar.remove(id),
+	///     }
+	/// }
+	/// ```
+	pub fn watch_windows(&self) -> WindowWatcher<'_> {
+		WindowWatcher::new(self)
+	}
+
+	/// Like [Self::watch_windows], only for the windows of one application, by class or process ID.
+	/// ```ignore
+	/// for change in session.watch_app_windows("firefox") {
+	///     if let WindowChange::Destroyed(_) = change {
+	///         println!("A Firefox window was closed.");
+	///     }
+	/// }
+	/// let editor = session.watch_app_windows(child.id());
+	/// ```
+	pub fn watch_app_windows(&self, app: impl Into<App>) -> AppWindowWatcher<'_> {
+		AppWindowWatcher::new(self, app.into())
+	}
+
+	/// Yields the new active window every time the focus changes, instead of polling [Self::active_window].
+	///
+	/// This selects [x11::xlib::PropertyChangeMask] on the root window, see [ActiveWindowWatcher].
+	/// ```ignore
+	/// for window in session.watch_active_window() {
+	///     println!("Focused {:?}", window.title());
+	/// }
+	/// ```
+	pub fn watch_active_window(&self) -> ActiveWindowWatcher<'_> {
+		ActiveWindowWatcher::new(self)
+	}
+
+	/// Yields the windows that are raised or lowered, with the windows right above and below them now.
+	///
+	/// This selects [x11::xlib::PropertyChangeMask] on the root window and follows `_NET_CLIENT_LIST_STACKING`,
+	/// see [StackingWatcher].
+	/// ```ignore
+	/// for restack in session.watch_stacking() {
+	///     if restack.below == Some(tracked.window) {
+	///         overlay.show();
+	///     }
+	/// }
+	/// ```
+	pub fn watch_stacking(&self) -> StackingWatcher<'_> {
+		StackingWatcher::new(self)
+	}
+
+	/// Like [Self::events], as a [futures_core::Stream] that doesn't block the thread while waiting.
+	///
+	/// See [EventStream] for how it waits. This has to be called on a tokio runtime,
+	/// it fails if the connection can't be registered with it.
+	#[cfg(feature = "async")]
+	pub fn event_stream(&self) -> io::Result<EventStream<'_>> {
+		EventStream::new(EventLoop::new(self))
+	}
+
+	/// Gets the logical button of every physical button of the pointer, the first is for button 1.
+	///
+	/// Left-handed configurations usually swap 1 and 3, so the physical right button is the primary one.
+	pub fn pointer_mapping(&self) -> Vec<u8> {
+		btn_event::pointer_mapping(&self.display)
+	}
+
+	/// Gets the physical button that acts as the primary, usually left, button.
+	///
+	/// Buttons faked with [Self::fake_button] are physical buttons that the server maps,
+	/// so click this one to get a primary click on a left-handed mouse.
+	/// [Window::send_btn] takes logical buttons and maps them for the XTest backend itself.
+	pub fn primary_button(&self) -> c_uint {
+		MouseButton::Left.physical(&self.display)
+	}
+
+	/// Saves the class, title, geometry, desktop and state of every window to a file.
+	///
+	/// Returns how many windows were saved, see [layouts::WindowLayout] for the format of the lines.
+	pub fn save_layout(&self, path: impl AsRef<Path>) -> io::Result<usize> {
+		layouts::save(self, path.as_ref())
+	}
+
+	/// Moves the windows back to the geometry and desktop saved with [Self::save_layout].
+	///
+	/// Saved windows are matched to open ones by their class and title, or by the class alone
+	/// if the title changed. Returns how many windows were restored.
+	pub fn restore_layout(&self, path: impl AsRef<Path>) -> io::Result<usize> {
+		layouts::restore(self, path.as_ref())
+	}
+
+	/// Starts the command and waits until the process shows a window that `matcher` accepts.
+	///
+	/// Windows are taken as the ones of the process if their `_NET_WM_PID` is the process or
+	/// one of its children, or if their `_NET_STARTUP_ID` is the `DESKTOP_STARTUP_ID` the command
+	/// is started with. Windows that have neither are taken if they were not there before.
+	///
+	/// # Example
+	/// ```ignore
+	/// let (child, window) = session
+	///     .spawn_and_wait_window(Command::new("xterm"), |_| true, Duration::from_secs(5))
+	///     .expect("xterm did not start.");
+	/// ```
+	pub fn spawn_and_wait_window(
+		&self,
+		command: Command,
+		matcher: impl Fn(&Window) -> bool,
+		timeout: Duration,
+	) -> Result<(Child, Window), SpawnError> {
+		spawn::spawn_and_wait_window(self, command, matcher, timeout)
+	}
+
+	/// Gets the keyboard layouts the server switches between, in the order of their XKB groups.
+	///
+	/// Returns an empty list if the server does not support XKB.
+	pub fn keyboard_layouts(&self) -> Vec<KeyboardLayout> {
+		keyboard_layouts(&self.display)
+	}
+
+	/// Gets the layout that is currently active, e.g. to check that typed text comes out right.
+	pub fn active_layout(&self) -> Option<KeyboardLayout> {
+		let group = active_group(&self.display)?;
+		let layout = self.keyboard_layouts().into_iter().nth(group);
+		Some(layout.unwrap_or(KeyboardLayout { index: group, name: String::new(), description: String::new() }))
+	}
+
+	/// Asks the server to switch to the layout with the [KeyboardLayout::index], returns false if the request could not be sent.
+	///
+	/// A [crate::keys::Keymap] loaded before this still has the keys of the old layout.
+	pub fn set_active_layout(&self, index: usize) -> bool {
+		lock_group(&self.display, index)
+	}
+}
+
+/// Gives the focus and the pointer back when dropped, see [Session::with_focus].
+struct FocusRestore<'a> {
+	session: &'a Session,
+	active: Option<Window>,
+	focus: XWindow,
+	pointer: Option<(i32, i32)>,
+}
+
+impl Drop for FocusRestore<'_> {
+	fn drop(&mut self) {
+		let display = &self.session.display;
+		if let Some(active) = &self.active {
+			let _ = active.focus();
+		}
+		// The focus can be on a child of the active window, which activating it doesn't bring back.
+		// No focus and PointerRoot are 0 and 1, neither is a window.
+		let child = self.active.as_ref().is_none_or(|it| it.window != self.focus);
+		if child && self.focus > PointerRoot as XWindow {
+			display.with_error_trap(|| unsafe { XSetInputFocus(display.as_raw(), self.focus, RevertToParent, CurrentTime) });
+		}
+		if let Some((x, y)) = self.pointer {
+			let _ = self.session.root().warp_pointer(x, y);
+		}
+		unsafe { XFlush(display.as_raw()) };
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{backend::{MockBackend, Property}, XImg};
+
+	#[test]
+	fn get_windows_lists_the_clients_of_the_backend() {
+		let mock = MockBackend::new();
+		let editor = mock.add_window("Editor", Rect::new(0, 0, 800, 600));
+		let terminal = mock.add_window("Terminal", Rect::new(800, 0, 400, 600));
+		let session = Session::with_backend(mock);
+
+		let windows = session.get_windows().unwrap();
+		assert_eq!(windows.0.iter().map(|it| it.window).collect::<Vec<_>>(), vec![editor, terminal]);
+	}
+
+	#[test]
+	fn root_is_the_one_of_the_backend() {
+		let mock = MockBackend::new();
+		let root = mock.root();
+		let session = Session::with_backend(mock);
+
+		assert_eq!(session.root().window, root);
+	}
+
+	#[test]
+	fn get_windows_fails_without_a_client_list() {
+		let mock = MockBackend::new();
+		mock.set_property(mock.root(), NET_CLIENT_LIST, Property::default());
+		let session = Session::with_backend(mock);
+
+		assert_eq!(session.get_windows().err(), Some(Error::PropertyMissing(NET_CLIENT_LIST)));
+	}
+
+	#[test]
+	fn find_window_filters_the_names_up_to_the_limit() {
+		let mock = MockBackend::new();
+		let first = mock.add_window("notes.txt - Editor", Rect::default());
+		let terminal = mock.add_window("Terminal", Rect::default());
+		let second = mock.add_window("todo.txt - Editor", Rect::default());
+		let session = Session::with_backend(mock);
+		let ids = |windows: Vec<Window>| windows.into_iter().map(|it| it.window).collect::<Vec<_>>();
+
+		assert_eq!(ids(session.find_window(|name| name.ends_with(b"- Editor"), 0)), vec![first, second]);
+		assert_eq!(ids(session.find_window(|name| name.ends_with(b"- Editor"), 1)), vec![first]);
+		assert_eq!(session.get_window_by_name("Terminal").map(|it| it.window), Some(terminal));
+		assert!(session.get_window_by_name("Browser").is_none());
+	}
+
+	#[test]
+	fn sessions_windows_and_images_can_go_to_other_threads() {
+		fn shareable<T: Send + Sync>() {}
+		fn sendable<T: Send>() {}
+		shareable::<Display>();
+		shareable::<Session>();
+		shareable::<Window>();
+		sendable::<XImg>();
 	}
 }
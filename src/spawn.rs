@@ -0,0 +1,108 @@
+use std::{
+	collections::HashSet,
+	fmt,
+	fs,
+	io,
+	process::{Child, Command},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use x11::xlib::Window as XWindow;
+
+use crate::{Session, Window};
+
+/// How often the list of windows is checked for the new one.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The error returned by [Session::spawn_and_wait_window].
+#[derive(Debug)]
+pub enum SpawnError {
+	/// The command could not be started.
+	Io(io::Error),
+	/// The process didn't create a matching window in time, it is still running.
+	Timeout(Child),
+	/// The [crate::CancelToken] of the session was cancelled while waiting, the process is still running.
+	Cancelled(Child),
+}
+
+impl fmt::Display for SpawnError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SpawnError::Io(err) => write!(f, "could not start the command: {}", err),
+			SpawnError::Timeout(child) => write!(f, "process {} did not create a window in time", child.id()),
+			SpawnError::Cancelled(child) => write!(f, "stopped waiting for a window of process {}", child.id()),
+		}
+	}
+}
+
+impl From<io::Error> for SpawnError {
+	fn from(err: io::Error) -> Self {
+		SpawnError::Io(err)
+	}
+}
+
+pub(crate) fn spawn_and_wait_window(
+	session: &Session,
+	mut command: Command,
+	matcher: impl Fn(&Window) -> bool,
+	timeout: Duration,
+) -> Result<(Child, Window), SpawnError> {
+	let before = window_ids(session);
+	let startup_id = startup_id();
+	let mut child = command.env("DESKTOP_STARTUP_ID", &startup_id).spawn()?;
+	let deadline = Instant::now() + timeout;
+	loop {
+		if let Ok(windows) = session.get_windows() {
+			let found = windows.inner().into_iter().find(|window| {
+				let ours = match (window.pid(), window.startup_id()) {
+					(_, Some(id)) if id == startup_id => true,
+					(Some(pid), _) => is_descendant(pid, child.id()),
+					// Windows that don't say where they come from are taken if they are new.
+					(None, _) => !before.contains(&window.window),
+				};
+				ours && matcher(window)
+			});
+			if let Some(window) = found {
+				return Ok((child, window));
+			}
+		}
+		// A launcher may exit after handing off to its window, so only stop at the deadline.
+		let _ = child.try_wait();
+		if Instant::now() >= deadline {
+			return Err(SpawnError::Timeout(child));
+		}
+		if session.display.cancel_token().sleep(POLL_INTERVAL) {
+			return Err(SpawnError::Cancelled(child));
+		}
+	}
+}
+
+fn window_ids(session: &Session) -> HashSet<XWindow> {
+	session.get_windows().map(|it| it.iter().map(|it| it.window).collect()).unwrap_or_default()
+}
+
+/// A startup notification ID, the timestamp at the end is what the window manager uses for focus stealing prevention.
+fn startup_id() -> String {
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	format!("x11_get_windows-{}-{}_TIME{}", std::process::id(), now.as_nanos(), now.as_millis() as u32)
+}
+
+/// Whether `pid` is `ancestor` or one of its children, read from `/proc`.
+fn is_descendant(mut pid: u32, ancestor: u32) -> bool {
+	for _ in 0..64 {
+		if pid == ancestor {
+			return true;
+		}
+		pid = match parent_pid(pid) {
+			Some(parent) if parent != 0 && parent != pid => parent,
+			_ => return false,
+		};
+	}
+	false
+}
+
+fn parent_pid(pid: u32) -> Option<u32> {
+	let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+	// The name in parentheses may contain spaces, the parent is the second field after it.
+	stat.rsplit_once(')')?.1.split_whitespace().nth(1)?.parse().ok()
+}
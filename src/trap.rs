@@ -0,0 +1,111 @@
+use std::{
+	cell::RefCell,
+	fmt,
+	os::raw::{c_int, c_uchar, c_ulong},
+	sync::{Once, OnceLock},
+};
+
+use x11::xlib::{
+	BadWindow, Display as XDisplay, False, XErrorEvent, XID, XLockDisplay, XNextRequest, XSetErrorHandler, XSync, XUnlockDisplay,
+};
+
+use crate::{Display, Error};
+
+type Handler = unsafe extern "C" fn(*mut XDisplay, *mut XErrorEvent) -> c_int;
+
+/// An error the X server answered a request with, caught by [Display::with_error_trap].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct XError {
+	/// What went wrong, e.g. [x11::xlib::BadWindow].
+	pub error_code: c_uchar,
+	/// The major opcode of the failed request.
+	pub request_code: c_uchar,
+	/// The minor opcode of the failed request, for requests of extensions.
+	pub minor_code: c_uchar,
+	/// The window, pixmap or other resource the request was about.
+	pub resource: XID,
+	/// The serial number of the failed request.
+	pub serial: c_ulong,
+}
+
+impl fmt::Display for XError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"X error {} on request {}.{} for resource {:#x}",
+			self.error_code, self.request_code, self.minor_code, self.resource,
+		)
+	}
+}
+
+impl std::error::Error for XError {}
+
+impl From<XError> for Error {
+	fn from(err: XError) -> Self {
+		if err.error_code == BadWindow {
+			Error::BadWindow(err.resource)
+		} else {
+			Error::RequestFailed(err.error_code as c_int)
+		}
+	}
+}
+
+thread_local! {
+	/// The traps that are active on this thread, the innermost last, with the display they are for
+	/// and the serial of their first request.
+	static TRAPS: RefCell<Vec<(usize, c_ulong, Vec<XError>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The handler that was installed before ours, errors outside of a trap go to it.
+static PREVIOUS: OnceLock<Option<Handler>> = OnceLock::new();
+static INSTALL: Once = Once::new();
+
+/// Xlib calls the handler on the thread that made the request, while it reads the reply.
+unsafe extern "C" fn handle(display: *mut XDisplay, event: *mut XErrorEvent) -> c_int {
+	let error = XError {
+		error_code: (*event).error_code,
+		request_code: (*event).request_code,
+		minor_code: (*event).minor_code,
+		resource: (*event).resourceid,
+		serial: (*event).serial,
+	};
+	let trapped = TRAPS.with(|traps| {
+		let mut traps = traps.borrow_mut();
+		// Errors of requests made before the trap can arrive while it is active, they are not its own.
+		match traps.iter_mut().rev().find(|(it, start, _)| *it == display as usize && error.serial >= *start) {
+			Some((_, _, errors)) => {
+				errors.push(error);
+				true
+			}
+			None => false,
+		}
+	});
+	if trapped {
+		return 0;
+	}
+	match PREVIOUS.get().copied().flatten() {
+		Some(previous) => previous(display, event),
+		None => 0,
+	}
+}
+
+/// Runs `requests`, waits for the server to answer them and returns the errors it answered with.
+///
+/// The handler of this crate is installed the first time, it passes the errors that happen
+/// outside of any trap on to the handler that was there before.
+pub(crate) fn trap<R>(display: &Display, requests: impl FnOnce() -> R) -> (R, Vec<XError>) {
+	INSTALL.call_once(|| {
+		let previous = unsafe { XSetErrorHandler(Some(handle)) };
+		let _ = PREVIOUS.set(previous);
+	});
+	// Other threads of the display wait until the trap is over, so no request of theirs gets between
+	// and the errors are read by the sync of this thread. The lock of Xlib can be taken again on the same thread.
+	unsafe { XLockDisplay(display.as_raw()) };
+	let start = unsafe { XNextRequest(display.as_raw()) };
+	TRAPS.with(|traps| traps.borrow_mut().push((display.as_raw() as usize, start, Vec::new())));
+	let res = requests();
+	unsafe { XSync(display.as_raw(), False) };
+	let errors = TRAPS.with(|traps| traps.borrow_mut().pop().map(|(_, _, errors)| errors).unwrap_or_default());
+	unsafe { XUnlockDisplay(display.as_raw()) };
+	(res, errors)
+}
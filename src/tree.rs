@@ -0,0 +1,115 @@
+use std::fmt;
+
+use x11::xlib::{IsUnviewable, IsViewable, Window as XWindow};
+
+use crate::{Rect, Window};
+
+/// Whether a window of a [WindowTree] is shown, from the `map_state` of its attributes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapState {
+	/// The window is not mapped.
+	Unmapped,
+	/// The window is mapped but an ancestor is not, so it is not shown.
+	Unviewable,
+	/// The window and all its ancestors are mapped.
+	Viewable,
+}
+
+impl MapState {
+	fn from_raw(state: i32) -> Self {
+		match state {
+			typ if typ == IsViewable => MapState::Viewable,
+			typ if typ == IsUnviewable => MapState::Unviewable,
+			_ => MapState::Unmapped,
+		}
+	}
+
+	/// The name [WindowTree] writes the state with.
+	pub fn name(&self) -> &'static str {
+		match self {
+			MapState::Unmapped => "unmapped",
+			MapState::Unviewable => "unviewable",
+			MapState::Viewable => "viewable",
+		}
+	}
+}
+
+/// A window and everything below it in the window hierarchy, see [crate::Session::dump_tree].
+///
+/// Unlike [crate::Windows] this has every window of the server, including the frames
+/// of the window manager, menus and windows that are not mapped.
+/// The tree is written one window per line, indented by its depth, which makes it easy to
+/// read while debugging and to compare against a saved snapshot in tests:
+/// ```text
+/// 0x4f7 - "" 1920x1080+0+0 viewable
+///   0x1800005 "firefox" "Mozilla Firefox" 1280x720+40+30 viewable
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowTree {
+	/// The ID of the window.
+	pub id: XWindow,
+	/// The class name in `WM_CLASS`, e.g. `firefox`.
+	pub class: Option<String>,
+	/// The title of the window, see [Window::title].
+	pub title: Option<String>,
+	/// The position relative to the parent, and the size without the border.
+	pub geometry: Rect,
+	/// Whether the window is shown.
+	pub map_state: MapState,
+	/// The children of the window, from the bottom of the stack to the top.
+	pub children: Vec<WindowTree>,
+}
+
+impl WindowTree {
+	/// Reads the window and all its descendants.
+	///
+	/// Windows that are destroyed while the tree is read are left out, [None] if it was this window.
+	pub fn of(window: &Window) -> Option<Self> {
+		let attr = window.try_get_attr().ok()?;
+		// The window may be destroyed at any point, its properties and children are empty then.
+		let ((class, title, children), _) =
+			window.display.with_error_trap(|| (window.class(), window.title(), window.children()));
+		Some(WindowTree {
+			id: window.window,
+			class: class.ok().flatten().map(|(_, class)| class),
+			title,
+			geometry: Rect::new(attr.x, attr.y, attr.width as u32, attr.height as u32),
+			map_state: MapState::from_raw(attr.map_state),
+			children: children.unwrap_or_default().iter().filter_map(WindowTree::of).collect(),
+		})
+	}
+
+	/// The window and all its descendants, depth first, every window before its children.
+	pub fn iter(&self) -> impl Iterator<Item = &WindowTree> {
+		let mut stack = vec![self];
+		std::iter::from_fn(move || {
+			let tree = stack.pop()?;
+			stack.extend(tree.children.iter().rev());
+			Some(tree)
+		})
+	}
+
+	/// Finds the window with this ID in the tree.
+	pub fn find(&self, id: XWindow) -> Option<&WindowTree> {
+		self.iter().find(|it| it.id == id)
+	}
+
+	fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+		let Rect { x, y, width, height } = self.geometry;
+		write!(f, "{:indent$}{:#x} ", "", self.id, indent = depth * 2)?;
+		match &self.class {
+			Some(class) => write!(f, "{:?} ", class)?,
+			None => f.write_str("- ")?,
+		}
+		let title = self.title.as_deref().unwrap_or_default();
+		writeln!(f, "{:?} {}x{}{:+}{:+} {}", title, width, height, x, y, self.map_state.name())?;
+		self.children.iter().try_for_each(|it| it.write(f, depth + 1))
+	}
+}
+
+/// Writes the tree with one window per line, see [WindowTree].
+impl fmt::Display for WindowTree {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.write(f, 0)
+	}
+}
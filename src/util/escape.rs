@@ -0,0 +1,22 @@
+/// Undoes [str::escape_default].
+pub(crate) fn unescape(text: &str) -> Result<String, ()> {
+	let mut res = String::with_capacity(text.len());
+	let mut chars = text.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			res.push(c);
+			continue;
+		}
+		res.push(match chars.next().ok_or(())? {
+			'n' => '\n',
+			'r' => '\r',
+			't' => '\t',
+			'u' => {
+				let code = chars.by_ref().skip(1).take_while(|it| *it != '}').collect::<String>();
+				u32::from_str_radix(&code, 16).ok().and_then(char::from_u32).ok_or(())?
+			}
+			c => c,
+		});
+	}
+	Ok(res)
+}
@@ -14,7 +14,7 @@ use std::{
 use crate::{
     Atom,
     Display,
-    NotSupported,
+    Error,
     Window,
 };
 
@@ -22,16 +22,19 @@ use crate::{
 /// Make sure to [x11::xlib::XFree] the pointer, when you're done with it.
 /// 
 /// An example of how to handle the response can be found in the [GetWindowPropertyResponse] docs.
+/// Returns [Error::BadWindow] if the window doesn't exist anymore.
+/// # Safety
+/// The returned pointer is owned by the caller and has to be freed exactly once with [x11::xlib::XFree].
 pub unsafe fn get_window_property(
     display: &Display,
     window: Window,
     property: Atom,
     expected_type: XAtom
-) -> Result<GetWindowPropertyResponse, NotSupported> {
+) -> Result<GetWindowPropertyResponse, Error> {
     let mut response = GetWindowPropertyResponse::default();
 
-    if XGetWindowProperty(
-        display.0,
+    let (status, errors) = display.with_error_trap(|| XGetWindowProperty(
+        display.as_raw(),
         window.window,
         property.0,
         0, 4096 / 4,
@@ -42,10 +45,14 @@ pub unsafe fn get_window_property(
         &mut response.nitems_return,
         &mut response.bytes_after_return,
         &mut response.proper_return
-    ) == 0 {
+    ));
+    if let Some(err) = errors.first() {
+        return Err((*err).into())
+    }
+    if status == 0 {
         return Ok(response)
     }
-    Err(NotSupported)
+    Err(Error::RequestFailed(status))
 }
 
 /// A response to [get_window_property].
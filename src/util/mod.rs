@@ -1,5 +1,7 @@
+mod escape;
 mod get_window_property;
 mod rwlock;
 
 pub use self::get_window_property::*;
 pub(crate) use rwlock::RwLockCell;
+pub(crate) use escape::unescape;
@@ -9,12 +9,12 @@ impl<T> RwLockCell<T> for RwLock<Option<T>> {
 	fn get_or_insert_with<F: FnOnce() -> T>(&self, f: F) -> &T {
 		let read = self.read().unwrap();
 		if let Some(val) = read.as_ref() {
-			unsafe { mem::transmute(val) }
+			unsafe { mem::transmute::<&T, &T>(val) }
 		} else {
 			drop(read);
 			let mut write = self.write().unwrap();
 			*write = Some(f());
-			unsafe { mem::transmute(write.as_ref().unwrap()) }
+			unsafe { mem::transmute::<&T, &T>(write.as_ref().unwrap()) }
 		}
 	}
 }
\ No newline at end of file
@@ -1,28 +1,65 @@
 use std::{
 	ffi::CStr,
 	ops::Drop,
-	os::raw::c_void,
+	os::raw::{c_char, c_void},
 	ptr::null_mut,
 	slice,
 };
 use std::borrow::BorrowMut;
-use std::ffi::{c_int, c_long, c_uint};
+use std::ffi::{c_int, c_long, c_uint, c_ulong};
 use std::ops::Deref;
-use std::rc::Rc;
+use std::sync::Arc;
 use std::slice::Windows;
+use std::thread;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem::MaybeUninit;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use x11::xlib::{CurrentTime, RevertToParent, True, Window as XWindow, XA_WINDOW, XAllPlanes, XButtonEvent, XDefaultRootWindow, XEvent, XFree, XGetImage, XGetWindowAttributes, XGetWMName, XImage, XKeyEvent, XKeysymToKeycode, XSendEvent, XSetInputFocus, XTextProperty, XWindowAttributes};
+use x11::xlib::{AnyPropertyType, IsViewable, ConfigureNotify, MapNotify, PropertyChangeMask, PropertyNotify, StructureNotifyMask, UnmapNotify, XA_WM_NAME, XCheckWindowEvent, Atom as XAtom, ClientMessage, ClientMessageData, SubstructureNotifyMask, SubstructureRedirectMask, XA_ATOM, XClassHint, XClientMessageEvent, XGetAtomName, XGetClassHint, XMoveResizeWindow, NotifyNonlinear, NotifyNormal, XCrossingEvent, KeySym, Button1, Button1MotionMask, Button2MotionMask, Button3MotionMask, Button4MotionMask, Button5MotionMask, Button2, Button3, ButtonMotionMask, MotionNotify, PointerMotionMask, XMotionEvent, CurrentTime, Time, XWarpPointer, KeyCode, XFlush, RevertToParent, True, Window as XWindow, XA_WINDOW, XAllPlanes, XButtonEvent, XDefaultRootWindow, XDestroyImage, XEvent, XFree, XGetImage, XGetWindowAttributes, XGetWMName, XImage, XKeyEvent, XKeysymToKeycode, XQueryPointer, XQueryTree, XSendEvent, XSetInputFocus, XTextProperty, XTranslateCoordinates, XWindowAttributes, XA_CARDINAL, BadWindow, XGetWMNormalHints, XSizeHints, PBaseSize, PMaxSize, PMinSize, PResizeInc};
+use x11::keysym::XK_v;
 use x11::xlib;
+#[cfg(feature = "xrender")]
+use x11::xrender::XRenderFindVisualFormat;
 
 use crate::{
+	Atom,
 	Display,
-	NotSupported,
-	Null,
+	NET_ACTIVE_WINDOW,
+	NET_FRAME_EXTENTS,
+	NET_WM_DESKTOP,
+	NET_STARTUP_ID,
+	NET_WM_NAME,
+	NET_WM_PID,
+	NET_WM_STATE,
+	Error,
+	Rect,
 	Session,
 	util::get_window_property,
 };
-use crate::event::btn_event::ButtonType;
+use crate::clipboard::{self, PasteError, Selection};
+use crate::grab::{GrabError, InputBlock};
+use crate::recorder::{SnapshotOptions, Snapshots};
+use crate::keys::{compose_sequence, KeyCombo, keysym_from_char, Modifier, Remap, UnknownKey};
+use crate::capture::{convert_into, convert_into_with_alpha, CaptureError, CaptureSession, Frame, PixelFormat};
+#[cfg(feature = "async")]
+use crate::capture::CaptureStream;
+use crate::event::btn_event::{ButtonType, MouseButton, ScrollDirection};
+use crate::event::EventOptions;
+use crate::event::crossing_event::CrossingType;
+use crate::event::{GeometryWatcher, HistoryEntry, HISTORY_MASK, HISTORY_SUBSCRIPTION, PropertyWatcher, release_mask, select_mask, Subscription, TitleWatcher};
+use crate::event::held_key::HeldKey;
 use crate::event::key_event::KeyType;
+use crate::event::motion::MotionProfile;
+#[cfg(feature = "xtest")]
+use crate::event::{backend::InputBackend, xtest};
+#[cfg(feature = "xtest")]
+use x11::xlib::{PointerRoot, XGetInputFocus};
+
+/// How often [Window::wait_until_stable] captures the window to look for changes.
+const STABLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// This struct represents a window and holds the ID of that window that can be used
 /// to query for its name.
@@ -30,14 +67,14 @@ pub struct Window {
 	/// raw reference to window
 	pub window: XWindow,
 	/// shared display reference
-	pub display: Rc<Display>,
+	pub display: Arc<Display>,
 }
 
 impl Clone for Window {
 	fn clone(&self) -> Self {
 		Self {
 			window: self.window,
-			display: Rc::clone(&self.display),
+			display: Arc::clone(&self.display),
 		}
 	}
 }
@@ -46,8 +83,8 @@ impl Window {
 	/// Gets the default root window of a display.
 	///
 	/// A wrapper around the [XDefaultRootWindow] function.
-	pub fn default_root_window(display: Rc<Display>) -> Self {
-		let window = unsafe { XDefaultRootWindow(display.0) };
+	pub fn default_root_window(display: Arc<Display>) -> Self {
+		let window = unsafe { XDefaultRootWindow(display.as_raw()) };
 		Window {
 			window,
 			display,
@@ -59,18 +96,25 @@ impl Window {
 	/// that are set to [None] but are required.
 	/// This uses the display, root_window, and active_window_atom properties
 	/// of the [Session] struct.
-	pub fn active_window(session: &Session) -> Result<Self, NotSupported> {
+	pub fn active_window(session: &Session) -> Result<Self, Error> {
 		let Session { display, .. } = session;
 		let root_window = session.root().clone();
 		let active_window_atom = session.active_list();
+		if active_window_atom.0 == 0 {
+			return Err(Error::AtomMissing(NET_ACTIVE_WINDOW));
+		}
 		let response = unsafe { get_window_property(display, root_window, *active_window_atom, XA_WINDOW)? };
-		let window = match response.actual_format_return {
+		if response.actual_type_return == 0 {
+			return Err(Error::PropertyMissing(NET_ACTIVE_WINDOW));
+		}
+		let format = response.actual_format_return;
+		let window = match format {
 			8 => {
 				unsafe { slice::from_raw_parts(response.proper_return as *const u8, response.nitems_return as usize) }
 					.first()
 					.map(|x| Window {
 						window: *x as XWindow,
-						display: Rc::clone(display),
+						display: Arc::clone(display),
 					})
 			}
 			16 => {
@@ -78,7 +122,7 @@ impl Window {
 					.first()
 					.map(|x| Window {
 						window: *x as XWindow,
-						display: Rc::clone(display),
+						display: Arc::clone(display),
 					})
 			}
 			32 => {
@@ -86,193 +130,1549 @@ impl Window {
 					.first()
 					.map(|x| Window {
 						window: *x as XWindow,
-						display: Rc::clone(display),
+						display: Arc::clone(display),
 					})
 			}
 			_ => { None }
 		};
 		unsafe { XFree(response.proper_return as *mut c_void) };
-		window.ok_or(NotSupported)
+		if ![8, 16, 32].contains(&format) {
+			return Err(Error::PropertyFormatUnexpected(format));
+		}
+		// An empty property means no window is active, as while the desktop has the focus.
+		window.filter(|it| it.window != 0).ok_or(Error::PropertyMissing(NET_ACTIVE_WINDOW))
 	}
 	/// Gets the title of the window.
 	///
-	/// If the window does not have a title, a null pointer may be returned.
-	/// In that case the [Null] error is returned.
-	/// However, I have not encountered a [Null] error yet.
-	pub fn get_title(&self) -> Result<WindowTitle, Null> {
-		let mut text_property = XTextProperty {
-			value: null_mut(),
-			encoding: 0,
-			format: 0,
-			nitems: 0,
-		};
-		unsafe {
-			XGetWMName(
-				self.display.0,
-				self.window,
-				&mut text_property,
-			)
-		};
+	/// Returns [Error::PropertyMissing] if the window does not have a `WM_NAME`,
+	/// [Error::BadWindow] if the window was closed.
+	pub fn get_title(&self) -> Result<WindowTitle<'_>, Error> {
+		let text_property = self.wm_name()?;
 		if !text_property.value.is_null() {
 			let text = unsafe { CStr::from_ptr(text_property.value as *mut i8) };
 			Ok(WindowTitle(text))
-		} else { Err(Null) }
+		} else { Err(Error::PropertyMissing("WM_NAME")) }
+	}
+
+	/// Get the title of the window as UTF-8.
+	///
+	/// Unlike [Self::get_title] this prefers `_NET_WM_NAME`, which modern applications set
+	/// in UTF-8, over `WM_NAME`, which is often limited to Latin-1.
+	pub fn title(&self) -> Option<String> {
+		if let Ok(atom) = Atom::new(&self.display, NET_WM_NAME) {
+			if atom.0 != 0 {
+				let response = unsafe { get_window_property(&self.display, self.clone(), atom, AnyPropertyType as _) };
+				if let Ok(response) = response {
+					let title = if response.actual_format_return == 8 && !response.proper_return.is_null() {
+						let bytes = unsafe { slice::from_raw_parts(response.proper_return, response.nitems_return as usize) };
+						Some(String::from_utf8_lossy(bytes).into_owned())
+					} else { None };
+					if !response.proper_return.is_null() {
+						unsafe { XFree(response.proper_return as *mut c_void) };
+					}
+					if title.is_some() {
+						return title;
+					}
+				}
+			}
+		}
+		self.get_title().ok().map(|it| it.as_ref().to_string_lossy().into_owned())
+	}
+
+	/// Watches the title of the window, the iterator yields the new title every time it changes.
+	///
+	/// This adds [x11::xlib::PropertyChangeMask] to the events selected for the window until the watcher
+	/// is dropped, see [crate::Display::set_mask_policy]. While waiting every other event of the connection is dropped.
+	/// # Example
+	/// ```donttest
+	/// for title in win.watch_title() {
+	///     println!("Now playing: {}", title);
+	/// }
+	/// ```
+	pub fn watch_title(&self) -> TitleWatcher<'_> {
+		TitleWatcher::new(self)
+	}
+
+	/// Watches the properties of the window, the iterator yields which one changed every time one does.
+	///
+	/// This selects [x11::xlib::PropertyChangeMask] and [x11::xlib::StructureNotifyMask] for the window,
+	/// keeping the events that were selected before. Narrow it down with [PropertyWatcher::only]
+	/// or [PropertyWatcher::titles].
+	/// # Example
+	/// ```donttest
+	/// for change in win.watch_properties().titles() {
+	///     label.set_text(&win.title().unwrap_or_default());
+	/// }
+	/// ```
+	pub fn watch_properties(&self) -> PropertyWatcher<'_> {
+		PropertyWatcher::new(self)
+	}
+
+	/// Watches the position and size of the window, the iterator yields the new area every time it changes.
+	///
+	/// This selects [x11::xlib::StructureNotifyMask] for the window, keeping the events that were selected before.
+	/// # Example
+	/// ```donttest
+	/// for area in win.watch_geometry() {
+	///     buffer.resize(PixelFormat::Bgra.buffer_len(area.width, area.height), 0);
+	/// }
+	/// ```
+	pub fn watch_geometry(&self) -> GeometryWatcher<'_> {
+		GeometryWatcher::new(self)
+	}
+
+	/// Starts keeping the last `capacity` property, state and geometry changes of the window, see [Self::history].
+	///
+	/// This selects [x11::xlib::PropertyChangeMask] and [x11::xlib::StructureNotifyMask] for the window,
+	/// on top of what is already selected, until [Self::stop_history]. Calling this again only changes the capacity.
+	pub fn record_history(&self, capacity: usize) {
+		self.display.histories().start(self.window, capacity);
+		select_mask(&self.display, self.window, HISTORY_SUBSCRIPTION, HISTORY_MASK);
+	}
+
+	/// Stops keeping the changes of the window and forgets the ones that were kept.
+	///
+	/// The events [Self::record_history] selected are deselected again, see [crate::Display::set_mask_policy].
+	pub fn stop_history(&self) {
+		self.display.histories().stop(self.window);
+		release_mask(&self.display, self.window, HISTORY_SUBSCRIPTION);
+	}
+
+	/// Gets the changes of the window kept since [Self::record_history], the oldest first.
+	///
+	/// Changes are recorded as an [crate::event::EventLoop] of the same connection reads them,
+	/// the ones still waiting on the connection are taken off it by this call.
+	/// # Example
+	/// ```donttest
+	/// win.record_history(64);
+	/// // ...
+	/// if !win.states()?.iter().any(|it| it == "_NET_WM_STATE_FULLSCREEN") {
+	///     panic!("Not fullscreen, recent changes: {:#?}", win.history());
+	/// }
+	/// ```
+	pub fn history(&self) -> Vec<HistoryEntry> {
+		let mut histories = self.display.histories();
+		if histories.is_recording(self.window) {
+			histories.drain(&self.display, self.window);
+		}
+		histories.entries(self.window)
+	}
+
+	/// Waits until the window stopped changing for `quiet_period`, e.g. after navigating or opening a dialog.
+	///
+	/// Moves, resizes, title changes and changes of the contents all count, the contents are compared
+	/// by capturing the window every 100 milliseconds. Returns false if the window still changed
+	/// when `timeout` passed, or if the [crate::CancelToken] of the display was cancelled.
+	/// # Example
+	/// ```donttest
+	/// win.click(40, 12);
+	/// assert!(win.wait_until_stable(Duration::from_millis(300), Duration::from_secs(5)));
+	/// ```
+	pub fn wait_until_stable(&self, quiet_period: Duration, timeout: Duration) -> bool {
+		let mask = PropertyChangeMask | StructureNotifyMask;
+		let _subscription = Subscription::new(&self.display, self.window, mask);
+		let titles = [XA_WM_NAME, Atom::new(&self.display, NET_WM_NAME).map_or(0, |it| it.0)];
+		let mut session = CaptureSession::new(self.clone());
+		let contents = |session: &mut CaptureSession| session.capture().ok().map(|img| {
+			let img = img.as_ref();
+			let data = unsafe { slice::from_raw_parts(img.data as *const u8, (img.bytes_per_line * img.height) as usize) };
+			let mut hasher = DefaultHasher::new();
+			data.hash(&mut hasher);
+			hasher.finish()
+		});
+
+		let start = Instant::now();
+		let mut changed = start;
+		let mut last = contents(&mut session);
+		let mut event = MaybeUninit::<XEvent>::uninit();
+		loop {
+			while unsafe { XCheckWindowEvent(self.display.as_raw(), self.window, mask, event.as_mut_ptr()) } != 0 {
+				let event = unsafe { event.assume_init() };
+				self.display.histories().record(&self.display, &event);
+				let typ = event.get_type();
+				let is_title = typ == PropertyNotify && titles.contains(&unsafe { event.property }.atom);
+				if is_title || typ == ConfigureNotify || typ == MapNotify || typ == UnmapNotify {
+					changed = Instant::now();
+				}
+			}
+			let now = contents(&mut session);
+			if now != last {
+				last = now;
+				changed = Instant::now();
+			}
+			if changed.elapsed() >= quiet_period {
+				return true;
+			}
+			if start.elapsed() >= timeout {
+				return false;
+			}
+			if self.display.cancel_token().sleep(STABLE_POLL_INTERVAL.min(quiet_period)) {
+				return false;
+			}
+		}
 	}
 
-	pub(crate) fn match_title(&self, title: impl AsRef<[u8]>) -> bool {
+	/// Reads `WM_NAME` with the errors of the server trapped, the value is null if the window has none.
+	fn wm_name(&self) -> Result<XTextProperty, Error> {
 		let mut text_property = XTextProperty {
 			value: null_mut(),
 			encoding: 0,
 			format: 0,
 			nitems: 0,
 		};
-		unsafe {
+		let (_, errors) = self.display.with_error_trap(|| unsafe {
 			XGetWMName(
-				self.display.0,
+				self.display.as_raw(),
 				self.window,
 				&mut text_property,
 			)
-		};
-		if !text_property.value.is_null() {
-			let text = unsafe { CStr::from_ptr(text_property.value as *mut i8) };
-			text.to_bytes() == title.as_ref()
-		} else {
-			false
+		});
+		match errors.first() {
+			Some(err) => Err((*err).into()),
+			None => Ok(text_property),
 		}
 	}
 
 	/// Get window attribute
+	///
+	/// If the window doesn't exist anymore, the attributes are all zero, see [Self::try_get_attr].
 	pub fn get_attr(&self) -> XWindowAttributes {
-		let mut attr = XWindowAttributes {
-			x: 0,
-			y: 0,
-			width: 0,
-			height: 0,
-			border_width: 0,
-			depth: 0,
-			visual: null_mut(),
-			root: 0,
-			class: 0,
-			bit_gravity: 0,
-			win_gravity: 0,
-			backing_store: 0,
-			backing_planes: 0,
-			backing_pixel: 0,
-			save_under: 0,
-			colormap: 0,
-			map_installed: 0,
-			map_state: 0,
-			all_event_masks: 0,
-			your_event_mask: 0,
-			do_not_propagate_mask: 0,
-			override_redirect: 0,
-			screen: null_mut(),
+		self.try_get_attr().unwrap_or_else(|_| empty_attributes())
+	}
+
+	/// Like [Self::get_attr], but returns [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn try_get_attr(&self) -> Result<XWindowAttributes, Error> {
+		let mut attr = empty_attributes();
+		let (status, errors) = self.display.with_error_trap(|| unsafe {
+			XGetWindowAttributes(self.display.as_raw(), self.window, attr.borrow_mut() as _)
+		});
+		match errors.first() {
+			Some(err) => Err((*err).into()),
+			None if status == 0 => Err(Error::BadWindow(self.window)),
+			None => Ok(attr),
+		}
+	}
+
+	/// Runs `requests` in an error trap, see [Display::with_error_trap], failing with the first error of the server.
+	pub(crate) fn trapped<R>(&self, requests: impl FnOnce() -> R) -> Result<R, Error> {
+		let (res, errors) = self.display.with_error_trap(requests);
+		match errors.first() {
+			Some(err) => Err((*err).into()),
+			None => Ok(res),
+		}
+	}
+
+	/// Get the position of the top left corner of this window, relative to the root window.
+	///
+	/// The `x` and `y` of [Self::get_attr] are relative to the parent,
+	/// which is usually the frame the window manager put around the window.
+	///
+	/// Returns [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn root_position(&self) -> Result<(i32, i32), Error> {
+		let root = Window::default_root_window(Arc::clone(&self.display)).window;
+		let (mut x, mut y, mut child) = (0, 0, 0);
+		self.trapped(|| unsafe { XTranslateCoordinates(self.display.as_raw(), self.window, root, 0, 0, &mut x, &mut y, &mut child) })?;
+		Ok((x, y))
+	}
+
+	/// Gets the child window at `x` and `y`, relative to this window.
+	///
+	/// Only the direct children are considered, returns `None` if the position is not on a child.
+	/// Fails with [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn child_at(&self, x: c_int, y: c_int) -> Result<Option<Window>, Error> {
+		let (mut child_x, mut child_y, mut child) = (0, 0, 0);
+		self.trapped(|| unsafe {
+			XTranslateCoordinates(self.display.as_raw(), self.window, self.window, x, y, &mut child_x, &mut child_y, &mut child)
+		})?;
+		Ok(if child == 0 { None } else { Some(Window { window: child, display: Arc::clone(&self.display) }) })
+	}
+
+	/// Gets the direct children of the window, from the bottom of the stack to the top.
+	///
+	/// Fails with [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn children(&self) -> Result<Vec<Window>, Error> {
+		let (mut root, mut parent, mut children, mut count) = (0, 0, null_mut(), 0);
+		let status = self.trapped(|| unsafe {
+			XQueryTree(self.display.as_raw(), self.window, &mut root, &mut parent, &mut children, &mut count)
+		})?;
+		if status == 0 || children.is_null() {
+			return Ok(Vec::new());
+		}
+		let res = unsafe { slice::from_raw_parts(children, count as usize) }
+			.iter()
+			.map(|it| Window { window: *it, display: Arc::clone(&self.display) })
+			.collect();
+		unsafe { XFree(children as *mut c_void) };
+		Ok(res)
+	}
+
+	/// Get the top level window that contains this window.
+	///
+	/// With a reparenting window manager this is the frame that holds the title bar and borders,
+	/// otherwise it is this window itself. Fails with [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn frame(&self) -> Result<Window, Error> {
+		let mut current = self.window;
+		loop {
+			let (mut root, mut parent, mut children, mut count) = (0, 0, null_mut(), 0);
+			let status = self.trapped(|| unsafe {
+				XQueryTree(self.display.as_raw(), current, &mut root, &mut parent, &mut children, &mut count)
+			})?;
+			if status == 0 {
+				break;
+			}
+			if !children.is_null() {
+				unsafe { XFree(children as *mut c_void) };
+			}
+			if parent == root || parent == 0 {
+				break;
+			}
+			current = parent;
+		}
+		Ok(Window {
+			window: current,
+			display: Arc::clone(&self.display),
+		})
+	}
+
+	/// Get the size of the decorations the window manager draws around this window.
+	///
+	/// This reads the `_NET_FRAME_EXTENTS` property, returns [None] if it is not set.
+	pub fn frame_extents(&self) -> Option<FrameExtents> {
+		let atom = Atom::new(&self.display, NET_FRAME_EXTENTS).ok()?;
+		if atom.0 == 0 {
+			return None;
+		}
+		let response = unsafe { get_window_property(&self.display, self.clone(), atom, XA_CARDINAL).ok()? };
+		let extents = if response.actual_format_return == 32 && response.nitems_return >= 4 {
+			let values = unsafe { slice::from_raw_parts(response.proper_return as *const c_long, 4) };
+			Some(FrameExtents {
+				left: values[0] as u32,
+				right: values[1] as u32,
+				top: values[2] as u32,
+				bottom: values[3] as u32,
+			})
+		} else { None };
+		unsafe { XFree(response.proper_return as *mut c_void) };
+		extents
+	}
+
+	/// Get the instance and class name of the window from `WM_CLASS`, e.g. `("navigator", "firefox")`.
+	///
+	/// Returns `None` if the window has no class, and [Error::BadWindow] if it doesn't exist anymore.
+	pub fn class(&self) -> Result<Option<(String, String)>, Error> {
+		let mut hint = XClassHint { res_name: null_mut(), res_class: null_mut() };
+		if self.trapped(|| unsafe { XGetClassHint(self.display.as_raw(), self.window, &mut hint) })? == 0 {
+			return Ok(None);
+		}
+		let take = |ptr: *mut c_char| {
+			if ptr.is_null() {
+				return String::new();
+			}
+			let text = unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned();
+			unsafe { XFree(ptr as *mut c_void) };
+			text
+		};
+		Ok(Some((take(hint.res_name), take(hint.res_class))))
+	}
+
+	/// Get the position of the window including its decorations, relative to the root window,
+	/// and the size of the window without them.
+	///
+	/// This is what [Self::move_resize] takes, so moving a window to its own geometry doesn't move it.
+	pub fn geometry(&self) -> Rect {
+		let attr = self.get_attr();
+		let (x, y) = self.root_position().unwrap_or_default();
+		let extents = self.frame_extents().unwrap_or_default();
+		Rect::new(x - extents.left as i32, y - extents.top as i32, attr.width as u32, attr.height as u32)
+	}
+
+	/// Moves and resizes the window, see [Self::geometry].
+	///
+	/// The window manager puts the top left corner of the decorations at `x` and `y`,
+	/// it may still refuse or adjust the request.
+	pub fn move_resize(&self, rect: Rect) {
+		unsafe {
+			XMoveResizeWindow(self.display.as_raw(), self.window, rect.x, rect.y, rect.width.max(1), rect.height.max(1));
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Moves and resizes the window so that it fills `rect` including its decorations, e.g. a region of a monitor.
+	///
+	/// Unlike [Self::move_resize] this first asks the window manager to take off the states that
+	/// would keep the window from being placed freely: maximized, fullscreen and shaded.
+	/// The size is shrunk to what the `WM_NORMAL_HINTS` of the window allow, so terminals that resize
+	/// in steps of a character may leave a few pixels of `rect` at the right and bottom empty.
+	/// Fails with [Error::BadWindow] if the window doesn't exist anymore.
+	/// # Example
+	/// ```donttest
+	/// // The left third of the first monitor
+	/// let monitor = session.monitors()[0];
+	/// win.fill_region(Rect::new(monitor.x, monitor.y, monitor.width / 3, monitor.height))?;
+	/// ```
+	pub fn fill_region(&self, rect: Rect) -> Result<(), Error> {
+		let states = self.states()?;
+		let blocking = FILL_REGION_BLOCKING_STATES.iter().copied().filter(|it| states.iter().any(|state| state == it)).collect::<Vec<_>>();
+		self.remove_states(&blocking);
+		let extents = self.frame_extents().unwrap_or_default();
+		let width = rect.width.saturating_sub(extents.left + extents.right).max(1);
+		let height = rect.height.saturating_sub(extents.top + extents.bottom).max(1);
+		let (width, height) = self.fit_size_hints(width, height)?;
+		self.move_resize(Rect::new(rect.x, rect.y, width, height));
+		Ok(())
+	}
+
+	/// Asks the window manager to take the states off the window, see [Self::states].
+	fn remove_states(&self, names: &[&str]) {
+		let atom = match Atom::new(&self.display, NET_WM_STATE) {
+			Ok(atom) if atom.0 != 0 => atom,
+			_ => return,
+		};
+		let root = Window::default_root_window(Arc::clone(&self.display));
+		// A message changes up to two states at once.
+		for pair in names.chunks(2) {
+			let mut message = XClientMessageEvent {
+				type_: ClientMessage,
+				serial: 0,
+				send_event: True,
+				display: self.display.as_raw(),
+				window: self.window,
+				message_type: atom.0,
+				format: 32,
+				data: ClientMessageData::new(),
+			};
+			message.data.set_long(0, NET_WM_STATE_REMOVE);
+			for (i, name) in pair.iter().enumerate() {
+				let state = Atom::new(&self.display, *name).map_or(0, |it| it.0);
+				message.data.set_long(i + 1, state as c_long);
+			}
+			message.data.set_long(3, 2);
+			root.post(XEvent { client_message: message }, SubstructureRedirectMask | SubstructureNotifyMask);
+		}
+	}
+
+	/// Shrinks the size to the largest one the `WM_NORMAL_HINTS` of the window allow, that is not larger.
+	///
+	/// The minimum size wins if it is larger than the size.
+	fn fit_size_hints(&self, width: u32, height: u32) -> Result<(u32, u32), Error> {
+		let mut hints = unsafe { MaybeUninit::<XSizeHints>::zeroed().assume_init() };
+		let mut supplied = 0;
+		if self.trapped(|| unsafe { XGetWMNormalHints(self.display.as_raw(), self.window, &mut hints, &mut supplied) })? == 0 {
+			return Ok((width, height));
+		}
+		let (mut width, mut height) = (width as c_int, height as c_int);
+		if hints.flags & PMaxSize != 0 && hints.max_width > 0 && hints.max_height > 0 {
+			width = width.min(hints.max_width);
+			height = height.min(hints.max_height);
+		}
+		// Without a base size the minimum size is the base of the increments, see ICCCM 4.1.2.3.
+		let (base_width, base_height) = match hints.flags {
+			flags if flags & PBaseSize != 0 => (hints.base_width, hints.base_height),
+			flags if flags & PMinSize != 0 => (hints.min_width, hints.min_height),
+			_ => (0, 0),
+		};
+		if hints.flags & PResizeInc != 0 {
+			if hints.width_inc > 1 && width > base_width {
+				width -= (width - base_width) % hints.width_inc;
+			}
+			if hints.height_inc > 1 && height > base_height {
+				height -= (height - base_height) % hints.height_inc;
+			}
+		}
+		if hints.flags & PMinSize != 0 {
+			width = width.max(hints.min_width);
+			height = height.max(hints.min_height);
+		}
+		Ok((width.max(1) as u32, height.max(1) as u32))
+	}
+
+	/// Get the virtual desktop the window is on from `_NET_WM_DESKTOP`, starting at 0.
+	///
+	/// Windows that are on every desktop have `0xFFFFFFFF`.
+	pub fn desktop(&self) -> Option<u32> {
+		let atom = Atom::new(&self.display, NET_WM_DESKTOP).ok()?;
+		if atom.0 == 0 {
+			return None;
+		}
+		let response = unsafe { get_window_property(&self.display, self.clone(), atom, XA_CARDINAL).ok()? };
+		let desktop = if response.actual_format_return == 32 && response.nitems_return >= 1 {
+			Some(unsafe { *(response.proper_return as *const c_long) } as u32)
+		} else { None };
+		if !response.proper_return.is_null() {
+			unsafe { XFree(response.proper_return as *mut c_void) };
+		}
+		desktop
+	}
+
+	/// Asks the window manager to move the window to another virtual desktop, see [Self::desktop].
+	pub fn set_desktop(&self, desktop: u32) {
+		let atom = match Atom::new(&self.display, NET_WM_DESKTOP) {
+			Ok(atom) if atom.0 != 0 => atom,
+			_ => return,
+		};
+		let mut message = XClientMessageEvent {
+			type_: ClientMessage,
+			serial: 0,
+			send_event: True,
+			display: self.display.as_raw(),
+			window: self.window,
+			message_type: atom.0,
+			format: 32,
+			data: ClientMessageData::new(),
+		};
+		// The second value tells the window manager the request comes from a pager instead of the application.
+		message.data.set_long(0, desktop as c_long);
+		message.data.set_long(1, 2);
+		let root = Window::default_root_window(Arc::clone(&self.display));
+		root.post(XEvent { client_message: message }, SubstructureRedirectMask | SubstructureNotifyMask);
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+
+	/// Get the ID of the process that owns the window from `_NET_WM_PID`.
+	///
+	/// The process is on the machine in `WM_CLIENT_MACHINE`, usually the one the server runs on.
+	pub fn pid(&self) -> Option<u32> {
+		let atom = Atom::new(&self.display, NET_WM_PID).ok()?;
+		if atom.0 == 0 {
+			return None;
+		}
+		let response = unsafe { get_window_property(&self.display, self.clone(), atom, XA_CARDINAL).ok()? };
+		let pid = if response.actual_format_return == 32 && response.nitems_return >= 1 {
+			Some(unsafe { *(response.proper_return as *const c_long) } as u32)
+		} else { None };
+		if !response.proper_return.is_null() {
+			unsafe { XFree(response.proper_return as *mut c_void) };
+		}
+		pid
+	}
+
+	/// Get the startup notification ID the window was created for, from `_NET_STARTUP_ID`.
+	pub fn startup_id(&self) -> Option<String> {
+		let atom = Atom::new(&self.display, NET_STARTUP_ID).ok()?;
+		if atom.0 == 0 {
+			return None;
+		}
+		let response = unsafe { get_window_property(&self.display, self.clone(), atom, AnyPropertyType as _).ok()? };
+		let id = if response.actual_format_return == 8 && !response.proper_return.is_null() {
+			let bytes = unsafe { slice::from_raw_parts(response.proper_return, response.nitems_return as usize) };
+			Some(String::from_utf8_lossy(bytes).into_owned())
+		} else { None };
+		if !response.proper_return.is_null() {
+			unsafe { XFree(response.proper_return as *mut c_void) };
+		}
+		id
+	}
+
+	/// Get the names of the atoms in `_NET_WM_STATE`, e.g. `_NET_WM_STATE_MAXIMIZED_VERT`.
+	///
+	/// Fails with [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn states(&self) -> Result<Vec<String>, Error> {
+		let atom = match Atom::new(&self.display, NET_WM_STATE) {
+			Ok(atom) if atom.0 != 0 => atom,
+			_ => return Ok(Vec::new()),
 		};
-		unsafe { XGetWindowAttributes(self.display.0, self.window, attr.borrow_mut() as _) };
-		attr
+		let response = unsafe { get_window_property(&self.display, self.clone(), atom, XA_ATOM) }?;
+		let mut states = Vec::new();
+		if response.actual_format_return == 32 && !response.proper_return.is_null() {
+			let atoms = unsafe { slice::from_raw_parts(response.proper_return as *const XAtom, response.nitems_return as usize) };
+			for atom in atoms {
+				// A client can put any number in the property, those that are no atoms fail with BadAtom.
+				let name = self.trapped(|| unsafe { XGetAtomName(self.display.as_raw(), *atom) }).unwrap_or(null_mut());
+				if !name.is_null() {
+					states.push(unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned());
+					unsafe { XFree(name as *mut c_void) };
+				}
+			}
+		}
+		if !response.proper_return.is_null() {
+			unsafe { XFree(response.proper_return as *mut c_void) };
+		}
+		Ok(states)
+	}
+
+	/// Get the window and the part of it that shows this window including its decorations.
+	///
+	/// Uses the [Self::frame] if there is one, otherwise the area of the root window
+	/// covered by this window grown by its [Self::frame_extents].
+	pub(crate) fn decorated_area(&self) -> (Window, Rect) {
+		let frame = self.frame().unwrap_or_else(|_| self.clone());
+		if frame.window != self.window {
+			let attr = frame.get_attr();
+			return (frame, Rect::new(0, 0, attr.width as u32, attr.height as u32));
+		}
+		let root = Window::default_root_window(Arc::clone(&self.display));
+		let root_attr = root.get_attr();
+		let attr = self.get_attr();
+		let (x, y) = self.root_position().unwrap_or_default();
+		let extents = self.frame_extents().unwrap_or_default();
+		let area = Rect::new(
+			x - extents.left as i32,
+			y - extents.top as i32,
+			attr.width as u32 + extents.left + extents.right,
+			attr.height as u32 + extents.top + extents.bottom,
+		);
+		let area = area
+			.intersect(&Rect::new(0, 0, root_attr.width as u32, root_attr.height as u32))
+			.unwrap_or_default();
+		(root, area)
 	}
 
 	/// Capture screenshot of this window
-	pub fn capture(&self) -> XImg {
+	///
+	/// Returns [Error::BadWindow] if the window doesn't exist anymore,
+	/// and [Error::Capture] with [CaptureError::Null] if it is not viewable, instead of ending the process.
+	pub fn capture(&self) -> Result<XImg, Error> {
+		let attr = self.try_get_attr()?;
+		Ok(self.get_image(Rect::new(0, 0, attr.width as u32, attr.height as u32))?)
+	}
+
+	/// Capture screenshot of this window including the title bar and borders, see [Self::frame].
+	///
+	/// Fails like [Self::capture].
+	pub fn capture_decorated(&self) -> Result<XImg, Error> {
+		self.try_get_attr()?;
+		let (source, area) = self.decorated_area();
+		Ok(source.get_image(area)?)
+	}
+
+	/// Grabs `area` of this window with [XGetImage], with the errors of the server trapped.
+	///
+	/// The server answers with BadMatch for windows that are not viewable, that is [CaptureError::Null] as well.
+	pub(crate) fn get_image(&self, area: Rect) -> Result<XImg, CaptureError> {
+		let Rect { x, y, width, height } = area;
+		let (img, errors) = self.display.with_error_trap(|| unsafe {
+			XGetImage(self.display.as_raw(), self.window, x, y, width, height, XAllPlanes(), xlib::ZPixmap)
+		});
+		let img = (!img.is_null()).then(|| XImg { img });
+		if errors.iter().any(|it| it.error_code == BadWindow) {
+			return Err(CaptureError::BadWindow(self.window));
+		}
+		img.ok_or(CaptureError::Null)
+	}
+
+	/// The attributes of the window, with [CaptureError::BadWindow] if it doesn't exist anymore.
+	fn capture_attr(&self) -> Result<XWindowAttributes, CaptureError> {
+		self.try_get_attr().map_err(|_| CaptureError::BadWindow(self.window))
+	}
+
+	/// Capture screenshot of this window directly into `buf`, converted to `format`.
+	///
+	/// Use this when the frame has to end up in memory you already own (e.g. a buffer mapped for a video encoder),
+	/// `buf` has to be at least [PixelFormat::buffer_len] bytes long for the current size of the window.
+	///
+	/// Returns the width and height of the captured image.
+	pub fn capture_into(&self, buf: &mut [u8], format: PixelFormat) -> Result<(u32, u32), CaptureError> {
+		let attr = self.capture_attr()?;
+		let width = attr.width as u32;
+		let height = attr.height as u32;
+		let required = format.buffer_len(width, height);
+		if buf.len() < required {
+			return Err(CaptureError::BufferTooSmall(required));
+		}
+
+		self.get_image(Rect::new(0, 0, width, height))?.convert_into(buf, format)?;
+		Ok((width, height))
+	}
+
+	/// Get the bits of a pixel of this window that hold its alpha channel.
+	///
+	/// This looks up the XRender picture format of the visual of the window,
+	/// returns [None] if the window has no alpha channel, e.g. because there is no compositor.
+	#[cfg(feature = "xrender")]
+	pub fn alpha_mask(&self) -> Option<c_ulong> {
 		let attr = self.get_attr();
+		let format = unsafe { XRenderFindVisualFormat(self.display.as_raw(), attr.visual) };
+		if format.is_null() {
+			return None;
+		}
+		let direct = unsafe { (*format).direct };
+		if direct.alphaMask == 0 {
+			return None;
+		}
+		Some((direct.alphaMask as u16 as c_ulong) << direct.alpha)
+	}
+
+	/// Like [Self::capture_into] but keeps the alpha channel of ARGB windows, see [Self::alpha_mask].
+	///
+	/// Windows without an alpha channel are captured as fully opaque.
+	#[cfg(feature = "xrender")]
+	pub fn capture_alpha_into(&self, buf: &mut [u8], format: PixelFormat) -> Result<(u32, u32), CaptureError> {
+		let attr = self.capture_attr()?;
+		let alpha_mask = self.alpha_mask().unwrap_or(0);
 		let width = attr.width as u32;
 		let height = attr.height as u32;
+		let required = format.buffer_len(width, height);
+		if buf.len() < required {
+			return Err(CaptureError::BufferTooSmall(required));
+		}
+
+		self.get_image(Rect::new(0, 0, width, height))?.convert_into_with_alpha(buf, format, alpha_mask)?;
+		Ok((width, height))
+	}
+
+	/// Captures this window every `interval` as a [futures_core::Stream] of frames converted to `format`.
+	///
+	/// Waiting for the frames doesn't block the thread, see [CaptureStream]. This has to be called on a tokio runtime,
+	/// it fails if the connection can't be registered with it.
+	#[cfg(feature = "async")]
+	pub fn capture_stream_async(&self, interval: Duration, format: PixelFormat) -> io::Result<CaptureStream> {
+		CaptureStream::new(self.clone(), interval, format)
+	}
 
-		let img = unsafe { XGetImage(self.display.0, self.window, 0, 0, width, height, XAllPlanes(), xlib::ZPixmap) };
-		XImg { img }
+	/// Saves a PNG image of the window into `dir` every time its contents change, until the returned guard is stopped.
+	///
+	/// Changes are looked for by capturing the window on a worker thread, an image is only saved once the window
+	/// stayed the same for [SnapshotOptions::debounce] and differs enough from the last saved one.
+	/// The images are named `snapshot-00000.png`, `snapshot-00001.png` and so on.
+	/// # Example
+	/// ```donttest
+	/// let snapshots = win.snapshot_on_change("shots", SnapshotOptions::default())?;
+	/// run_the_test();
+	/// println!("saved {:?}", snapshots.stop()?);
+	/// ```
+	pub fn snapshot_on_change(&self, dir: impl AsRef<Path>, options: SnapshotOptions) -> io::Result<Snapshots> {
+		Snapshots::start(self, dir.as_ref(), options)
+	}
+
+	/// Keeps the user's keyboard and pointer input away from this window for `duration`,
+	/// so they can't interfere while automation runs.
+	///
+	/// The keyboard and pointer are grabbed, with the pointer confined to the window, until the duration is over
+	/// or the returned block is dropped. Input sent to the window with XSendEvent still arrives,
+	/// input faked with XTest is blocked as well, see [InputBlock].
+	/// Returns [GrabError::NotViewable] if the window isn't mapped.
+	/// # Example
+	/// ```donttest
+	/// let _block = win.block_input(Duration::from_secs(10))?;
+	/// win.type_text("typed without the user getting in between", Duration::from_millis(10));
+	/// ```
+	pub fn block_input(&self, duration: Duration) -> Result<InputBlock, GrabError> {
+		InputBlock::new(self.window, duration)
 	}
 
 	/// Request to focus current window
-	/// # Known issue
-	/// + if compositor is disabled it can't send event (if you have compositor it will be fine)
-	///     + window is beside fullscreen window
-	///     + minimized window
-	pub fn focus(&self) {
-		unsafe { XSetInputFocus(self.display.0, self.window, RevertToParent, CurrentTime); }
+	///
+	/// Without a compositor, see [Display::compositor_active], a window that is minimized or behind
+	/// a fullscreen window can't take the focus by itself, so the window manager is asked to activate it
+	/// with `_NET_ACTIVE_WINDOW` first and focuses it once it is shown.
+	/// Returns [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn focus(&self) -> Result<(), Error> {
+		if !self.display.compositor_active() {
+			self.request_activation();
+		}
+		// Focusing a window that isn't viewable fails with BadMatch.
+		if self.try_get_attr()?.map_state == IsViewable {
+			self.trapped(|| unsafe { XSetInputFocus(self.display.as_raw(), self.window, RevertToParent, CurrentTime) })?;
+		}
+		Ok(())
+	}
+
+	/// Asks the window manager to show, raise and focus the window, as a pager does.
+	fn request_activation(&self) {
+		let atom = match Atom::new(&self.display, NET_ACTIVE_WINDOW) {
+			Ok(atom) if atom.0 != 0 => atom,
+			_ => return,
+		};
+		let mut message = XClientMessageEvent {
+			type_: ClientMessage,
+			serial: 0,
+			send_event: True,
+			display: self.display.as_raw(),
+			window: self.window,
+			message_type: atom.0,
+			format: 32,
+			data: ClientMessageData::new(),
+		};
+		message.data.set_long(0, 2);
+		message.data.set_long(1, CurrentTime as c_long);
+		let root = Window::default_root_window(Arc::clone(&self.display));
+		root.post(XEvent { client_message: message }, SubstructureRedirectMask | SubstructureNotifyMask);
+		unsafe { XFlush(self.display.as_raw()) };
 	}
 
 	/// Send event to window
-	#[inline]
-	pub fn send(&self, mut ev: XEvent, mask: c_long) {
-		unsafe { XSendEvent(self.display.0, self.window, True, mask, (&mut ev) as _); }
+	///
+	/// This waits until the server handled the event, so that [Error::BadWindow] can be returned
+	/// if the window doesn't exist anymore, use [Self::send_batch] to send many events with one round trip.
+	pub fn send(&self, ev: XEvent, mask: c_long) -> Result<(), Error> {
+		self.trapped(|| self.post(ev, mask))
+	}
+
+	/// Sends the event without waiting for the server, the caller traps the errors, see [Self::send].
+	pub(crate) fn post(&self, mut ev: XEvent, mask: c_long) {
+		unsafe { XSendEvent(self.display.as_raw(), self.window, True, mask, (&mut ev) as _); }
 	}
 
 	/// Send key to current window (you have to [Self::focus] before send key)
+	///
+	/// How the event is delivered depends on the [crate::Display::input_backend].
 	/// # Example
 	/// ```donttest
 	/// use x11::keysym::XK_F1;
 	/// use x11_get_windows::event::key_event::KeyType;
 	/// use x11_get_windows::Window;
 	/// let win:Window;
-	/// win.focus();
+	/// win.focus()?;
 	/// win.send_key(KeyType::Press, XK_F1, 0);
 	/// win.send_key(KeyType::Release, XK_F1, 0);
 	/// ```
 	pub fn send_key(&self, typ: KeyType, keycode: c_uint, modifiers: c_uint) {
+		let (code, modifiers) = self.resolve_keysym(keycode, modifiers);
+		self.send_keycode(typ, code, modifiers);
+	}
+
+	/// Like [Self::send_key], with the position, timestamp, child window and `same_screen` of the event
+	/// set by `options`, for toolkits that look at those, see [EventOptions].
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::event::EventOptions;
+	/// let options = EventOptions { position: Some((300, 200)), ..Default::default() };
+	/// win.send_key_with(KeyType::Press, XK_Return, 0, &options);
+	/// win.send_key_with(KeyType::Release, XK_Return, 0, &options);
+	/// ```
+	pub fn send_key_with(&self, typ: KeyType, keysym: c_uint, modifiers: c_uint, options: &EventOptions) {
+		let (code, modifiers) = self.resolve_keysym(keysym, modifiers);
+		self.send_keycode_with(typ, code, modifiers, options);
+	}
+
+	/// Like [Self::send_key], always faked with XTest as if it came from the keyboard, whatever
+	/// the [Display::input_backend] is, so applications that ignore sent events get it as well.
+	///
+	/// XTest input goes to the window with the input focus, the window is focused first if it doesn't have it.
+	/// Keysyms that are not on the current layout are not sent, [Self::tap_key] binds those to a spare key.
+	/// Returns [Error::ExtensionMissing] if the server lacks XTest.
+	/// # Example
+	/// ```donttest
+	/// use x11::keysym::XK_Return;
+	/// win.send_key_xtest(KeyType::Press, XK_Return, 0)?;
+	/// win.send_key_xtest(KeyType::Release, XK_Return, 0)?;
+	/// ```
+	#[cfg(feature = "xtest")]
+	pub fn send_key_xtest(&self, typ: KeyType, keysym: c_uint, modifiers: c_uint) -> Result<(), Error> {
+		let (mut focus, mut revert) = (0, 0);
+		unsafe { XGetInputFocus(self.display.as_raw(), &mut focus, &mut revert) };
+		let frame = self.frame()?.window;
+		let focused = focus > PointerRoot as XWindow
+			&& Window { window: focus, display: Arc::clone(&self.display) }.frame().is_ok_and(|it| it.window == frame);
+		if !focused {
+			self.focus()?;
+			unsafe { XFlush(self.display.as_raw()) };
+		}
+		let (code, modifiers) = self.resolve_keysym(keysym, modifiers);
+		xtest::fake(&self.display, || {
+			if code != 0 {
+				xtest::fake_key(&self.display, code, matches!(typ, KeyType::Press), modifiers);
+			}
+		})
+	}
+
+	/// Finds the key code of the keysym and adds the modifiers its level needs, see [Keymap::lookup].
+	pub(crate) fn resolve_keysym(&self, keysym: c_uint, modifiers: c_uint) -> (KeyCode, c_uint) {
+		match self.display.keymap().lookup(keysym as KeySym) {
+			Some((code, level)) => (code, modifiers | level),
+			None => (unsafe { XKeysymToKeycode(self.display.as_raw(), keysym as _) }, modifiers),
+		}
+	}
+
+	/// Presses and releases the key, see [Self::send_key].
+	///
+	/// `delay` is waited between the press and the release,
+	/// some applications ignore keys that are released in the same instant they are pressed.
+	/// Keysyms that are not on the current layout are temporarily bound to a spare key code, see [Remap].
+	/// # Example
+	/// ```donttest
+	/// use x11::keysym::XK_F1;
+	/// win.focus()?;
+	/// win.tap_key(XK_F1, 0, Duration::from_millis(20));
+	/// ```
+	pub fn tap_key(&self, keysym: c_uint, modifiers: c_uint, delay: Duration) {
+		let keymap = self.display.keymap();
+		let mut remap = Remap::new(Arc::clone(&self.display), &keymap);
+		let (code, modifiers) = match remap.resolve(&keymap, keysym as KeySym) {
+			Some((code, level)) => (code, modifiers | level),
+			None => return,
+		};
+		self.send_keycode(KeyType::Press, code, modifiers);
+		if !delay.is_zero() {
+			unsafe { XFlush(self.display.as_raw()) };
+			thread::sleep(delay);
+		}
+		self.send_keycode(KeyType::Release, code, modifiers);
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+
+	/// Presses the key and keeps it held until the returned guard is dropped.
+	///
+	/// With XTest the server repeats the key like a real one, with XSendEvent
+	/// no repeats happen unless you call [HeldKey::refresh].
+	/// See [crate::Display::set_detectable_auto_repeat] to tell repeats apart from new presses.
+	/// # Example
+	/// ```donttest
+	/// use x11::keysym::XK_w;
+	/// let forward = win.hold_key(XK_w, 0).expect("No key for w.");
+	/// thread::sleep(Duration::from_secs(1));
+	/// drop(forward);
+	/// ```
+	pub fn hold_key(&self, keysym: c_uint, modifiers: c_uint) -> Option<HeldKey<'_>> {
+		let keymap = self.display.keymap();
+		let mut remap = Remap::new(Arc::clone(&self.display), &keymap);
+		let (code, level) = remap.resolve(&keymap, keysym as KeySym)?;
+		Some(HeldKey::new(self, code, modifiers | level, remap))
+	}
+
+	/// Like [Self::send_key], but takes a key code instead of a keysym.
+	pub fn send_keycode(&self, typ: KeyType, code: KeyCode, modifiers: c_uint) {
+		self.send_keycode_with(typ, code, modifiers, &EventOptions::default());
+	}
+
+	/// Like [Self::send_keycode], with the fields of the event set by `options`, see [Self::send_key_with].
+	pub fn send_keycode_with(&self, typ: KeyType, code: KeyCode, modifiers: c_uint, options: &EventOptions) {
 		let mask = typ.mask();
-		let code = unsafe { XKeysymToKeycode(self.display.0, keycode as _) };
-		let ev = XEvent {
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			let fake = || xtest::fake_key(&self.display, code, matches!(typ, KeyType::Press), modifiers);
+			if self.display.neutral_modifiers() { xtest::without_held_modifiers(&self.display, fake) } else { fake() }
+			return self.display.flush_if_auto();
+		}
+		// Events a window that is gone can't get are dropped, as the window would drop them.
+		let _ = self.send(self.key_event(typ, code, modifiers, options), mask);
+	}
+
+	/// Builds the synthetic event [Self::send_keycode_with] sends when XTest is not used.
+	pub(crate) fn key_event(&self, typ: KeyType, code: KeyCode, modifiers: c_uint, options: &EventOptions) -> XEvent {
+		// Without a position the event is at 1, 1 of the window and of the root, as it always was.
+		let (x, y, x_root, y_root) = match options.position {
+			Some((x, y)) => {
+				let (root_x, root_y) = self.root_position().unwrap_or_default();
+				(x, y, root_x + x, root_y + y)
+			}
+			None => (1, 1, 1, 1),
+		};
+		XEvent {
 			key: XKeyEvent {
 				type_: typ.into(),
 				serial: 0,
 				send_event: 1,
-				display: self.display.0,
+				display: self.display.as_raw(),
 				window: self.window,
-				root: Window::default_root_window(Rc::clone(&self.display)).window,
-				subwindow: 0,
-				time: CurrentTime,
-				x: 1,
-				y: 1,
-				x_root: 1,
-				y_root: 1,
+				root: Window::default_root_window(Arc::clone(&self.display)).window,
+				subwindow: options.subwindow.unwrap_or(0),
+				time: options.time.unwrap_or(CurrentTime),
+				x,
+				y,
+				x_root,
+				y_root,
 				state: modifiers,
 				keycode: code as _,
-				same_screen: True,
+				same_screen: options.same_screen as _,
+			}
+		}
+	}
+
+	/// Sends the key that types the character, holding Shift if the layout needs it.
+	///
+	/// Unlike [Self::send_key] this looks at the whole keyboard mapping, see [Keymap].
+	/// Nothing is sent if the character is not on the current layout, [Self::type_text] handles that as well.
+	pub fn send_char(&self, typ: KeyType, c: char) {
+		if let Some((code, modifiers)) = self.display.keymap().resolve_char(c) {
+			self.send_keycode(typ, code, modifiers);
+		}
+	}
+
+	/// Types the text into this window, pressing and releasing a key for every character.
+	///
+	/// Shift is held for characters that need it. Accented characters that are not on the current
+	/// keyboard layout are typed with a dead key or the compose key if there is one, see [compose_sequence].
+	/// Other characters that are not on it are typed by temporarily binding them to an unused key code,
+	/// the keyboard mapping is restored before this returns.
+	/// Newlines press Return, a `\r\n` presses it only once.
+	/// `delay` is waited after every character, some applications drop keys that come in too fast.
+	/// Typing stops early once the [crate::CancelToken] of the display is cancelled.
+	///
+	/// You have to [Self::focus] the window first, like for [Self::send_key].
+	/// # Example
+	/// ```donttest
+	/// win.focus()?;
+	/// win.type_text("Hello, wörld!", Duration::from_millis(10));
+	/// ```
+	pub fn type_text(&self, text: &str, delay: Duration) {
+		let keymap = self.display.keymap();
+		let mut remap = Remap::new(Arc::clone(&self.display), &keymap);
+		let token = self.display.cancel_token();
+		let mut chars = text.chars().peekable();
+		while let Some(c) = chars.next() {
+			if token.is_cancelled() {
+				break;
+			}
+			if c == '\r' && chars.peek() == Some(&'\n') {
+				continue;
+			}
+			let keysym = keysym_from_char(c);
+			let keys = match keymap.lookup(keysym) {
+				Some(key) => vec![key],
+				None => match compose_sequence(&keymap, c).or_else(|| Some(vec![(remap.bind(keysym)?, 0)])) {
+					Some(keys) => keys,
+					None => continue,
+				},
+			};
+			for (code, modifiers) in keys {
+				self.send_keycode(KeyType::Press, code, modifiers);
+				self.send_keycode(KeyType::Release, code, modifiers);
+			}
+			unsafe { XFlush(self.display.as_raw()) };
+			if !delay.is_zero() && token.sleep(delay) {
+				break;
+			}
+		}
+	}
+
+	/// Types the text by pasting it with Ctrl+V, much faster than [Self::type_text] for long text.
+	///
+	/// See [Self::paste_text_with] for how it works.
+	pub fn paste_text(&self, text: &str) -> Result<(), PasteError> {
+		self.paste_text_with(text, &KeyCombo { modifiers: vec![Modifier::Control], key: XK_v as KeySym })
+	}
+
+	/// Types the text by putting it on the clipboard and sending `shortcut`, e.g. Shift+Insert for terminals.
+	///
+	/// The clipboard is owned by an invisible window until this window asked for the text,
+	/// then the previous contents are put back. They are served by a worker thread from then on,
+	/// so they are lost when the process exits before anyone else copies something.
+	/// Fails with [PasteError::NotRequested] if the window didn't ask for the text within a second.
+	/// # Example
+	/// ```donttest
+	/// win.focus()?;
+	/// win.paste_text(&fs::read_to_string("report.txt")?)?;
+	/// ```
+	pub fn paste_text_with(&self, text: &str, shortcut: &KeyCombo) -> Result<(), PasteError> {
+		clipboard::paste_with(&self.display, Selection::Clipboard, text, || self.send_combo(shortcut))
+	}
+
+	/// Inserts the text at `x` and `y`, relative to the window, by making it the primary selection
+	/// and clicking the middle button there, the way selected text is pasted on X11.
+	///
+	/// This leaves the clipboard alone and needs no focus or shortcut, so it also works for terminals.
+	/// The pointer is moved to the position first, as some applications paste where the pointer is.
+	/// The previous primary selection is put back like the clipboard in [Self::paste_text_with].
+	/// Fails with [PasteError::NotRequested] if the window didn't ask for the text within a second,
+	/// e.g. because pasting with the middle button is turned off.
+	/// # Example
+	/// ```donttest
+	/// win.paste_primary_at(120, 40, "ls -la\n")?;
+	/// ```
+	pub fn paste_primary_at(&self, x: c_int, y: c_int, text: &str) -> Result<(), PasteError> {
+		clipboard::paste_with(&self.display, Selection::Primary, text, || self.click_button(Button2, x, y, 1, true))
+	}
+
+	/// Presses a keyboard shortcut, holding its modifiers while the key is tapped.
+	///
+	/// The modifier keys are pressed in order and released in reverse, so applications that
+	/// track the modifier state themselves see the same events as for a real shortcut.
+	/// Shift or AltGr are added when the key is only on a shifted level of the current layout,
+	/// keys that are not on it at all are temporarily bound to a spare key code, see [Remap].
+	/// The keys are sent at once, or with the [Display::combo_gap] between them.
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::keys::KeyCombo;
+	/// win.focus()?;
+	/// win.send_combo(&KeyCombo::parse("Ctrl+Shift+T").unwrap());
+	/// ```
+	pub fn send_combo(&self, combo: &KeyCombo) {
+		self.send_combo_with(combo, self.display.combo_gap());
+	}
+
+	/// Like [Self::send_combo], waiting `gap` after every press and release, for applications
+	/// that mishandle modifiers pressed in the same instant as the key.
+	///
+	/// The keys are still released once the [crate::CancelToken] of the display is cancelled, without the gaps.
+	/// # Example
+	/// ```donttest
+	/// // Ctrl, 30 ms, C, 30 ms, release C, 30 ms, release Ctrl
+	/// win.send_combo_with(&KeyCombo::parse("Ctrl+C").unwrap(), Duration::from_millis(30));
+	/// ```
+	pub fn send_combo_with(&self, combo: &KeyCombo, gap: Duration) {
+		let keymap = self.display.keymap();
+		let mut remap = Remap::new(Arc::clone(&self.display), &keymap);
+		let (code, level) = match remap.resolve(&keymap, combo.key) {
+			Some(key) => key,
+			None => return,
+		};
+		let mut modifiers = combo.modifiers.clone();
+		for modifier in [Modifier::Shift, Modifier::AltGr] {
+			if level & modifier.mask() != 0 && !modifiers.contains(&modifier) {
+				modifiers.push(modifier);
+			}
+		}
+		let modifiers = modifiers
+			.into_iter()
+			.filter_map(|it| Some((keymap.lookup(it.keysym())?.0, it.mask())))
+			.collect::<Vec<_>>();
+
+		// With XTest the server tracks the state of the faked modifier keys itself.
+		let state = |mask: c_uint| match self.display.input_backend() {
+			#[cfg(feature = "xtest")]
+			InputBackend::XTest => 0,
+			_ => mask,
+		};
+		let pause = || {
+			if !gap.is_zero() {
+				unsafe { XFlush(self.display.as_raw()) };
+				self.display.cancel_token().sleep(gap);
 			}
 		};
+		let mut mask = 0;
+		for (modifier, bit) in &modifiers {
+			self.send_keycode(KeyType::Press, *modifier, state(mask));
+			mask |= bit;
+			pause();
+		}
+		self.send_keycode(KeyType::Press, code, state(mask));
+		pause();
+		self.send_keycode(KeyType::Release, code, state(mask));
+		for (modifier, bit) in modifiers.iter().rev() {
+			pause();
+			self.send_keycode(KeyType::Release, *modifier, state(mask));
+			mask &= !bit;
+		}
+		unsafe { XFlush(self.display.as_raw()) };
+	}
 
-		self.send(ev, mask);
+	/// Parses a shortcut like `ctrl+shift+t` with [KeyCombo::parse] and presses it like [Self::send_combo].
+	///
+	/// Returns the part that is not a known modifier or key, nothing is sent then.
+	/// # Example
+	/// ```donttest
+	/// win.focus()?;
+	/// win.send_chord("ctrl+shift+t")?;
+	/// ```
+	pub fn send_chord(&self, chord: &str) -> Result<(), UnknownKey> {
+		self.send_combo(&KeyCombo::parse(chord)?);
+		Ok(())
 	}
 
+	/// Scrolls the wheel by `clicks` steps with the pointer at `x` and `y`, relative to the window.
+	///
+	/// X has no scroll events, every step of the wheel is a press and release of button 4 to 7.
 	/// # Example
 	/// ```donttest
-	/// use x11::keysym::XK_F1;
-	/// use x11_get_windows::event::key_event::KeyType;
+	/// use x11_get_windows::event::btn_event::ScrollDirection;
+	/// win.scroll(ScrollDirection::Down, 3, 100, 100);
+	/// ```
+	pub fn scroll(&self, direction: ScrollDirection, clicks: u32, x: c_int, y: c_int) {
+		let button = direction.button();
+		for _ in 0..clicks {
+			self.send_btn(ButtonType::Press, button, x, y, 0);
+			self.send_btn(ButtonType::Release, button, x, y, 0);
+		}
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+
+	/// Scrolls up by `clicks` steps of the wheel with the pointer at `x` and `y`, see [Self::scroll].
+	pub fn scroll_up(&self, clicks: u32, x: c_int, y: c_int) {
+		self.scroll(ScrollDirection::Up, clicks, x, y);
+	}
+
+	/// Scrolls down by `clicks` steps of the wheel with the pointer at `x` and `y`, see [Self::scroll].
+	pub fn scroll_down(&self, clicks: u32, x: c_int, y: c_int) {
+		self.scroll(ScrollDirection::Down, clicks, x, y);
+	}
+
+	/// Scrolls `dx` steps to the right and `dy` steps down with the pointer at `x` and `y`,
+	/// negative steps scroll left and up.
+	///
+	/// The horizontal steps are sent first, as buttons 6 and 7, which not every application handles.
+	/// # Example
+	/// ```donttest
+	/// // Two steps up and one to the left
+	/// win.scroll_by(-1, -2, 100, 100);
+	/// ```
+	pub fn scroll_by(&self, dx: i32, dy: i32, x: c_int, y: c_int) {
+		let horizontal = if dx < 0 { ScrollDirection::Left } else { ScrollDirection::Right };
+		let vertical = if dy < 0 { ScrollDirection::Up } else { ScrollDirection::Down };
+		self.scroll(horizontal, dx.unsigned_abs(), x, y);
+		self.scroll(vertical, dy.unsigned_abs(), x, y);
+	}
+
+	/// Send mouse button event to current window, `x` and `y` are relative to the window.
+	///
+	/// The button is a logical one, like the buttons applications receive, raw numbers like [Button1] work as well.
+	/// How the event is delivered depends on the [crate::Display::input_backend],
+	/// with XTest the pointer is moved to the position first and the physical button
+	/// that the pointer mapping of the server turns into `button` is faked, so [MouseButton::Left]
+	/// is the primary button on left-handed mice too.
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::event::btn_event::{ButtonType, MouseButton};
 	/// use x11_get_windows::Window;
 	/// let win:Window;
-	/// win.focus();
-	/// win.send_mouse(ButtonType::Press, Button1, 1, 1, 0);
-	/// win.send_mouse(ButtonType::Release, Button1, 1, 1, 0);
+	/// win.focus()?;
+	/// win.send_btn(ButtonType::Press, MouseButton::Left, 1, 1, 0);
+	/// win.send_btn(ButtonType::Release, MouseButton::Left, 1, 1, 0);
 	/// ```
-	pub fn send_btn(&self, typ: ButtonType, button: c_uint, mut x: c_int, mut y: c_int, modifiers: c_uint) {
+	pub fn send_btn(&self, typ: ButtonType, button: impl Into<MouseButton>, x: c_int, y: c_int, modifiers: c_uint) {
+		self.send_btn_at(typ, button.into(), x, y, modifiers, CurrentTime);
+	}
+
+	/// Like [Self::send_btn], with the position in the event, timestamp, child window and `same_screen`
+	/// set by `options`, see [EventOptions]. The XTest backend still clicks at `x` and `y`.
+	pub fn send_btn_with(&self, typ: ButtonType, button: impl Into<MouseButton>, x: c_int, y: c_int, modifiers: c_uint, options: &EventOptions) {
+		let button = button.into();
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			let (root_x, root_y) = self.root_position().unwrap_or_default();
+			xtest::fake_motion(&self.display, root_x + x, root_y + y);
+			let button = button.physical(&self.display);
+			let fake = || xtest::fake_button(&self.display, button, matches!(typ, ButtonType::Press), modifiers);
+			if self.display.neutral_modifiers() { xtest::without_held_modifiers(&self.display, fake) } else { fake() }
+			return self.display.flush_if_auto();
+		}
 		let mask = typ.mask();
-		let attr = self.get_attr();
-		x += attr.x;
-		y += attr.y;
-		let mut ev = XEvent {
+		let (x, y) = options.position.unwrap_or((x, y));
+		// Toolkits with native child windows route the event to the widget in `subwindow`.
+		let subwindow = options.subwindow.unwrap_or_else(|| self.child_at(x, y).ok().flatten().map_or(0, |it| it.window));
+		let (root_x, root_y) = self.root_position().unwrap_or_default();
+		let ev = XEvent {
 			button: XButtonEvent {
 				type_: typ.into(),
 				serial: 0,
 				send_event: 1,
-				display: self.display.0,
+				display: self.display.as_raw(),
 				window: self.window,
-				root: Window::default_root_window(Rc::clone(&self.display)).window,
-				subwindow: 0,
-				time: CurrentTime,
+				root: Window::default_root_window(Arc::clone(&self.display)).window,
+				subwindow,
+				time: options.time.unwrap_or(CurrentTime),
+				x,
+				y,
+				x_root: root_x + x,
+				y_root: root_y + y,
+				state: modifiers,
+				button: button.number(),
+				same_screen: options.same_screen as _,
+			}
+		};
+
+		let _ = self.send(ev, mask);
+	}
+
+	/// Like [Self::send_btn], but with the timestamp of the event.
+	///
+	/// Toolkits only detect double clicks when the clicks have increasing timestamps.
+	fn send_btn_at(&self, typ: ButtonType, button: MouseButton, x: c_int, y: c_int, modifiers: c_uint, time: Time) {
+		self.send_btn_with(typ, button, x, y, modifiers, &EventOptions { time: Some(time), ..Default::default() });
+	}
+
+	/// Clicks the left button at `x` and `y`, relative to the window.
+	pub fn click(&self, x: c_int, y: c_int) {
+		self.click_button(Button1, x, y, 1, false);
+	}
+
+	/// Clicks the left button twice at `x` and `y`, quickly enough to be seen as a double click.
+	pub fn double_click(&self, x: c_int, y: c_int) {
+		self.click_button(Button1, x, y, 2, false);
+	}
+
+	/// Clicks the left button three times at `x` and `y`, which selects a whole line or paragraph in most applications.
+	pub fn triple_click(&self, x: c_int, y: c_int) {
+		self.click_button(Button1, x, y, 3, false);
+	}
+
+	/// Clicks the middle button at `x` and `y`, relative to the window.
+	///
+	/// This pastes the primary selection in most applications, see [Self::paste_primary_at].
+	pub fn middle_click(&self, x: c_int, y: c_int) {
+		self.click_button(Button2, x, y, 1, false);
+	}
+
+	/// Clicks the right button at `x` and `y`, relative to the window.
+	pub fn right_click(&self, x: c_int, y: c_int) {
+		self.click_button(Button3, x, y, 1, false);
+	}
+
+	/// Presses and releases `button` `count` times at `x` and `y`, relative to the window.
+	///
+	/// The events get increasing timestamps, so that toolkits can tell apart single and double clicks.
+	/// With `warp` the pointer is moved to the position first, which some applications need
+	/// to highlight what is under the pointer. With the XTest backend the pointer is always moved.
+	/// # Example
+	/// ```donttest
+	/// use x11::xlib::Button2;
+	/// // Triple click with the middle button
+	/// win.click_button(Button2, 10, 10, 3, true);
+	/// ```
+	pub fn click_button(&self, button: impl Into<MouseButton>, x: c_int, y: c_int, count: u32, warp: bool) {
+		let button = button.into();
+		if warp {
+			// The clicks are dropped as well if the window is gone.
+			let _ = self.warp_pointer(x, y);
+		}
+		for _ in 0..count {
+			self.send_btn_at(ButtonType::Press, button, x, y, 0, self.display.event_time());
+			self.send_btn_at(ButtonType::Release, button, x, y, 0, self.display.event_time());
+		}
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+
+	/// Finds `needle` on the window and clicks its center, returning where it clicked.
+	///
+	/// The window is captured in the [PixelFormat] of the needle, see [Frame::find] for the `tolerance`.
+	/// Returns [None] if the needle is not on the window or the window can't be captured.
+	/// # Example
+	/// ```donttest
+	/// let ok = Frame::from_image(&ok_button.capture()?, PixelFormat::Rgb).unwrap();
+	/// win.click_image(&ok, 16).expect("No OK button.");
+	/// ```
+	pub fn click_image(&self, needle: &Frame, tolerance: u8) -> Option<(c_int, c_int)> {
+		let mut session = CaptureSession::new(self.clone());
+		let frame = session.capture().and_then(|img| Frame::from_image(img, needle.format)).ok()?;
+		let area = frame.find(needle, tolerance)?;
+		let (x, y) = (area.x + area.width as i32 / 2, area.y + area.height as i32 / 2);
+		self.click(x, y);
+		Some((x, y))
+	}
+
+	/// Moves the pointer to `x` and `y`, relative to the window.
+	///
+	/// Returns [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn warp_pointer(&self, x: c_int, y: c_int) -> Result<(), Error> {
+		self.trapped(|| unsafe { XWarpPointer(self.display.as_raw(), 0, self.window, 0, 0, 0, 0, x, y) })?;
+		Ok(())
+	}
+
+	/// Moves the pointer by `dx` and `dy` pixels from where it is, if it is inside this window.
+	///
+	/// Nothing happens when the pointer is elsewhere, so the pointer doesn't jump when the user moved on.
+	/// With the XTest backend this fakes relative motion like [crate::Session::move_pointer_relative],
+	/// otherwise the pointer is warped.
+	pub fn move_pointer_relative(&self, dx: c_int, dy: c_int) {
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			let attr = self.get_attr();
+			let inside = self.pointer_position().ok().flatten().is_some_and(|(x, y)| x >= 0 && y >= 0 && x < attr.width && y < attr.height);
+			if inside {
+				xtest::fake_relative_motion(&self.display, dx, dy);
+				unsafe { XFlush(self.display.as_raw()) };
+			}
+			return;
+		}
+		unsafe {
+			XWarpPointer(self.display.as_raw(), self.window, 0, 0, 0, 0, 0, dx, dy);
+			XFlush(self.display.as_raw());
+		}
+	}
+
+	/// Send a pointer motion event to the position `x` and `y`, relative to the window.
+	///
+	/// `modifiers` is the state of the event, e.g. [x11::xlib::Button1Mask] while a button is held.
+	/// With the XTest backend the pointer is really moved and the state comes from the server.
+	pub fn send_motion(&self, x: c_int, y: c_int, modifiers: c_uint) {
+		let (root_x, root_y) = self.root_position().unwrap_or_default();
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			xtest::fake_motion(&self.display, root_x + x, root_y + y);
+			return self.display.flush_if_auto();
+		}
+		let ev = XEvent {
+			motion: XMotionEvent {
+				type_: MotionNotify,
+				serial: 0,
+				send_event: 1,
+				display: self.display.as_raw(),
+				window: self.window,
+				root: Window::default_root_window(Arc::clone(&self.display)).window,
+				subwindow: self.child_at(x, y).ok().flatten().map_or(0, |it| it.window),
+				time: self.display.event_time(),
 				x,
 				y,
-				x_root: x,
-				y_root: y,
+				x_root: root_x + x,
+				y_root: root_y + y,
 				state: modifiers,
-				button,
+				is_hint: 0,
+				same_screen: True,
+			}
+		};
+		let buttons = Button1MotionMask | Button2MotionMask | Button3MotionMask | Button4MotionMask | Button5MotionMask;
+		let _ = self.send(ev, PointerMotionMask | ButtonMotionMask | buttons);
+	}
+
+	/// Tells the window that the pointer entered or left it at `x` and `y`, relative to the window.
+	///
+	/// Many toolkits only show tooltips and highlight items after the pointer entered the window,
+	/// so send this before [Self::send_motion] when hovering with the XSendEvent backend.
+	/// With XTest this does nothing, the server sends these when the pointer really moves.
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::event::crossing_event::CrossingType;
+	/// win.send_crossing(CrossingType::Enter, 5, 5);
+	/// win.send_motion(20, 5, 0);
+	/// win.send_crossing(CrossingType::Leave, 20, 5);
+	/// ```
+	pub fn send_crossing(&self, typ: CrossingType, x: c_int, y: c_int) {
+		#[cfg(feature = "xtest")]
+		if self.display.input_backend() == InputBackend::XTest {
+			return;
+		}
+		let mask = typ.mask();
+		let (root_x, root_y) = self.root_position().unwrap_or_default();
+		let ev = XEvent {
+			crossing: XCrossingEvent {
+				type_: typ.into(),
+				serial: 0,
+				send_event: 1,
+				display: self.display.as_raw(),
+				window: self.window,
+				root: Window::default_root_window(Arc::clone(&self.display)).window,
+				subwindow: 0,
+				time: self.display.event_time(),
+				x,
+				y,
+				x_root: root_x + x,
+				y_root: root_y + y,
+				mode: NotifyNormal,
+				detail: NotifyNonlinear,
 				same_screen: True,
+				focus: 0,
+				state: 0,
 			}
 		};
+		let _ = self.send(ev, mask);
+	}
+
+	/// Hovers the pointer over `x` and `y`, relative to the window, without moving the real pointer.
+	///
+	/// This sends [Self::send_crossing] and [Self::send_motion], which is enough for most tooltips
+	/// and menus. With XTest the real pointer is moved instead.
+	pub fn hover(&self, x: c_int, y: c_int) {
+		self.send_crossing(CrossingType::Enter, x, y);
+		self.send_motion(x, y, 0);
+		unsafe { XFlush(self.display.as_raw()) };
+	}
+
+	/// Gets the position of the pointer relative to the window.
+	///
+	/// Returns `None` if the pointer is on another screen, and [Error::BadWindow] if the window doesn't exist anymore.
+	pub fn pointer_position(&self) -> Result<Option<(c_int, c_int)>, Error> {
+		let (mut root, mut child, mut root_x, mut root_y, mut x, mut y, mut mask) = (0, 0, 0, 0, 0, 0, 0);
+		let same_screen = self.trapped(|| unsafe {
+			XQueryPointer(self.display.as_raw(), self.window, &mut root, &mut child, &mut root_x, &mut root_y, &mut x, &mut y, &mut mask)
+		})?;
+		Ok(if same_screen == 0 { None } else { Some((x, y)) })
+	}
+
+	/// Moves the pointer to `to`, relative to the window, along a path planned by `profile`.
+	///
+	/// The movement starts where the pointer currently is, or at `to` if that is unknown,
+	/// and sends a motion event for every point, see [Self::send_motion].
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::event::motion::MotionProfile;
+	/// win.move_pointer((120, 40), &MotionProfile::default());
+	/// win.click(120, 40);
+	/// ```
+	pub fn move_pointer(&self, to: (c_int, c_int), profile: &MotionProfile) {
+		let from = self.pointer_position().ok().flatten().unwrap_or(to);
+		for (x, y) in profile.path(from, to) {
+			self.send_motion(x, y, 0);
+			unsafe { XFlush(self.display.as_raw()) };
+			thread::sleep(profile.step);
+		}
+	}
+
+	/// Drags with the left button from `from` to `to`, both relative to the window.
+	///
+	/// The button is pressed at `from`, the pointer moves in a straight line in steps of
+	/// about 10 milliseconds over `duration`, and the button is released at `to`.
+	/// This is enough for drag and drop, sliders and selecting text, see [Self::drag_with] for other buttons.
+	/// # Example
+	/// ```donttest
+	/// win.drag((10, 10), (200, 10), Duration::from_millis(300));
+	/// ```
+	pub fn drag(&self, from: (c_int, c_int), to: (c_int, c_int), duration: Duration) {
+		const STEP: Duration = Duration::from_millis(10);
+		let steps = (duration.as_millis() / STEP.as_millis()).max(1) as u32;
+		self.drag_with(from, to, MouseButton::Left, steps, duration / steps);
+	}
+
+	/// Drags with `button` from `from` to `to`, both relative to the window, in `steps` motion events `delay` apart.
+	///
+	/// The button is pressed at `from`, the positions in between are interpolated on a straight line,
+	/// and the button is released at `to`. With XSendEvent the motion events carry the state of the held button,
+	/// with XTest the pointer really moves. Toolkits often only start a drag after a few pixels of motion
+	/// and some time, so use more than one step for drag and drop.
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::event::btn_event::MouseButton;
+	/// // Pan a map with the middle button
+	/// win.drag_with((400, 300), (250, 300), MouseButton::Middle, 20, Duration::from_millis(15));
+	/// ```
+	pub fn drag_with(&self, from: (c_int, c_int), to: (c_int, c_int), button: impl Into<MouseButton>, steps: u32, delay: Duration) {
+		let steps = steps.max(1) as i32;
+		let points = (0..=steps)
+			.map(|step| (from.0 + (to.0 - from.0) * step / steps, from.1 + (to.1 - from.1) * step / steps))
+			.collect::<Vec<_>>();
+		self.drag_path(&points, button, delay);
+	}
+
+	/// Drags with `button` along `points`, relative to the window, with `delay` between the motion events.
+	///
+	/// Like [Self::drag_with] for paths that aren't straight: the button is pressed at the first point,
+	/// held while moving through the others, and released at the last one. Nothing is sent without points.
+	/// See [Self::send_touch] for touches.
+	/// # Example
+	/// ```donttest
+	/// use x11_get_windows::event::btn_event::MouseButton;
+	/// // Draw a triangle in a paint program
+	/// win.drag_path(&[(100, 300), (200, 100), (300, 300), (100, 300)], MouseButton::Left, Duration::from_millis(16));
+	/// ```
+	pub fn drag_path(&self, points: &[(c_int, c_int)], button: impl Into<MouseButton>, delay: Duration) {
+		let (first, last) = match (points.first(), points.last()) {
+			(Some(first), Some(last)) => (*first, *last),
+			_ => return,
+		};
+		let button = button.into();
+		self.send_motion(first.0, first.1, 0);
+		self.send_btn_at(ButtonType::Press, button, first.0, first.1, 0, self.display.event_time());
+		for (x, y) in &points[1..] {
+			unsafe { XFlush(self.display.as_raw()) };
+			thread::sleep(delay);
+			self.send_motion(*x, *y, button.mask());
+		}
+		self.send_btn_at(ButtonType::Release, button, last.0, last.1, button.mask(), self.display.event_time());
+		unsafe { XFlush(self.display.as_raw()) };
+	}
 
-		self.send(ev, mask);
+	/// Touches the window with one finger along `points`, relative to the window, `delay` apart.
+	///
+	/// A client can't make XInput 2 touch events: XTest has no requests for touch devices and the server
+	/// refuses generic events in XSendEvent, it only creates them for real touch screens. This sends what
+	/// the server turns the first touch into for clients that don't select touch events instead: the pointer
+	/// moves to the first point, the left button is pressed, held while moving through the others, and released
+	/// at the last point, see [Self::drag_path]. Applications see a left button drag: the ones that handle a touch
+	/// like the pointer react the same, the ones that only handle touch events, like gestures, don't. Taps are a single point.
+	/// # Example
+	/// ```donttest
+	/// // Swipe to the left
+	/// win.send_touch(&[(400, 300), (300, 300), (200, 300), (100, 300)], Duration::from_millis(16));
+	/// // Tap
+	/// win.send_touch(&[(50, 50)], Duration::ZERO);
+	/// ```
+	pub fn send_touch(&self, points: &[(c_int, c_int)], delay: Duration) {
+		self.drag_path(points, MouseButton::Left, delay);
 	}
 }
 
+/// The size of the decorations around a window, see [Window::frame_extents].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameExtents {
+	/// Width of the left border.
+	pub left: u32,
+	/// Width of the right border.
+	pub right: u32,
+	/// Height of the title bar, or the top border.
+	pub top: u32,
+	/// Height of the bottom border.
+	pub bottom: u32,
+}
+
 #[derive(Debug)]
 pub struct WindowTitle<'a>(&'a CStr);
 
@@ -292,7 +1692,7 @@ impl<'a> Drop for WindowTitle<'a> {
 ///
 /// XFree is handled by dropping this struct
 pub struct XImg {
-	img: *mut XImage,
+	pub(crate) img: *mut XImage,
 }
 
 /// This struct represent pixel value from XImage
@@ -347,8 +1747,25 @@ impl XImg {
 	/// Get raw image pointer
 	#[inline]
 	pub fn as_ptr(&self) -> *mut XImage { self.img }
+
+	/// Convert this image into `buf` using the given [PixelFormat], rows are tightly packed.
+	pub fn convert_into(&self, buf: &mut [u8], format: PixelFormat) -> Result<(), CaptureError> {
+		unsafe { convert_into(self.img, buf, format) }
+	}
+
+	/// Like [Self::convert_into], but reads the alpha channel from the bits in `alpha_mask`.
+	///
+	/// The colours are written un-premultiplied, an `alpha_mask` of 0 makes every pixel opaque.
+	pub fn convert_into_with_alpha(&self, buf: &mut [u8], format: PixelFormat, alpha_mask: c_ulong) -> Result<(), CaptureError> {
+		unsafe { convert_into_with_alpha(self.img, buf, format, alpha_mask) }
+	}
 }
 
+// An XImage doesn't point to its connection, its pixels and the functions that read them and
+// XDestroyImage only touch memory of the client. Moving an owned image to another thread needs
+// no lock, images of MIT-SHM captures are only lent out by CaptureSession and never owned.
+unsafe impl Send for XImg {}
+
 impl AsRef<XImage> for XImg {
 	#[inline]
 	fn as_ref(&self) -> &XImage { unsafe { self.img.as_ref() }.unwrap() }
@@ -363,6 +1780,81 @@ impl AsMut<XImage> for XImg {
 
 impl Drop for XImg {
 	fn drop(&mut self) {
-		unsafe { XFree(self.img as _); }
+		unsafe { XDestroyImage(self.img); }
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+impl XImg {
+	/// An image in the usual 24 bit layout without a connection, `pixel` gives the red, green and blue of every pixel.
+	pub(crate) fn from_fn(width: u32, height: u32, pixel: impl Fn(u32, u32) -> (u8, u8, u8)) -> XImg {
+		unsafe {
+			// XDestroyImage frees the image and its pixels with free.
+			let data = libc::malloc((width * height * 4).max(1) as usize) as *mut u8;
+			for (i, out) in slice::from_raw_parts_mut(data, (width * height * 4) as usize).chunks_exact_mut(4).enumerate() {
+				let (r, g, b) = pixel(i as u32 % width, i as u32 / width);
+				out.copy_from_slice(&[b, g, r, 0]);
+			}
+			let img = libc::calloc(1, std::mem::size_of::<XImage>()) as *mut XImage;
+			*img = XImage {
+				width: width as c_int,
+				height: height as c_int,
+				format: xlib::ZPixmap,
+				data: data as _,
+				byte_order: xlib::LSBFirst,
+				bitmap_unit: 32,
+				bitmap_bit_order: xlib::LSBFirst,
+				bitmap_pad: 32,
+				depth: 24,
+				bytes_per_line: (width * 4) as c_int,
+				bits_per_pixel: 32,
+				red_mask: 0xff0000,
+				green_mask: 0xff00,
+				blue_mask: 0xff,
+				..(*img)
+			};
+			xlib::XInitImage(img);
+			XImg { img }
+		}
+	}
+}
+
+/// Attributes with every field zero, as Xlib leaves them when the window doesn't exist.
+fn empty_attributes() -> XWindowAttributes {
+	XWindowAttributes {
+		x: 0,
+		y: 0,
+		width: 0,
+		height: 0,
+		border_width: 0,
+		depth: 0,
+		visual: null_mut(),
+		root: 0,
+		class: 0,
+		bit_gravity: 0,
+		win_gravity: 0,
+		backing_store: 0,
+		backing_planes: 0,
+		backing_pixel: 0,
+		save_under: 0,
+		colormap: 0,
+		map_installed: 0,
+		map_state: 0,
+		all_event_masks: 0,
+		your_event_mask: 0,
+		do_not_propagate_mask: 0,
+		override_redirect: 0,
+		screen: null_mut(),
+	}
+}
+
+/// The states [Window::fill_region] takes off the window before moving it.
+const FILL_REGION_BLOCKING_STATES: &[&str] = &[
+	"_NET_WM_STATE_MAXIMIZED_VERT",
+	"_NET_WM_STATE_MAXIMIZED_HORZ",
+	"_NET_WM_STATE_FULLSCREEN",
+	"_NET_WM_STATE_SHADED",
+];
+
+/// The action of a `_NET_WM_STATE` message that removes the states.
+const NET_WM_STATE_REMOVE: c_long = 0;
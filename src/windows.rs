@@ -1,11 +1,39 @@
+use std::os::raw::c_uint;
 use std::slice::Iter;
+use std::time::Duration;
+use x11::xlib::XFlush;
+use crate::keys::KeyCombo;
 use crate::Window;
 
+/// How [Windows::broadcast_key] and [Windows::broadcast_combo] deliver the input to every window.
+#[derive(Copy, Clone, Debug)]
+pub struct BroadcastOptions {
+    /// Focus every window before sending to it, for applications that ignore keys while they are not focused.
+    ///
+    /// The window that had the focus before doesn't get it back, wrap the broadcast in
+    /// [crate::Session::with_focus] for that.
+    pub focus: bool,
+    /// How long to wait after focusing a window, so the window manager has moved the focus before the keys arrive.
+    pub focus_delay: Duration,
+    /// How long the key of [Windows::broadcast_key] is held, see [Window::tap_key].
+    pub key_delay: Duration,
+}
+
+impl Default for BroadcastOptions {
+    fn default() -> Self {
+        Self {
+            focus: false,
+            focus_delay: Duration::from_millis(50),
+            key_delay: Duration::ZERO,
+        }
+    }
+}
+
 /// A Collection of Windows.
 pub struct Windows(pub(crate) Vec<Window>);
 impl Windows {
     /// Exposes the iter function of the Vec so that you can use iterator methods on it.
-    pub fn iter(&self) -> Iter<Window> {
+    pub fn iter(&self) -> Iter<'_, Window> {
         self.0.iter()
     }
     /// Destroys the wrapper to give the inner vec.
@@ -20,4 +48,41 @@ impl Windows {
     pub fn as_vec_mut(&mut self) -> &mut Vec<Window> {
         &mut self.0
     }
+    /// Keeps only the windows `predicate` returns true for, e.g. the windows of one application.
+    /// 
+    /// # Example
+    /// ```ignore
+    /// session.get_windows()?
+    ///     .filter(|it| it.class().ok().flatten().is_some_and(|(_, class)| class == "firefox"))
+    ///     .broadcast_key(XK_F5, 0, BroadcastOptions::default());
+    /// ```
+    pub fn filter(mut self, predicate: impl FnMut(&Window) -> bool) -> Self {
+        self.0.retain(predicate);
+        self
+    }
+    /// Taps the same key in every window, see [Window::tap_key].
+    pub fn broadcast_key(&self, keysym: c_uint, modifiers: c_uint, options: BroadcastOptions) {
+        self.broadcast(options, |window| window.tap_key(keysym, modifiers, options.key_delay));
+    }
+    /// Presses the same shortcut in every window, see [Window::send_combo].
+    pub fn broadcast_combo(&self, combo: &KeyCombo, options: BroadcastOptions) {
+        self.broadcast(options, |window| window.send_combo(combo));
+    }
+    fn broadcast(&self, options: BroadcastOptions, mut send: impl FnMut(&Window)) {
+        for window in &self.0 {
+            // Stops at the next window once the display is cancelled.
+            if options.focus {
+                // Windows that are gone get no focus and ignore what is sent to them.
+                let _ = window.focus();
+                unsafe { XFlush(window.display.as_raw()) };
+                if window.display.cancel_token().sleep(options.focus_delay) {
+                    return;
+                }
+            } else if window.display.cancel_token().is_cancelled() {
+                return;
+            }
+            send(window);
+            unsafe { XFlush(window.display.as_raw()) };
+        }
+    }
 }